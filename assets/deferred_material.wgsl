@@ -0,0 +1,205 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+// Must match DeferredExtension
+const LAYER1_ENABLED: u32 = 1u;
+const LAYER1_ALPHA_MASK: u32 = 2u;
+const VERTEX_COLOR_BLEND: u32 = 4u;
+const LAYER0_DIFFUSE_TEXTURE: u32 = 8u;
+const LAYER1_DIFFUSE_TEXTURE: u32 = 16u;
+const LAYER0_NORMAL_TEXTURE: u32 = 32u;
+const LAYER1_NORMAL_TEXTURE: u32 = 64u;
+const LAYER0_MATERIAL_TEXTURE: u32 = 128u;
+const LAYER1_MATERIAL_TEXTURE: u32 = 256u;
+const REFLECTION_MAP: u32 = 512u;
+const MATERIAL_TEXTURE_REFLECTIVENESS: u32 = 1024u;
+const LAYER0_DIFFUSE_ALPHA_DISABLED: u32 = 2048u;
+
+struct DeferredLayers {
+    layer0_color: vec4<f32>,
+    layer1_color: vec4<f32>,
+    layer0_emissive: f32,
+    layer1_emissive: f32,
+    layer0_spec_amount: f32,
+    layer1_spec_amount: f32,
+    layer0_spec_power: f32,
+    layer1_spec_power: f32,
+    layer0_reflectiveness: f32,
+    layer1_reflectiveness: f32,
+    layer0_normal_power: f32,
+    layer1_normal_power: f32,
+    sharpness: f32,
+    flags: u32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> layers: DeferredLayers;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var layer0_diffuse: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var layer0_diffuse_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(103) var layer1_diffuse: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(104) var layer1_diffuse_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(105) var layer0_normal: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(106) var layer0_normal_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(107) var layer1_normal: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(108) var layer1_normal_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(109) var layer0_material: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(110) var layer0_material_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(111) var layer1_material: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(112) var layer1_material_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(113) var reflection_map: texture_cube<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(114) var reflection_map_sampler: sampler;
+
+fn has_flag(flag: u32) -> bool {
+    return (layers.flags & flag) != 0u;
+}
+
+// Unpacks a tangent space normal, scaled by the layer's normal power
+fn tangent_normal(sample: vec4<f32>, power: f32) -> vec3<f32> {
+    // Magicka's normal maps are Y down
+    let n = vec3(sample.r * 2.0 - 1.0, 1.0 - sample.g * 2.0, sample.b * 2.0 - 1.0);
+    return normalize(vec3(n.xy * power, n.z));
+}
+
+// Must match specular_to_pbr in effect.rs, returns (reflectance, perceptual roughness)
+fn specular_to_pbr(amount: f32, power: f32) -> vec2<f32> {
+    let reflectance = clamp(amount * 0.5, 0.0, 1.0);
+    let perceptual_roughness = clamp(sqrt(2.0 / (max(power, 0.0) + 2.0)), 0.089, 1.0);
+    return vec2(reflectance, perceptual_roughness);
+}
+
+// Specular amount, specular power and reflectiveness of a layer, scaled by its material texture
+fn layer_material(amount: f32, power: f32, reflectiveness: f32, sample: vec4<f32>, has_texture: bool) -> vec3<f32> {
+    if !has_texture {
+        return vec3(amount, power, reflectiveness);
+    }
+    var material = vec3(amount * sample.r, power * sample.g, reflectiveness);
+    if has_flag(MATERIAL_TEXTURE_REFLECTIVENESS) {
+        material.z *= sample.b;
+    }
+    return material;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+#ifdef VERTEX_UVS_A
+    let uv = in.uv;
+#else
+    let uv = vec2(0.0);
+#endif
+
+    var color0 = layers.layer0_color;
+    if has_flag(LAYER0_DIFFUSE_TEXTURE) {
+        let sample = textureSample(layer0_diffuse, layer0_diffuse_sampler, uv);
+        if has_flag(LAYER0_DIFFUSE_ALPHA_DISABLED) {
+            color0 *= vec4(sample.rgb, 1.0);
+        } else {
+            color0 *= sample;
+        }
+    }
+    var color1 = layers.layer1_color;
+    var layer1_alpha = 1.0;
+    if has_flag(LAYER1_DIFFUSE_TEXTURE) {
+        let sample = textureSample(layer1_diffuse, layer1_diffuse_sampler, uv);
+        color1 *= vec4(sample.rgb, 1.0);
+        layer1_alpha = sample.a;
+    }
+
+    // How much of layer 1 covers layer 0
+    var blend = 0.0;
+    if has_flag(LAYER1_ENABLED) {
+        blend = 1.0;
+        if has_flag(LAYER1_ALPHA_MASK) {
+            blend *= layer1_alpha;
+        }
+#ifdef VERTEX_COLORS
+        if has_flag(VERTEX_COLOR_BLEND) {
+            blend *= in.color.a;
+        }
+#endif
+    }
+
+    let diffuse = mix(color0.rgb, color1.rgb, blend);
+    let emissive = mix(layers.layer0_emissive, layers.layer1_emissive, blend);
+    // Emissive layers glow in their own color rather than being lit
+    pbr_input.material.base_color = vec4(diffuse * max(1.0 - emissive, 0.0), color0.a);
+    pbr_input.material.emissive = vec4(diffuse * emissive, 1.0);
+
+    let material0 = layer_material(
+        layers.layer0_spec_amount,
+        layers.layer0_spec_power,
+        layers.layer0_reflectiveness,
+        textureSample(layer0_material, layer0_material_sampler, uv),
+        has_flag(LAYER0_MATERIAL_TEXTURE),
+    );
+    let material1 = layer_material(
+        layers.layer1_spec_amount,
+        layers.layer1_spec_power,
+        layers.layer1_reflectiveness,
+        textureSample(layer1_material, layer1_material_sampler, uv),
+        has_flag(LAYER1_MATERIAL_TEXTURE),
+    );
+    let material = mix(material0, material1, blend);
+    let pbr = specular_to_pbr(material.x, material.y);
+    pbr_input.material.reflectance = vec3(pbr.x);
+    pbr_input.material.perceptual_roughness = pbr.y;
+
+#ifdef VERTEX_TANGENTS
+    if has_flag(LAYER0_NORMAL_TEXTURE) || has_flag(LAYER1_NORMAL_TEXTURE) {
+        var n0 = vec3(0.0, 0.0, 1.0);
+        if has_flag(LAYER0_NORMAL_TEXTURE) {
+            n0 = tangent_normal(textureSample(layer0_normal, layer0_normal_sampler, uv), layers.layer0_normal_power);
+        }
+        var n1 = vec3(0.0, 0.0, 1.0);
+        if has_flag(LAYER1_NORMAL_TEXTURE) {
+            n1 = tangent_normal(textureSample(layer1_normal, layer1_normal_sampler, uv), layers.layer1_normal_power);
+        }
+        let nt = normalize(mix(n0, n1, blend));
+
+        let N = pbr_input.world_normal;
+        let T = normalize(in.world_tangent.xyz - N * dot(in.world_tangent.xyz, N));
+        let B = cross(N, T) * in.world_tangent.w;
+        pbr_input.N = normalize(T * nt.x + B * nt.y + N * nt.z);
+    }
+#endif
+
+    if has_flag(REFLECTION_MAP) {
+        let reflectiveness = clamp(material.z, 0.0, 1.0);
+        let direction = reflect(-pbr_input.V, pbr_input.N);
+        let max_level = f32(textureNumLevels(reflection_map) - 1u);
+        let level = (1.0 - clamp(layers.sharpness, 0.0, 1.0)) * max_level;
+        let reflection = textureSampleLevel(reflection_map, reflection_map_sampler, direction, level).rgb;
+        // Reflections replace the lit diffuse color rather than adding to it
+        pbr_input.material.base_color = vec4(pbr_input.material.base_color.rgb * (1.0 - reflectiveness), pbr_input.material.base_color.a);
+        pbr_input.material.emissive = vec4(pbr_input.material.emissive.rgb + reflection * reflectiveness, 1.0);
+    }
+
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+
+    return out;
+}
@@ -0,0 +1,74 @@
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::{globals, view, view_transmission_texture, view_transmission_sampler},
+}
+
+const MAX_RIPPLES: u32 = 8u;
+// Distortion parameters are small offsets, scale them to a fraction of the screen
+const DISTORTION_SCALE: f32 = 0.02;
+
+struct ForceFieldMaterial {
+    color: vec4<f32>,
+    width: f32,
+    alpha_power: f32,
+    alpha_falloff_power: f32,
+    max_radius: f32,
+    ripple_distortion: f32,
+    map_distortion: f32,
+    ripple_count: u32,
+    // xyz: impact point in world space, w: age from 0 to 1
+    ripples: array<vec4<f32>, MAX_RIPPLES>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0)
+var<uniform> material: ForceFieldMaterial;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1)
+var displacement_map: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2)
+var displacement_sampler: sampler;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    let normal = normalize(select(-in.world_normal, in.world_normal, is_front));
+    let view_dir = normalize(view.world_position.xyz - in.world_position.xyz);
+
+    // Glow towards the silhouette
+    let rim = 1.0 - abs(dot(normal, view_dir));
+    var alpha = pow(rim, material.alpha_falloff_power) * material.alpha_power;
+
+    // Rings expanding out from each impact, fading as they grow
+    var ripple = 0.0;
+    for (var i = 0u; i < min(material.ripple_count, MAX_RIPPLES); i++) {
+        let impact = material.ripples[i];
+        let radius = impact.w * material.max_radius;
+        let distance = length(in.world_position.xyz - impact.xyz);
+        let ring = 1.0 - saturate(abs(distance - radius) / max(material.width, 0.001));
+        ripple += ring * (1.0 - impact.w);
+    }
+    alpha += ripple;
+
+    var displacement = vec2(0.0);
+#ifdef VERTEX_UVS_A
+    let scroll = vec2(0.1, 0.07) * globals.time;
+    displacement = textureSample(displacement_map, displacement_sampler, in.uv + scroll).xy * 2.0 - 1.0;
+#endif
+    let offset = displacement * material.map_distortion + normal.xy * ripple * material.ripple_distortion;
+    let screen_uv = (in.position.xy - view.viewport.xy) / view.viewport.zw;
+    let background = textureSample(
+        view_transmission_texture,
+        view_transmission_sampler,
+        screen_uv + offset * DISTORTION_SCALE,
+    ).rgb;
+
+    var color = material.color.rgb;
+#ifdef VERTEX_COLORS
+    color *= in.color.rgb;
+    alpha *= in.color.a;
+#endif
+
+    // The distorted background is drawn too, so the surface itself is opaque
+    return vec4(mix(background, color, saturate(alpha)), 1.0);
+}
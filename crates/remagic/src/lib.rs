@@ -1,7 +1,7 @@
 pub mod xnb;
 
 pub mod xnb_readers {
-    mod magicka_common;
+    pub mod magicka_common;
 
     pub mod magicka_character;
     pub mod magicka_content;
@@ -10,13 +10,14 @@ pub mod xnb_readers {
     pub mod magicka_mesh;
 
     pub mod skinning;
+    pub mod xna_effect;
     pub mod xna_mesh;
     pub mod xna_tex;
 }
 
 use crate::{
     xnb::{XnbAsset, object},
-    xnb_readers::xna_tex::Texture2d,
+    xnb_readers::xna_tex::{Texture, Texture2d},
 };
 
 pub use crate::xnb::SharedResources;
@@ -44,6 +45,16 @@ pub fn parse_texture_2d<'i>(
     xnb::parse(bytes, object(xnb_readers::xna_tex::texture_2d))
 }
 
+/// Parses either a [`Texture2d`] or a [`TextureCube`](xnb_readers::xna_tex::TextureCube).
+pub fn parse_texture<'i>(
+    bytes: &'i [u8],
+) -> Result<
+    XnbAsset<Option<Texture>>,
+    winnow::error::ParseError<xnb::Stream<'i>, winnow::error::ContextError>,
+> {
+    xnb::parse(bytes, xnb_readers::xna_tex::texture)
+}
+
 pub fn parse_character<'i>(
     bytes: &'i [u8],
 ) -> Result<
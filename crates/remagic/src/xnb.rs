@@ -23,9 +23,7 @@ pub(crate) fn parse<'i, O, P: for<'d> Parser<Stream<'d>, O, ContextError>>(
     let stream = Stream {
         // input: Bytes::new(bytes),
         input: LocatingSlice::new(Bytes::new(bytes)),
-        state: State {
-            type_readers: Vec::new(),
-        },
+        state: State::default(),
     };
     parse_xnb(primary).parse(stream)
 }
@@ -48,23 +46,47 @@ pub(crate) fn parse<'i, O, P: for<'d> Parser<Stream<'d>, O, ContextError>>(
 struct Header {
     // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentManager.cs#L37
     platform: u8,
-
-    // version: u8,
+    version: u8,
     flags: u8,
     /// This should match the size of the whole file, including the header
     _file_size: u32,
 }
 
-const HEADER_FLAG_COMPRESSED_LZ4: u8 = 1 << 7;
+const HEADER_FLAG_HIDEF: u8 = 1 << 0;
+const HEADER_FLAG_COMPRESSED_LZ4: u8 = 1 << 6;
 const HEADER_FLAG_COMPRESSED_LZX: u8 = 1 << 7;
 
+/// The XNB format version from the file header.
+///
+/// Built-in XNA types such as textures and vertex buffers changed layout between versions,
+/// so their readers check this in [`State`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FormatVersion {
+    /// XNA Game Studio 3.1 (format version 4), which is what Magicka ships with.
+    #[default]
+    Xna31,
+    /// XNA Game Studio 4.0 (format version 5), also produced by MonoGame pipelines.
+    Xna40,
+}
+
+impl FormatVersion {
+    fn from_header(version: u8) -> Option<Self> {
+        match version {
+            4 => Some(Self::Xna31),
+            5 => Some(Self::Xna40),
+            _ => None,
+        }
+    }
+}
+
 // pub type Stream<'i> = &'i Bytes;
 // pub type Stream<'i> = Stateful<&'i Bytes, State<'i>>;
 pub type Stream<'i> = Stateful<LocatingSlice<&'i Bytes>, State<'i>>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct State<'i> {
     pub(super) type_readers: Vec<TypeReaderEntry<'i>>,
+    pub(super) version: FormatVersion,
 }
 
 pub(super) struct TypeReaderEntry<'i> {
@@ -108,9 +130,20 @@ pub trait SharedResources {
 pub struct XnbAsset<T> {
     primary: T,
     shared_resources: Vec<Option<AnyObject>>,
+    version: FormatVersion,
+    hidef: bool,
 }
 
 impl<T> XnbAsset<T> {
+    pub fn format_version(&self) -> FormatVersion {
+        self.version
+    }
+
+    /// Whether the content targets the XNA 4.0 HiDef graphics profile.
+    pub fn hidef(&self) -> bool {
+        self.hidef
+    }
+
     pub fn inner(&self) -> &T {
         &self.primary
     }
@@ -188,22 +221,37 @@ where
         let header = seq!(Header {
             _: b"XNB",
             platform: u8,
-            _: 4, // Version
+            version: u8,
             flags: u8,
             _file_size: le_u32,
         })
         .parse_next(input)?;
+        let Some(version) = FormatVersion::from_header(header.version) else {
+            return Err(ContextError::from_external_error(
+                input,
+                UnsupportedVersionError {
+                    version: header.version,
+                },
+            ));
+        };
         if header.platform != 119 {
             eprintln!("unhandled .xnb platform: {}", header.platform);
         }
-        if (header.flags & !HEADER_FLAG_COMPRESSED_LZX) != 0 {
+        let known_flags = match version {
+            FormatVersion::Xna31 => HEADER_FLAG_COMPRESSED_LZX,
+            FormatVersion::Xna40 => {
+                HEADER_FLAG_HIDEF | HEADER_FLAG_COMPRESSED_LZ4 | HEADER_FLAG_COMPRESSED_LZX
+            }
+        };
+        if (header.flags & !known_flags) != 0 {
             eprintln!("unhandled .xnb flags: {}", header.flags);
         }
+        let hidef = version == FormatVersion::Xna40 && header.flags & HEADER_FLAG_HIDEF != 0;
 
         let data = if header.flags & HEADER_FLAG_COMPRESSED_LZX != 0 {
             compressed_lxz.parse_next(input)?
         } else if header.flags & HEADER_FLAG_COMPRESSED_LZ4 != 0 {
-            todo!("not lzx")
+            compressed_lz4.parse_next(input)?
         } else {
             // XXX: Is this right?
             input.finish().to_vec().into_boxed_slice()
@@ -213,6 +261,7 @@ where
             input: LocatingSlice::new(Bytes::new(&data)),
             state: State {
                 type_readers: vec![],
+                version,
             },
         };
         let input = &mut input;
@@ -262,11 +311,28 @@ where
             Ok(XnbAsset {
                 primary: primary_value,
                 shared_resources,
+                version,
+                hidef,
             })
         }
     }
 }
 
+#[derive(Debug)]
+struct UnsupportedVersionError {
+    version: u8,
+}
+impl std::error::Error for UnsupportedVersionError {}
+impl std::fmt::Display for UnsupportedVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unsupported .xnb format version {}, expected 4 (XNA 3.1) or 5 (XNA 4.0)",
+            self.version
+        )
+    }
+}
+
 fn resolve_type_reader(info: &TypeReaderInfo) -> Option<AnyReader> {
     match *info {
         TypeReaderInfo {
@@ -319,6 +385,88 @@ fn resolve_type_reader(info: &TypeReaderInfo) -> Option<AnyReader> {
                 ))
             },
         )),
+        // XNA 4.0 models store their buffers as shared resources
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_mesh::VertexBuffer::NAME,
+            version: crate::xnb_readers::xna_mesh::VertexBuffer::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_mesh::vertex_buffer,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_mesh::IndexBuffer::NAME,
+            version: crate::xnb_readers::xna_mesh::IndexBuffer::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_mesh::index_buffer,
+                ))
+            },
+        )),
+        // And their effects
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::CompiledEffect::NAME,
+            version: crate::xnb_readers::xna_effect::CompiledEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::compiled_effect,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::BasicEffect::NAME,
+            version: crate::xnb_readers::xna_effect::BasicEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::basic_effect,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::AlphaTestEffect::NAME,
+            version: crate::xnb_readers::xna_effect::AlphaTestEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::alpha_test_effect,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::DualTextureEffect::NAME,
+            version: crate::xnb_readers::xna_effect::DualTextureEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::dual_texture_effect,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::EnvironmentMapEffect::NAME,
+            version: crate::xnb_readers::xna_effect::EnvironmentMapEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::environment_map_effect,
+                ))
+            },
+        )),
+        TypeReaderInfo {
+            name: crate::xnb_readers::xna_effect::SkinnedEffect::NAME,
+            version: crate::xnb_readers::xna_effect::SkinnedEffect::VERSION,
+        } => Some(Box::new(
+            || -> Box<dyn for<'g> TypeReaderParser<'g, AnyObject>> {
+                Box::new(BoxingParser::new(
+                    crate::xnb_readers::xna_effect::skinned_effect,
+                ))
+            },
+        )),
         /* TypeReaderInfo {
             name: types::AnyExternalReference::NAME,
             version: types::AnyExternalReference::VERSION,
@@ -625,6 +773,84 @@ fn compressed_lxz(input: &mut Stream) -> winnow::Result<Box<[u8]>> {
     Ok(decompressed)
 }
 
+// https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentManager.cs#L400
+/// MonoGame's LZ4 compression is the decompressed size followed by a single LZ4 block.
+fn compressed_lz4(input: &mut Stream) -> winnow::Result<Box<[u8]>> {
+    let decompressed_data_size = le_u32.parse_next(input)? as usize;
+    let block: &[u8] = rest.parse_next(input)?;
+    decompress_lz4_block(block, decompressed_data_size)
+        .map(Vec::into_boxed_slice)
+        .map_err(|e| ContextError::from_external_error(input, e))
+}
+
+#[derive(Debug)]
+struct InvalidLz4Error;
+impl std::error::Error for InvalidLz4Error {}
+impl std::fmt::Display for InvalidLz4Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("invalid LZ4 compressed data")
+    }
+}
+
+// https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md
+fn decompress_lz4_block(mut block: &[u8], size: usize) -> Result<Vec<u8>, InvalidLz4Error> {
+    fn length(block: &mut &[u8], nibble: u8) -> Result<usize, InvalidLz4Error> {
+        let mut length = usize::from(nibble);
+        if nibble == 15 {
+            loop {
+                let (&byte, rest) = block.split_first().ok_or(InvalidLz4Error)?;
+                *block = rest;
+                length = length.checked_add(byte.into()).ok_or(InvalidLz4Error)?;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(length)
+    }
+
+    // Don't trust the size for the allocation, it could be huge
+    let mut decompressed = Vec::with_capacity(size.min(block.len().saturating_mul(255)));
+    loop {
+        let (&token, rest) = block.split_first().ok_or(InvalidLz4Error)?;
+        block = rest;
+        let literals_length = length(&mut block, token >> 4)?;
+        let (literals, rest) = block
+            .split_at_checked(literals_length)
+            .ok_or(InvalidLz4Error)?;
+        block = rest;
+        if decompressed.len() + literals.len() > size {
+            return Err(InvalidLz4Error);
+        }
+        decompressed.extend_from_slice(literals);
+        // The last sequence only has literals
+        if block.is_empty() {
+            break;
+        }
+
+        let (offset, rest) = block.split_first_chunk::<2>().ok_or(InvalidLz4Error)?;
+        block = rest;
+        let offset = usize::from(u16::from_le_bytes(*offset));
+        let match_length = length(&mut block, token & 0xF)? + 4;
+        let start = decompressed
+            .len()
+            .checked_sub(offset)
+            .filter(|_| offset != 0)
+            .ok_or(InvalidLz4Error)?;
+        if decompressed.len() + match_length > size {
+            return Err(InvalidLz4Error);
+        }
+        // The match can overlap what it's copying to
+        for i in start..start + match_length {
+            decompressed.push(decompressed[i]);
+        }
+    }
+    if decompressed.len() != size {
+        return Err(InvalidLz4Error);
+    }
+    Ok(decompressed)
+}
+
 // Used very commonly
 pub(crate) fn quicklist<'i, 'p, O, P: Parser<Stream<'i>, O, ContextError> + 'p>(
     parser: P,
@@ -647,4 +873,120 @@ mod test {
             }
         }
     }
+
+    fn xna40_texture_content() -> Vec<u8> {
+        let reader = b"Microsoft.Xna.Framework.Content.Texture2DReader";
+        let mut content = vec![1, reader.len() as u8];
+        content.extend_from_slice(reader);
+        content.extend_from_slice(&0i32.to_le_bytes()); // Reader version
+        content.push(0); // Shared resource count
+        content.push(1); // Primary object type
+        for value in [6u32, 4, 4, 1, 16] {
+            // Dxt5, 4x4, one level of 16 bytes
+            content.extend_from_slice(&value.to_le_bytes());
+        }
+        content.extend_from_slice(&[0; 16]);
+        content
+    }
+
+    fn xna40_xnb(flags: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"XNBw".to_vec();
+        bytes.push(5);
+        bytes.push(flags);
+        bytes.extend_from_slice(&(10 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_xna40_texture() {
+        let bytes = xna40_xnb(super::HEADER_FLAG_HIDEF, &xna40_texture_content());
+
+        let asset = match crate::parse_texture_2d(&bytes) {
+            Ok(asset) => asset,
+            Err(e) => panic!("parsing failed at {}\n{}", e.offset(), e.inner()),
+        };
+        assert_eq!(asset.format_version(), super::FormatVersion::Xna40);
+        assert!(asset.hidef());
+        let texture = asset.into_inner().unwrap();
+        assert_eq!(
+            texture.format,
+            crate::xnb_readers::xna_tex::SurfaceFormat::Dxt5
+        );
+        assert_eq!(texture.data_levels.len(), 1);
+    }
+
+    #[test]
+    fn test_decompress_lz4_block() {
+        let block = [
+            0x35, b'a', b'b', b'c', // 3 literals, then a match of 5 + 4
+            3, 0, // Copying from 3 bytes back, overlapping what it writes
+            0x10, b'd', // The last literal
+        ];
+        let decompressed = super::decompress_lz4_block(&block, 13).unwrap();
+        assert_eq!(decompressed, b"abcabcabcabcd");
+
+        // Offsets can't point before the start, or be 0
+        assert!(super::decompress_lz4_block(&[0x10, b'a', 2, 0, 0x00], 6).is_err());
+        assert!(super::decompress_lz4_block(&[0x10, b'a', 0, 0, 0x00], 6).is_err());
+        // The size has to match
+        assert!(super::decompress_lz4_block(&block, 12).is_err());
+        assert!(super::decompress_lz4_block(&block, 14).is_err());
+    }
+
+    #[test]
+    fn test_parse_xna40_lz4_texture() {
+        let content = xna40_texture_content();
+        // A single sequence of just literals
+        let mut data = (content.len() as u32).to_le_bytes().to_vec();
+        data.push(0xF0);
+        let mut length = content.len() - 15;
+        while length >= 255 {
+            data.push(255);
+            length -= 255;
+        }
+        data.push(length as u8);
+        data.extend_from_slice(&content);
+        let bytes = xna40_xnb(
+            super::HEADER_FLAG_HIDEF | super::HEADER_FLAG_COMPRESSED_LZ4,
+            &data,
+        );
+
+        let asset = match crate::parse_texture_2d(&bytes) {
+            Ok(asset) => asset,
+            Err(e) => panic!("parsing failed at {}\n{}", e.offset(), e.inner()),
+        };
+        assert_eq!(asset.into_inner().unwrap().data_levels[0].len(), 16);
+    }
+
+    #[test]
+    fn test_parse_xna40_basic_effect() {
+        let reader = b"Microsoft.Xna.Framework.Content.BasicEffectReader";
+        let mut content = vec![1, reader.len() as u8];
+        content.extend_from_slice(reader);
+        content.extend_from_slice(&0i32.to_le_bytes()); // Reader version
+        content.push(0); // Shared resource count
+        content.push(1); // Primary object type
+        content.push(b"wall".len() as u8);
+        content.extend_from_slice(b"wall");
+        for value in [1f32, 0.5, 0.25, 0., 0., 0., 1., 1., 1., 16., 1.] {
+            // Diffuse, emissive and specular colors, specular power and alpha
+            content.extend_from_slice(&value.to_le_bytes());
+        }
+        content.push(0); // Vertex colors disabled
+        let bytes = xna40_xnb(0, &content);
+
+        let asset = match super::parse(
+            &bytes,
+            super::object(crate::xnb_readers::xna_effect::basic_effect),
+        ) {
+            Ok(asset) => asset,
+            Err(e) => panic!("parsing failed at {}\n{}", e.offset(), e.inner()),
+        };
+        let effect = asset.into_inner().unwrap();
+        assert_eq!(effect.texture.as_deref(), Some("wall"));
+        assert_eq!(effect.diffuse_color.1, 0.5);
+        assert_eq!(effect.specular_power, 16.);
+        assert!(!effect.vertex_color_enabled);
+    }
 }
@@ -10,7 +10,11 @@ use winnow::{
 
 use crate::{
     xnb::{Stream, TypeReaderMeta, quicklist, types::*},
-    xnb_readers::{magicka_common::special_ability, skinning::SkinnedModel, xna_mesh::Model},
+    xnb_readers::{
+        magicka_common::{EventCollection, condition_collection, special_ability},
+        skinning::SkinnedModel,
+        xna_mesh::Model,
+    },
 };
 
 // External reference types
@@ -72,12 +76,13 @@ pub struct CharacterTemplate {
     // attached_effects: Vec<(String, String)>,
     pub animation_sets: Vec<AnimationSet>,
     pub equipment: Vec<CharacterEquip>,
+    /// The `MovementProperties` flags of all the character's move animations, the ways it can
+    /// move through the level. `None` when the end of the template can't be read.
+    pub movement_properties: Option<u8>,
+    /// What happens when the character is hit, damaged, killed, etc. Empty when the end of the
+    /// template can't be read.
+    pub event_conditions: Vec<EventCollection>,
     /*
-    pub event_conditions: Vec<(
-        (u8, i32, i32, f32, f32), // event_condition
-        bool,
-        Vec<()>, // event_storage
-    )>,
     pub alert_radius: f32,
     pub group_chase: f32,
     pub group_separation: f32,
@@ -220,12 +225,44 @@ pub fn character_template(input: &mut Stream) -> Result<CharacterTemplate> {
     .parse_next(input)?;*/
     let equipment = quicklist(equip).parse_next(input)?;
 
-    // TODO: Parse rest
+    // TODO: Parse rest properly. Abilities don't always parse yet, so this is read speculatively
+    let checkpoint = input.checkpoint();
+    let (event_conditions, movement_properties) =
+        template_tail.parse_next(input).unwrap_or_else(|e| {
+            eprintln!(
+                "warn: reading the end of character template {id:?} failed, its event conditions \
+                 and movement properties are left out: {e}"
+            );
+            input.reset(&checkpoint);
+            (Vec::new(), None)
+        });
 
-    /*
-    let event_conditions = quicklist(condition_collection).parse_next(input)?;
+    // Buffs and auras aren't read yet
+
+    let _uninterpreted = input.finish();
+
+    Ok(CharacterTemplate {
+        id,
+        display_id,
+        max_hitpoints,
+        length,
+        radius,
+        mass,
+        speed,
+        turn_speed,
+        skinned_models,
+        animation_sets,
+        equipment,
+        movement_properties,
+        event_conditions,
+    })
+}
 
-    let d = (
+/// Reads the end of a character template, up to its move animations, for its event conditions
+/// and movement properties.
+fn template_tail(input: &mut Stream) -> Result<(Vec<EventCollection>, Option<u8>)> {
+    let event_conditions = quicklist(condition_collection).parse_next(input)?;
+    let _ai = (
         f32, // alert_radius
         f32, // group_chase
         f32, // group_separation
@@ -245,29 +282,16 @@ pub fn character_template(input: &mut Stream) -> Result<CharacterTemplate> {
         f32,  // break_free_strength
     )
         .parse_next(input)?;
-
-    let abilities = quicklist(ability).parse_next(input)?;
-    let move_animations = quicklist((u8, quicklist(string))).parse_next(input)?; // map<movement_properties, vec<animation>>
-    let buffs = quicklist(buff).parse_next(input)?;
-    let auras = quicklist(aura).parse_next(input)?;
-
-    */
-
-    let _uninterpreted = input.finish();
-
-    Ok(CharacterTemplate {
-        id,
-        display_id,
-        max_hitpoints,
-        length,
-        radius,
-        mass,
-        speed,
-        turn_speed,
-        skinned_models,
-        animation_sets,
-        equipment,
-    })
+    let _abilities = quicklist(ability).parse_next(input)?;
+    // map<movement_properties, vec<animation>>
+    let move_animations = quicklist((u8, quicklist(string))).parse_next(input)?;
+    let movement_properties = move_animations
+        .into_iter()
+        .fold(0, |properties, (movement, _)| properties | movement);
+    Ok((
+        event_conditions.into_iter().flatten().collect(),
+        Some(movement_properties),
+    ))
 }
 
 /// A moveset for the character, such as while wielding a staff or while unarmed.
@@ -591,7 +615,8 @@ pub enum AbilityData {
         max_range: f32,
         min_range: f32,
         elevation: f32,
-        damages: Vec<(i32, i32, i32, f32)>,
+        /// Attack properties, elements, amount and magnitude, like Magicka's `Damage`.
+        damages: Vec<(i32, i32, f32, f32)>,
     },
     ZombieGrip {
         max_range: f32,
@@ -619,7 +644,7 @@ fn equip(input: &mut Stream) -> Result<CharacterEquip> {
     .parse_next(input)
 }
 
-#[expect(dead_code)] // TODO: Parse character abilities. Last I checked it still crashes a bit, so not used yet
+// TODO: Last I checked it still fails on some templates, so only used speculatively
 fn ability(input: &mut Stream) -> Result<Ability> {
     let (type_name, cooldown, target, fuzzy_expression, animation_keys) = (
         string,
@@ -710,10 +735,7 @@ fn ability(input: &mut Stream) -> Result<Ability> {
             max_range: f32,
             min_range: f32,
             elevation: f32,
-            damages: length_repeat(
-                i32.try_map(usize::try_from).map(|n| n.min(4)),
-                (i32, i32, i32, f32),
-            ),
+            damages: length_repeat(i32.try_map(usize::try_from), (i32, i32, f32, f32)),
         })
         .parse_next(input)?,
         "ZombieGrip" => seq!(AbilityData::ZombieGrip {
@@ -724,11 +746,14 @@ fn ability(input: &mut Stream) -> Result<Ability> {
             drop_animation: string.map(ToOwned::to_owned),
         })
         .parse_next(input)?,
-        _t => winnow::combinator::fail
-            .context(StrContext::Expected(StrContextValue::Description(
-                "a valid character template ability type",
-            )))
-            .parse_next(input)?,
+        t => {
+            eprintln!("warn: unknown character template ability type {t:?}");
+            winnow::combinator::fail
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "a valid character template ability type",
+                )))
+                .parse_next(input)?
+        }
     };
     Ok(Ability {
         cooldown,
@@ -1,7 +1,7 @@
 use num_enum::TryFromPrimitive;
 use winnow::{
     Parser, Result,
-    combinator::seq,
+    combinator::{fail, seq},
     error::{StrContext, StrContextValue},
 };
 
@@ -9,17 +9,16 @@ use crate::xnb::{Stream, quicklist, types::*};
 
 pub type ConditionCollection = Vec<EventCollection>;
 
-#[expect(dead_code)] // ?
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EventCollection {
     pub condition: EventCondition,
     pub repeat: bool,
     pub event_storages: Vec<EventStorage>,
 }
 
-#[expect(dead_code)] // ?
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EventCondition {
+    /// Flags of when the events happen, such as [`EventCondition::KILLED`].
     pub condition_type: u8,
     pub hitpoints: i32,
     pub element_set: i32,
@@ -27,8 +26,32 @@ pub struct EventCondition {
     pub time: f32,
 }
 
-#[derive(Debug)]
-pub struct EventStorage {}
+impl EventCondition {
+    pub const DEFAULT: u8 = 1;
+    pub const HIT: u8 = 2;
+    pub const COLLISION: u8 = 4;
+    pub const DAMAGED: u8 = 8;
+    pub const KILLED: u8 = 16;
+    pub const OVERKILLED: u8 = 32;
+    pub const DESTROYED: u8 = 64;
+    pub const TIMER: u8 = 128;
+}
+
+/// An event to run when an [`EventCondition`] is met. Only some are kept so far.
+#[derive(Debug, Clone)]
+pub enum EventStorage {
+    SpawnDecal(DecalEvent),
+    Other,
+}
+
+/// Leaves a decal where the event happens.
+#[derive(Debug, Clone, Copy)]
+pub struct DecalEvent {
+    pub decal_type: i32,
+    pub index: i32,
+    /// In hundredths of the decal's normal size.
+    pub scale: i32,
+}
 
 pub fn condition_collection(input: &mut Stream) -> Result<ConditionCollection> {
     quicklist(event_collection).parse_next(input)
@@ -64,34 +87,42 @@ fn event_storage(input: &mut Stream) -> Result<EventStorage> {
     match event_type {
         EventType::Damage => {
             let (_damage, _use_velocity) = (damage, bool).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::Splash => {
             let (_damage, _radius) = (damage, f32).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::Sound => {
             let (_sound_bank, _sound_name, _magnitude, _stop_on_remove) =
                 (i32, string, f32, bool).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::Effect => {
             let (_follow, _world_align, _effect_name) = (bool, bool, string).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::Remove => {
             let _bounce = bool.parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::CameraShake => {
             let (_duration, _magnitude, _positional) = (f32, f32, bool).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::SpawnDecal => {
-            let (_i, _j, _scale) = (i32, i32, i32).parse_next(input)?;
-            Ok(EventStorage {})
+            let (decal_type, index, scale) = (i32, i32, i32).parse_next(input)?;
+            Ok(EventStorage::SpawnDecal(DecalEvent {
+                decal_type,
+                index,
+                scale,
+            }))
         }
-        EventType::Blast => unimplemented!("item blast event"),
+        EventType::Blast => fail
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a supported event storage type, not blast",
+            )))
+            .parse_next(input),
         EventType::SpawnCharacter => {
             let (
                 _character_template_name,
@@ -104,25 +135,25 @@ fn event_storage(input: &mut Stream) -> Result<EventStorage> {
                 _rotation,
                 _offset,
             ) = (string, string, string, f32, u8, u8, u8, f32, vec3).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
-        EventType::Overkill => Ok(EventStorage {}),
+        EventType::Overkill => Ok(EventStorage::Other),
         EventType::SpawnGibs => {
             let (_start_index, _end_index) = (i32, i32).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::SpawnItem => {
             let _wizard_item_name = string.parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::SpawnMagick => {
             let _magick_name = string.parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::SpawnMissile => {
             let (_weapon_type_name, _velocity, _directional) =
                 (string, vec3, bool).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::Light => {
             let (
@@ -134,17 +165,21 @@ fn event_storage(input: &mut Stream) -> Result<EventStorage> {
                 _variation_amount,
                 _variation_speed,
             ) = (f32, vec3, vec3, f32, u8, f32, f32).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::CastMagick => {
             let (_magick_type_name, _element_sets) = (string, quicklist(i32)).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
         EventType::DamageOwner => {
             let (_damage, _use_velocity) = (damage, bool).parse_next(input)?;
-            Ok(EventStorage {})
+            Ok(EventStorage::Other)
         }
-        EventType::Callback => unimplemented!("deserialize item callback event"),
+        EventType::Callback => fail
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a supported event storage type, not callback",
+            )))
+            .parse_next(input),
     }
 }
 
@@ -185,6 +220,9 @@ pub fn special_ability(input: &mut Stream) -> Result<()> {
     Ok(())
 }
 
-pub fn aura(_input: &mut Stream) -> Result<()> {
-    todo!("parse aura")
+pub fn aura(input: &mut Stream) -> Result<()> {
+    fail.context(StrContext::Expected(StrContextValue::Description(
+        "no auras, they aren't supported yet",
+    )))
+    .parse_next(input)
 }
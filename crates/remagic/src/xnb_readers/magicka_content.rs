@@ -139,7 +139,8 @@ pub struct AnimatedLevelPart {
 }
 #[derive(Debug)]
 pub struct AnimatedLevelPartCollision {
-    pub material: u8, // CollisionMaterial
+    /// The index of a CollisionMaterial, like the level collision slots.
+    pub material: u8,
     pub vertices: Option<List<Vector3>>,
     pub triangle_vertex_indices: Vec<(i32, i32, i32)>,
 }
@@ -0,0 +1,179 @@
+//! XNA's built-in effects, which XNA 4.0 models reference as shared resources.
+//!
+//! XNA 4.0 replaced material content with configurable effects, adding every reader here except
+//! [`CompiledEffect`] and [`BasicEffect`].
+
+// https://github.com/MonoGame/MonoGame/tree/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentReaders
+
+use winnow::{
+    Parser as _, Result,
+    binary::{le_u32, length_take},
+    combinator::seq,
+};
+
+use crate::xnb::{
+    Stream, TypeReaderMeta,
+    types::{Vector3, bool, f32, i32, string, vec3},
+};
+
+/// Reads an external reference's asset name, which is empty for none.
+fn external_name(input: &mut Stream) -> Result<Option<String>> {
+    string
+        .map(|name| (!name.is_empty()).then(|| name.to_owned()))
+        .parse_next(input)
+}
+
+/// A compiled shader, which differs between XNA 3.1 and 4.0 but is read the same.
+pub struct CompiledEffect {
+    pub bytecode: Vec<u8>,
+}
+impl std::fmt::Debug for CompiledEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompiledEffect")
+            .field("bytecode", &"...")
+            .finish()
+    }
+}
+impl TypeReaderMeta for CompiledEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.EffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn compiled_effect(input: &mut Stream) -> Result<CompiledEffect> {
+    length_take(le_u32.try_map(usize::try_from))
+        .map(|bytecode: &[u8]| CompiledEffect {
+            bytecode: bytecode.to_owned(),
+        })
+        .parse_next(input)
+}
+
+#[derive(Debug)]
+pub struct BasicEffect {
+    pub texture: Option<String>,
+    pub diffuse_color: Vector3,
+    pub emissive_color: Vector3,
+    pub specular_color: Vector3,
+    pub specular_power: f32,
+    pub alpha: f32,
+    pub vertex_color_enabled: bool,
+}
+impl TypeReaderMeta for BasicEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.BasicEffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn basic_effect(input: &mut Stream) -> Result<BasicEffect> {
+    seq!(BasicEffect {
+        texture: external_name,
+        diffuse_color: vec3,
+        emissive_color: vec3,
+        specular_color: vec3,
+        specular_power: f32,
+        alpha: f32,
+        vertex_color_enabled: bool,
+    })
+    .parse_next(input)
+}
+
+#[derive(Debug)]
+pub struct AlphaTestEffect {
+    pub texture: Option<String>,
+    /// XNA's `CompareFunction`.
+    pub alpha_function: i32,
+    pub reference_alpha: u32,
+    pub diffuse_color: Vector3,
+    pub alpha: f32,
+    pub vertex_color_enabled: bool,
+}
+impl TypeReaderMeta for AlphaTestEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.AlphaTestEffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn alpha_test_effect(input: &mut Stream) -> Result<AlphaTestEffect> {
+    seq!(AlphaTestEffect {
+        texture: external_name,
+        alpha_function: i32,
+        reference_alpha: le_u32,
+        diffuse_color: vec3,
+        alpha: f32,
+        vertex_color_enabled: bool,
+    })
+    .parse_next(input)
+}
+
+#[derive(Debug)]
+pub struct DualTextureEffect {
+    pub texture: Option<String>,
+    pub texture_2: Option<String>,
+    pub diffuse_color: Vector3,
+    pub alpha: f32,
+    pub vertex_color_enabled: bool,
+}
+impl TypeReaderMeta for DualTextureEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.DualTextureEffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn dual_texture_effect(input: &mut Stream) -> Result<DualTextureEffect> {
+    seq!(DualTextureEffect {
+        texture: external_name,
+        texture_2: external_name,
+        diffuse_color: vec3,
+        alpha: f32,
+        vertex_color_enabled: bool,
+    })
+    .parse_next(input)
+}
+
+#[derive(Debug)]
+pub struct EnvironmentMapEffect {
+    pub texture: Option<String>,
+    pub environment_map: Option<String>,
+    pub environment_map_amount: f32,
+    pub environment_map_specular: Vector3,
+    pub fresnel_factor: f32,
+    pub diffuse_color: Vector3,
+    pub emissive_color: Vector3,
+    pub alpha: f32,
+}
+impl TypeReaderMeta for EnvironmentMapEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.EnvironmentMapEffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn environment_map_effect(input: &mut Stream) -> Result<EnvironmentMapEffect> {
+    seq!(EnvironmentMapEffect {
+        texture: external_name,
+        environment_map: external_name,
+        environment_map_amount: f32,
+        environment_map_specular: vec3,
+        fresnel_factor: f32,
+        diffuse_color: vec3,
+        emissive_color: vec3,
+        alpha: f32,
+    })
+    .parse_next(input)
+}
+
+#[derive(Debug)]
+pub struct SkinnedEffect {
+    pub texture: Option<String>,
+    pub weights_per_vertex: i32,
+    pub diffuse_color: Vector3,
+    pub emissive_color: Vector3,
+    pub specular_color: Vector3,
+    pub specular_power: f32,
+    pub alpha: f32,
+}
+impl TypeReaderMeta for SkinnedEffect {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.SkinnedEffectReader";
+    const VERSION: i32 = 0;
+}
+pub(crate) fn skinned_effect(input: &mut Stream) -> Result<SkinnedEffect> {
+    seq!(SkinnedEffect {
+        texture: external_name,
+        weights_per_vertex: i32,
+        diffuse_color: vec3,
+        emissive_color: vec3,
+        specular_color: vec3,
+        specular_power: f32,
+        alpha: f32,
+    })
+    .parse_next(input)
+}
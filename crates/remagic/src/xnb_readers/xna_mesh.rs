@@ -9,13 +9,14 @@ use winnow::{
 };
 
 use crate::xnb::{
-    SharedResourceReference, Stream, TypeReaderMeta, object, object_any, shared_resource_ref,
-    types::*,
+    FormatVersion, SharedResourceReference, Stream, TypeReaderMeta, object, object_any,
+    shared_resource_ref, types::*,
 };
 
 #[derive(Debug)]
 pub struct Model {
     pub bones: Vec<ModelBone>,
+    /// Empty for XNA 4.0, where each [`VertexBuffer`] carries its own declaration.
     pub vertex_declarations: Vec<Option<VertexDeclaration>>,
     pub meshes: Vec<Mesh>,
     pub root_bone: Option<i32>,
@@ -82,6 +83,28 @@ pub(crate) fn model(input: &mut Stream) -> Result<Model> {
             start_index,
             primitive_count,
             vertex_declaration_index,
+            vertex_buffer: None,
+            index_buffer: None,
+            effect,
+        })
+    }
+    // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentReaders/ModelReader.cs#L124
+    fn mesh_part_xna40(input: &mut Stream) -> Result<MeshPart> {
+        let (base_vertex, num_vertices, start_index, primitive_count) =
+            (i32, i32, i32, i32).parse_next(input)?;
+        let _tag = object_any.parse_next(input)?;
+        let vertex_buffer = shared_resource_ref.parse_next(input)?;
+        let index_buffer = shared_resource_ref.parse_next(input)?;
+        let effect = shared_resource_ref.parse_next(input)?;
+        Ok(MeshPart {
+            stream_offset: 0,
+            base_vertex,
+            num_vertices,
+            start_index,
+            primitive_count,
+            vertex_declaration_index: 0,
+            vertex_buffer,
+            index_buffer,
             effect,
         })
     }
@@ -91,11 +114,23 @@ pub(crate) fn model(input: &mut Stream) -> Result<Model> {
             let parent_bone = bone_ref(bone_count).parse_next(input)?;
             let bounding_sphere_center = vec3.parse_next(input)?;
             let bounding_sphere_radius = f32.parse_next(input)?;
-            let vertex_buffer = object(vertex_buffer).parse_next(input)?;
-            let index_buffer = object(index_buffer).parse_next(input)?;
-            let obj = object_any.parse_next(input)?;
-            let parts: Vec<_> =
-                length_repeat(i32.try_map(usize::try_from), mesh_part).parse_next(input)?;
+            let (vertex_buffer, index_buffer, parts) = match input.state.version {
+                FormatVersion::Xna31 => {
+                    let vertex_buffer = object(vertex_buffer).parse_next(input)?;
+                    let index_buffer = object(index_buffer).parse_next(input)?;
+                    let obj = object_any.parse_next(input)?;
+                    let parts: Vec<_> =
+                        length_repeat(i32.try_map(usize::try_from), mesh_part).parse_next(input)?;
+                    (vertex_buffer, index_buffer, parts)
+                }
+                FormatVersion::Xna40 => {
+                    let obj = object_any.parse_next(input)?;
+                    let parts: Vec<_> =
+                        length_repeat(u32.try_map(usize::try_from), mesh_part_xna40)
+                            .parse_next(input)?;
+                    (None, None, parts)
+                }
+            };
             Ok(Mesh {
                 name,
                 parent_bone,
@@ -112,12 +147,14 @@ pub(crate) fn model(input: &mut Stream) -> Result<Model> {
             "model bones",
         )))
         .parse_next(input)?;
-    let vertex_declarations: Vec<_> =
-        length_repeat(i32.try_map(usize::try_from), object(vertex_decl))
+    let vertex_declarations: Vec<_> = match input.state.version {
+        FormatVersion::Xna31 => length_repeat(i32.try_map(usize::try_from), object(vertex_decl))
             .context(StrContext::Expected(StrContextValue::Description(
                 "model vertex declarations",
             )))
-            .parse_next(input)?;
+            .parse_next(input)?,
+        FormatVersion::Xna40 => Vec::new(),
+    };
     let meshes: Vec<_> = length_repeat(i32.try_map(usize::try_from), mesh(bones.len()))
         .context(StrContext::Expected(StrContextValue::Description(
             "model meshes",
@@ -149,7 +186,9 @@ pub struct Mesh {
     pub parent_bone: Option<i32>,
     pub bounding_sphere_center: Vector3,
     pub bounding_sphere_radius: f32,
+    /// Only in XNA 3.1, see [`MeshPart::vertex_buffer`] for XNA 4.0.
     pub vertex_buffer: Option<VertexBuffer>,
+    /// Only in XNA 3.1, see [`MeshPart::index_buffer`] for XNA 4.0.
     pub index_buffer: Option<IndexBuffer>,
     pub parts: Vec<MeshPart>,
 }
@@ -161,6 +200,9 @@ pub struct MeshPart {
     pub start_index: i32,
     pub primitive_count: i32,
     pub vertex_declaration_index: i32,
+    /// Only in XNA 4.0, where parts reference shared buffers instead of the mesh owning them.
+    pub vertex_buffer: Option<SharedResourceReference<VertexBuffer>>,
+    pub index_buffer: Option<SharedResourceReference<IndexBuffer>>,
     // TODO: This should be any Effect, not DeferredEffect
     // pub effect: Option<SharedResourceReference<Effect>>,
     // HACK: Use common one since we don't support polymorphism yet
@@ -173,9 +215,15 @@ pub struct AnyEffect;
 
 // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentReaders/VertexDeclarationReader.cs#L9
 pub(crate) fn vertex_decl(input: &mut Stream) -> Result<VertexDeclaration> {
-    return length_repeat(i32.try_map(usize::try_from), vertex_element)
-        .map(|elements| VertexDeclaration { elements })
-        .parse_next(input);
+    return match input.state.version {
+        FormatVersion::Xna31 => length_repeat(i32.try_map(usize::try_from), vertex_element)
+            .map(|elements| VertexDeclaration {
+                vertex_stride: None,
+                elements,
+            })
+            .parse_next(input),
+        FormatVersion::Xna40 => vertex_decl_xna40.parse_next(input),
+    };
     fn vertex_element(input: &mut Stream) -> Result<VertexElement> {
         seq!(VertexElement {
             stream: i16,
@@ -188,8 +236,38 @@ pub(crate) fn vertex_decl(input: &mut Stream) -> Result<VertexDeclaration> {
         .parse_next(input)
     }
 }
+// XNA 4.0 dropped streams and methods, and widened every field.
+fn vertex_decl_xna40(input: &mut Stream) -> Result<VertexDeclaration> {
+    let vertex_stride = u32.parse_next(input)?;
+    let elements = length_repeat(u32.try_map(usize::try_from), vertex_element).parse_next(input)?;
+    return Ok(VertexDeclaration {
+        vertex_stride: Some(vertex_stride),
+        elements,
+    });
+    fn vertex_element(input: &mut Stream) -> Result<VertexElement> {
+        let (offset, element_format, element_usage, usage_index) = (
+            u32.try_map(i16::try_from),
+            i32.verify_map(VertexElementFormat::from_xna40)
+                .context(StrContext::Label("XNA 4.0 VertexElementFormat")),
+            i32.verify_map(VertexElementUsage::from_xna40)
+                .context(StrContext::Label("XNA 4.0 VertexElementUsage")),
+            u32.try_map(u8::try_from),
+        )
+            .parse_next(input)?;
+        Ok(VertexElement {
+            stream: 0,
+            offset,
+            element_format,
+            element_method: VertexElementMethod::Default,
+            element_usage,
+            usage_index,
+        })
+    }
+}
 #[derive(Debug)]
 pub struct VertexDeclaration {
+    /// Only in XNA 4.0, XNA 3.1 derives it from the elements.
+    pub vertex_stride: Option<u32>,
     pub elements: Vec<VertexElement>,
 }
 impl TypeReaderMeta for VertexDeclaration {
@@ -228,6 +306,26 @@ pub enum VertexElementFormat {
     Vector4 = 3,
     Unused = 17,
 }
+impl VertexElementFormat {
+    // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Graphics/Vertices/VertexElementFormat.cs
+    pub fn from_xna40(value: i32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Single,
+            1 => Self::Vector2,
+            2 => Self::Vector3,
+            3 => Self::Vector4,
+            4 => Self::Color,
+            5 => Self::Byte4,
+            6 => Self::Short2,
+            7 => Self::Short4,
+            8 => Self::NormalizedShort2,
+            9 => Self::NormalizedShort4,
+            10 => Self::HalfVector2,
+            11 => Self::HalfVector4,
+            _ => return None,
+        })
+    }
+}
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum VertexElementMethod {
@@ -253,20 +351,62 @@ pub enum VertexElementUsage {
     TessellateFactor = 8,
     TextureCoordinate = 5,
 }
+impl VertexElementUsage {
+    // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Graphics/Vertices/VertexElementUsage.cs
+    pub fn from_xna40(value: i32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Position,
+            1 => Self::Color,
+            2 => Self::TextureCoordinate,
+            3 => Self::Normal,
+            4 => Self::Binormal,
+            5 => Self::Tangent,
+            6 => Self::BlendIndices,
+            7 => Self::BlendWeight,
+            8 => Self::Depth,
+            9 => Self::Fog,
+            10 => Self::PointSize,
+            11 => Self::Sample,
+            12 => Self::TessellateFactor,
+            _ => return None,
+        })
+    }
+}
 
 // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Content/ContentReaders/VertexBufferReader.cs
 pub(crate) fn vertex_buffer(input: &mut Stream) -> Result<VertexBuffer> {
-    let data = length_take(u32.try_map(usize::try_from)).parse_next(input)?;
-    Ok(VertexBuffer {
-        data: data.to_owned(),
-    })
+    match input.state.version {
+        FormatVersion::Xna31 => {
+            let data = length_take(u32.try_map(usize::try_from)).parse_next(input)?;
+            Ok(VertexBuffer {
+                declaration: None,
+                data: data.to_owned(),
+            })
+        }
+        FormatVersion::Xna40 => {
+            let declaration = vertex_decl_xna40.parse_next(input)?;
+            let stride = declaration.vertex_stride.unwrap_or_default();
+            let data = length_take(
+                u32.try_map(usize::try_from)
+                    .verify_map(|count| count.checked_mul(usize::try_from(stride).ok()?)),
+            )
+            .parse_next(input)?;
+            Ok(VertexBuffer {
+                declaration: Some(declaration),
+                data: data.to_owned(),
+            })
+        }
+    }
 }
 pub struct VertexBuffer {
+    /// Only in XNA 4.0, where the declaration is stored inline with the vertices.
+    pub declaration: Option<VertexDeclaration>,
     pub data: Vec<u8>,
 }
 impl std::fmt::Debug for VertexBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("VertexBuffer")
+            .field("declaration", &self.declaration)
             .field("data", &"...")
             .finish()
     }
@@ -278,7 +418,10 @@ impl TypeReaderMeta for VertexBuffer {
 
 pub(crate) fn index_buffer(input: &mut Stream) -> Result<IndexBuffer> {
     let sixteenbit = bool.parse_next(input)?;
-    let data = length_take(i32.try_map(usize::try_from)).parse_next(input)?;
+    let data = match input.state.version {
+        FormatVersion::Xna31 => length_take(i32.try_map(usize::try_from)).parse_next(input)?,
+        FormatVersion::Xna40 => length_take(u32.try_map(usize::try_from)).parse_next(input)?,
+    };
     Ok(IndexBuffer {
         sixteenbit,
         data: data.to_owned(),
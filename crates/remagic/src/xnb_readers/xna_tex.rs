@@ -1,10 +1,11 @@
-use crate::xnb::TypeReaderMeta;
+use crate::xnb::{FormatVersion, TypeReaderMeta, object};
 use crate::xnb::{Stream, types::i32};
 use num_enum::TryFromPrimitive;
 use winnow::Parser as _;
 use winnow::Result;
 use winnow::binary::length_repeat;
 use winnow::binary::length_take;
+use winnow::combinator::{alt, repeat};
 use winnow::error::StrContext;
 
 pub struct Texture2d {
@@ -31,7 +32,7 @@ impl std::fmt::Debug for Texture2d {
     }
 }
 pub(crate) fn texture_2d(input: &mut Stream) -> Result<Texture2d> {
-    let format = i32.try_map(TryInto::try_into).parse_next(input)?;
+    let format = surface_format.parse_next(input)?;
     let width = i32.parse_next(input)?;
     let height = i32.parse_next(input)?;
     let data_levels = length_repeat(
@@ -116,5 +117,108 @@ pub enum SurfaceFormat {
     Unknown = -1,
 }
 
+impl SurfaceFormat {
+    /// Maps the renumbered XNA 4.0 `SurfaceFormat` onto the XNA 3.1 one.
+    // https://github.com/MonoGame/MonoGame/blob/b5ead4c88dd114354f0d433fcb0ce635e7a05212/MonoGame.Framework/Graphics/SurfaceFormat.cs
+    pub fn from_xna40(value: i32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Color,
+            1 => Self::Bgr565,
+            2 => Self::Bgra5551,
+            3 => Self::Bgra4444,
+            4 => Self::Dxt1,
+            5 => Self::Dxt3,
+            6 => Self::Dxt5,
+            7 => Self::NormalizedByte2,
+            8 => Self::NormalizedByte4,
+            9 => Self::Rgba1010102,
+            10 => Self::Rg32,
+            11 => Self::Rgba64,
+            12 => Self::Alpha8,
+            13 => Self::Single,
+            14 => Self::Vector2,
+            15 => Self::Vector4,
+            16 => Self::HalfSingle,
+            17 => Self::HalfVector2,
+            18 => Self::HalfVector4,
+            // HdrBlendable has no 3.1 equivalent, but is HalfVector4 on Windows
+            19 => Self::HalfVector4,
+            _ => return None,
+        })
+    }
+}
+
+pub struct TextureCube {
+    pub format: SurfaceFormat,
+    /// The width and height of each face.
+    pub size: i32,
+    /// For each face in the order +X, -X, +Y, -Y, +Z, -Z, its mip levels.
+    pub faces: [Vec<Vec<u8>>; 6],
+}
+
+impl TypeReaderMeta for TextureCube {
+    const NAME: &'static str = "Microsoft.Xna.Framework.Content.TextureCubeReader";
+
+    const VERSION: i32 = 0;
+}
+
+impl std::fmt::Debug for TextureCube {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextureCube")
+            .field("format", &self.format)
+            .field("size", &self.size)
+            .field("faces", &format!("[[...; {}]; 6]", self.faces[0].len()))
+            .finish()
+    }
+}
+pub(crate) fn texture_cube(input: &mut Stream) -> Result<TextureCube> {
+    let format = surface_format.parse_next(input)?;
+    let size = i32.parse_next(input)?;
+    let level_count = i32
+        .try_map(usize::try_from)
+        .context(StrContext::Label("TextureCube level count"))
+        .parse_next(input)?;
+    let mut faces: [Vec<Vec<u8>>; 6] = Default::default();
+    for face in &mut faces {
+        *face = repeat(
+            level_count,
+            length_take(
+                i32.try_map(usize::try_from)
+                    .context(StrContext::Label("TextureCube data layer length")),
+            )
+            .map(ToOwned::to_owned),
+        )
+        .context(StrContext::Label("TextureCube face data"))
+        .parse_next(input)?;
+    }
+    Ok(TextureCube {
+        format,
+        size,
+        faces,
+    })
+}
+
+/// A texture of any kind, for where the reader doesn't say which.
 #[derive(Debug)]
-pub struct TextureCube;
+pub enum Texture {
+    Texture2d(Texture2d),
+    TextureCube(TextureCube),
+}
+
+pub(crate) fn texture(input: &mut Stream) -> Result<Option<Texture>> {
+    alt((
+        object(texture_2d).map(|t| t.map(Texture::Texture2d)),
+        object(texture_cube).map(|t| t.map(Texture::TextureCube)),
+    ))
+    .parse_next(input)
+}
+
+fn surface_format(input: &mut Stream) -> Result<SurfaceFormat> {
+    match input.state.version {
+        FormatVersion::Xna31 => i32.try_map(TryInto::try_into).parse_next(input),
+        FormatVersion::Xna40 => i32
+            .verify_map(SurfaceFormat::from_xna40)
+            .context(StrContext::Label("XNA 4.0 SurfaceFormat"))
+            .parse_next(input),
+    }
+}
@@ -3,7 +3,7 @@ use crate::magicka_level_model::CameraMesh;
 use bevy::{
     camera::Hdr,
     camera::RenderTarget,
-    core_pipeline::tonemapping::Tonemapping,
+    core_pipeline::{prepass::DepthPrepass, tonemapping::Tonemapping},
     input::InputSystems,
     post_process::bloom::Bloom,
     prelude::*,
@@ -19,12 +19,50 @@ pub fn plugin(app: &mut App) {
     app.add_plugins(free_camera::plugin);
 }
 
+const CAMERAOFFSET: Vec3 = vec3(0., 144., 171.);
+const CAMERAPITCH: f32 = 40.0f32.to_radians();
+const DEFAULTFOV: f32 = 5.0f32.to_radians();
+
+/// How far a [`CameraGroupFollower`] is from the point it looks at.
+pub fn follow_distance() -> f32 {
+    CAMERAOFFSET.length()
+}
+
+/// How far the ground a [`CameraGroupFollower`] sees at a magnification reaches towards and away
+/// from it, measured along its view.
+pub fn follow_view_depth(magnify: f32) -> f32 {
+    let half_fov = DEFAULTFOV / magnify * 0.5;
+    follow_distance() * half_fov.tan() / CAMERAPITCH.tan()
+}
+
 #[derive(Component, Reflect)]
+#[require(CameraScript)]
 pub struct CameraGroupFollower {
     pub position: Vec3,
     pub magnify: f32,
 }
 
+/// How scene triggers direct a [`CameraGroupFollower`] away from following its group.
+#[derive(Component, Default, Debug, Reflect)]
+pub struct CameraScript {
+    /// Looks at this point instead of the group.
+    pub focus: Option<Vec3>,
+    /// About how long it takes to get to the [`focus`](Self::focus), in seconds.
+    pub move_time: f32,
+    /// Stays where it is.
+    pub locked: bool,
+    pub shake: Option<CameraShake>,
+}
+
+/// Jitters the view, less and less until it has lasted its duration.
+#[derive(Debug, Reflect)]
+pub struct CameraShake {
+    /// How far the view moves, in world units.
+    pub magnitude: f32,
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
 /// A camera with [`CameraGroupFollower`] includes this entity in its view.
 #[derive(Component, Default)]
 pub struct CameraGroupMember;
@@ -63,6 +101,7 @@ fn world_view_camera() -> impl Bundle {
             ev100: crate::magicka_level_model::light::MAGICKA_EXPOSURE,
         },
         Bloom::default(),
+        DepthPrepass, // For decals
         PrimaryView,
     )
 }
@@ -91,14 +130,20 @@ pub fn project_pointer_camera_rays(
 
 fn camera_follow_group(
     camera: Single<
-        (&mut Transform, &mut Projection, &mut CameraGroupFollower),
+        (
+            &mut Transform,
+            &mut Projection,
+            &mut CameraGroupFollower,
+            &mut CameraScript,
+        ),
         Without<PlayerControlled>,
     >,
     characters: Query<&Transform, (With<CameraGroupMember>, Without<CameraGroupFollower>)>,
     camera_mesh: Option<Single<&CameraMesh>>,
     time: Res<Time>,
 ) {
-    let (mut camera_transform, mut camera_projection, mut camera_state) = camera.into_inner();
+    let (mut camera_transform, mut camera_projection, mut camera_state, mut script) =
+        camera.into_inner();
     let players_center = {
         let mut sum = Vec3::ZERO;
         let mut count = 0;
@@ -141,20 +186,43 @@ fn camera_follow_group(
                 .project_point(Vec3::ZERO, Quat::IDENTITY, target_position, false);
         target_position = projected_point;
     }
+    let mut decay_rate = f32::ln(60.);
+    if script.locked {
+        target_position = camera_state.position;
+    } else if let Some(focus) = script.focus {
+        target_position = focus;
+        if script.move_time > 0. {
+            // Within a percent of the focus by then
+            decay_rate = f32::ln(100.) / script.move_time;
+        }
+    }
     let mut interpolated_position = camera_state.position;
-    interpolated_position.smooth_nudge(&target_position, f32::ln(60.), time.delta_secs());
+    interpolated_position.smooth_nudge(&target_position, decay_rate, time.delta_secs());
     let velocity = interpolated_position - camera_state.position;
     camera_state.position += velocity;
     // TODO: Determine target_magnification and interpolate camera_state.magnify towards it
 
-    const CAMERAOFFSET: Vec3 = vec3(0., 144., 171.);
-    const DEFAULTFOV: f32 = 5.0f32.to_radians();
-
     let position = camera_state.position + CAMERAOFFSET;
     let fov = DEFAULTFOV / camera_state.magnify;
 
     *camera_transform = Transform::from_translation(position);
-    camera_transform.rotate_x(-(40.0f32.to_radians()));
+    camera_transform.rotate_x(-CAMERAPITCH);
+    if let Some(shake) = &mut script.shake {
+        use rand::distr::uniform::SampleRange as _;
+        let mut rng: rand::rngs::SmallRng = rand::make_rng();
+        let strength = shake.magnitude * (1. - shake.elapsed / shake.duration).max(0.);
+        let offset = vec3(
+            (-1.0..=1.0).sample_single(&mut rng).unwrap(),
+            (-1.0..=1.0).sample_single(&mut rng).unwrap(),
+            0.,
+        ) * strength;
+        let offset = camera_transform.rotation * offset;
+        camera_transform.translation += offset;
+        shake.elapsed += time.delta_secs();
+        if shake.elapsed >= shake.duration {
+            script.shake = None;
+        }
+    }
     *camera_projection = Projection::Perspective(PerspectiveProjection { fov, ..default() });
 }
 
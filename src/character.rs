@@ -1,28 +1,30 @@
 use crate::{
     character::model::attach_model,
-    gameplay::damage::Health,
+    gameplay::{damage::Health, decal::EventDecals},
     item::{DeferredAttachedItem, ItemInstance},
     magicka_assets::{
         character_template::CharacterTemplate as TemplateAsset, skinned_model::AnimationLibrary,
     },
-    magicka_level_model::map_vec3,
+    magicka_level_model::{collision::CollisionMaterial, map_vec3, nav_mesh},
 };
 use avian3d::prelude::*;
-use bevy::{asset::AsAssetId, prelude::*};
+use bevy::{
+    asset::{AsAssetId, AssetPath, RecursiveDependencyLoadState},
+    prelude::*,
+};
 use remagic::xnb_readers::magicka_character::CharacterTemplate;
-use std::ffi::OsStr;
 use typed_path::PlatformPathBuf;
 
 mod agent;
 mod model;
 pub(crate) mod player;
 
-pub(crate) use agent::spawn_follower;
-pub(crate) use player::spawn_player_character;
+pub(crate) use agent::{FOLLOWER_TEMPLATE, spawn_follower};
+pub(crate) use player::{PLAYER_TEMPLATE, spawn_player_character};
 
 pub fn plugin(app: &mut App) {
     app.add_plugins((model::plugin, agent::plugin, player::plugin));
-    app.init_resource::<TemplateAssetsTable>();
+    app.add_systems(Update, spawn_pending_characters);
     app.add_systems(
         FixedUpdate,
         (character_walk, turn_to_direction).before(PhysicsSystems::First),
@@ -53,6 +55,26 @@ pub struct CharacterDesiredMovement {
     pub direction: Vec3,
 }
 
+/// What a character is standing on.
+#[derive(Component, Default, Debug, Reflect)]
+pub struct CharacterGround {
+    pub entity: Option<Entity>,
+    pub material: CollisionMaterial,
+    /// Distance moved over the ground since the last [`Footstep`].
+    stride: f32,
+}
+
+/// Triggers when a character takes a step on the ground, for footstep sounds and effects.
+#[derive(EntityEvent, Debug)]
+pub struct Footstep {
+    #[event_target]
+    pub character: Entity,
+    pub material: CollisionMaterial,
+}
+
+/// How far characters move between footsteps.
+const STRIDE_LENGTH: f32 = 1.2;
+
 #[derive(Component, Debug, Reflect)]
 pub struct CharacterAnimationState {
     pub base_animation: &'static str,
@@ -69,7 +91,7 @@ pub struct CharacterEquipment {
     pub slots: Vec<Option<Entity>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CharacterArgs {
     pub type_name: String,
     pub spawn_transform: Transform,
@@ -78,6 +100,9 @@ pub struct CharacterArgs {
     pub scene_entity: Option<Entity>,
     pub model_index: Option<usize>,
     pub start_as_agent: bool,
+    /// The type="" for triggers, if it should differ from [`type_name`] (e.g. "wizard" for players)
+    pub trigger_type_name: Option<String>,
+    pub accel: Option<f32>,
 }
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -87,90 +112,112 @@ pub enum CharacterAnchorPoint {
     Bottom,
 }
 
-// HACK: Workaround for AssetServer not permitting insert of loaded assets with path
-#[derive(Resource, Default, Debug)]
-pub struct TemplateAssetsTable {
-    handles: std::collections::HashMap<String, Handle<TemplateAsset>>,
+/// The character template to fall back to when one fails to load.
+const FALLBACK_TEMPLATE: &str = "Wizard_Detective";
+
+/// A character waiting for its template, models and items to load before it is spawned.
+#[derive(Component, Debug)]
+pub struct PendingCharacter {
+    args: CharacterArgs,
+    template: Handle<TemplateAsset>,
+}
+
+pub(crate) fn template_asset_path(template_name: &str) -> AssetPath<'static> {
+    let mut content_path: PlatformPathBuf =
+        ["Data", "Characters", template_name].iter().collect();
+    content_path.set_extension("xnb");
+    crate::magicka_assets::source::asset_path(&content_path)
 }
 
-fn get_or_load_template(
-    template_name: &str,
-    mut name_table: Mut<TemplateAssetsTable>,
-    mut templates: Mut<Assets<TemplateAsset>>,
-) -> Handle<TemplateAsset> {
-    if let Some(handle) = name_table.handles.get(template_name) {
-        return handle.clone();
-    };
+/// Spawns a character entity, which becomes a full character once its template has loaded.
+pub(crate) fn spawn_character(
+    InRef(args): InRef<CharacterArgs>,
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+) -> Result<Entity> {
+    debug!("Loading character template {:?}", args.type_name);
+    let template = assets.load(template_asset_path(&args.type_name));
 
-    debug!("Loading character template {template_name:?}");
-
-    fn read_template(template_name: &str) -> (CharacterTemplate, PlatformPathBuf) {
-        let mut content_path: PlatformPathBuf =
-            ["Data", "Characters", template_name].iter().collect();
-        content_path.set_extension("xnb");
-        let path = crate::magicka_assets::content_root()
-            .join_checked(&content_path)
-            .unwrap();
-        let path = std::path::Path::new(path.as_ref() as &OsStr);
-        let template_bytes = crate::magicka_assets::read_ignore_path_ascii_case(path).unwrap();
-
-        let template = remagic::parse_character(&template_bytes)
-            .map_err(|e| e.into_inner())
-            .unwrap()
-            .into_inner()
-            .unwrap();
-        (template, content_path)
+    let mut character = commands.spawn((
+        Name::new("Character"),
+        args.spawn_transform,
+        Visibility::default(),
+        PendingCharacter {
+            args: args.clone(),
+            template,
+        },
+    ));
+    if let Some(level_entity) = args.scene_entity {
+        character.insert(ChildOf(level_entity));
     }
 
-    // HACK: Character template parsing is not fully implemented, catch todo!/unimplemented!s and fall back to known supported template
-    let (template, content_path) = std::panic::catch_unwind(|| read_template(template_name))
-        .unwrap_or_else(|_why| {
-            error!("Reading character template {template_name:?} failed (will fall back to Wizard_Detective)");
-            read_template("Wizard_Detective")
-        });
+    Ok(character.id())
+}
 
-    let handle = templates.add(TemplateAsset {
-        template,
-        content_path,
-    });
-    name_table
-        .handles
-        .insert(template_name.to_owned(), handle.clone());
-    handle
+fn spawn_pending_characters(
+    pending_characters: Query<(Entity, &mut PendingCharacter)>,
+    templates: Res<Assets<TemplateAsset>>,
+    assets: Res<AssetServer>,
+    nav_mesh_archipelago: Option<
+        Single<Entity, With<bevy_landmass::Archipelago<bevy_landmass::coords::ThreeD>>>,
+    >,
+    mut commands: Commands,
+) {
+    let archipelago = nav_mesh_archipelago.map(|a| a.into_inner());
+    for (entity, mut pending) in pending_characters {
+        match assets.recursive_dependency_load_state(&pending.template) {
+            RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading => {
+                continue;
+            }
+            RecursiveDependencyLoadState::Loaded => {}
+            RecursiveDependencyLoadState::Failed(error) => {
+                if templates.contains(&pending.template) {
+                    // Only some models or items are missing, spawn without them
+                    warn!(
+                        "Character template {:?} loaded with missing dependencies: {error}",
+                        pending.args.type_name
+                    );
+                } else if pending.args.type_name != FALLBACK_TEMPLATE {
+                    // HACK: Character template parsing is not fully implemented, fall back to known supported template
+                    error!(
+                        "Loading character template {:?} failed (will fall back to {FALLBACK_TEMPLATE}): {error}",
+                        pending.args.type_name
+                    );
+                    pending.args.type_name = FALLBACK_TEMPLATE.to_owned();
+                    pending.template = assets.load(template_asset_path(FALLBACK_TEMPLATE));
+                    continue;
+                } else {
+                    error!("Loading fallback character template failed: {error}");
+                    commands.entity(entity).despawn();
+                    continue;
+                }
+            }
+        }
+
+        let Some(template) = templates.get(&pending.template) else {
+            continue;
+        };
+        let mut character = commands.entity(entity);
+        character.remove::<PendingCharacter>();
+        finish_character(character, &pending.args, template, archipelago);
+    }
 }
 
-pub(crate) fn spawn_character(
-    InRef(CharacterArgs {
+fn finish_character(
+    mut character: EntityCommands,
+    CharacterArgs {
         type_name: template_name,
         spawn_transform,
         spawn_anchor,
-        scene_entity: level_entity,
+        scene_entity: _,
         model_index,
         start_as_agent,
-    }): InRef<CharacterArgs>,
-    mut template_name_table: ResMut<TemplateAssetsTable>,
-    mut templates: ResMut<Assets<TemplateAsset>>,
-    mut commands: Commands,
-    model_cache: ResMut<model::ModelCache>,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<StandardMaterial>>,
-    nav_mesh_archipelago: Option<
-        Single<Entity, With<bevy_landmass::Archipelago<bevy_landmass::coords::ThreeD>>>,
-    >,
-    assets: Res<AssetServer>,
-) -> Result<Entity> {
-    let template_asset = get_or_load_template(
-        template_name,
-        template_name_table.reborrow(),
-        templates.reborrow(),
-    );
-    let TemplateAsset {
-        template,
-        content_path,
-    } = templates
-        .get(&template_asset)
-        .expect("character template handle returned by synchronous load to be loaded");
-
+        trigger_type_name,
+        accel,
+    }: &CharacterArgs,
+    template_asset: &TemplateAsset,
+    archipelago: Option<Entity>,
+) {
     let CharacterTemplate {
         id: _, // This is the type="" for triggers, unless this is for a player, where it's overridden to "wizard"
         display_id: _,
@@ -183,7 +230,9 @@ pub(crate) fn spawn_character(
         skinned_models: _,
         ref animation_sets,
         ref equipment,
-    } = *template;
+        movement_properties,
+        ref event_conditions,
+    } = template_asset.template;
 
     let model_index = model_index.unwrap_or(0); // TODO: random
 
@@ -196,10 +245,9 @@ pub(crate) fn spawn_character(
         }
     };
 
-    let mut player = commands.spawn((
-        Name::new("Character"),
+    let character_entity = character.id();
+    character.insert((
         spawn_transform,
-        Visibility::default(),
         RigidBody::Dynamic,
         Collider::capsule(radius, length),
         Mass(mass),
@@ -207,39 +255,45 @@ pub(crate) fn spawn_character(
         TransformInterpolation,
         Health::full(max_hitpoints),
         Character {
-            type_name: template_name.clone(),
+            type_name: trigger_type_name
+                .clone()
+                .unwrap_or_else(|| template_name.clone()),
             speed,
-            accel: 100.,
+            accel: accel.unwrap_or(100.),
             turn_speed,
         },
         CharacterDesiredMovement::default(),
+        CharacterGround::default(),
     ));
-    let player_entity = player.id();
-    if let Some(level_entity) = level_entity {
-        player.insert(ChildOf(*level_entity));
+    if let Some(decals) = EventDecals::from_event_conditions(event_conditions) {
+        character.insert(decals);
     }
 
-    if let Some(archipelago) = nav_mesh_archipelago {
+    if let Some(archipelago) = archipelago {
         let nav_radius = full_height * 0.5;
 
-        player.insert(
-            bevy_landmass::ArchipelagoRef::<bevy_landmass::coords::ThreeD>::new(
-                archipelago.into_inner(),
-            ),
-        );
+        character.insert(bevy_landmass::ArchipelagoRef::<
+            bevy_landmass::coords::ThreeD,
+        >::new(archipelago));
 
         if *start_as_agent {
-            player.insert((
+            character.insert((
                 bevy_landmass::Agent3d::default(),
                 bevy_landmass::AgentSettings {
                     radius: nav_radius,
                     desired_speed: speed * 0.7,
                     max_speed: speed,
                 },
+                // Without its movement properties, don't guess which ways it can't move
+                movement_properties
+                    .map(|movement| {
+                        nav_mesh::agent_type_costs(nav_mesh::MovementProperties(movement))
+                    })
+                    .unwrap_or_default(),
                 agent::FaceMoveDir,
             ));
         } else {
-            player.insert((
+            character.insert((
                 bevy_landmass::Character::<bevy_landmass::coords::ThreeD>::default(),
                 bevy_landmass::CharacterSettings { radius: nav_radius },
             ));
@@ -247,34 +301,25 @@ pub(crate) fn spawn_character(
     }
 
     let attached_model = attach_model(
-        content_path.as_path(),
-        template,
+        template_asset,
         model_index,
-        player.reborrow(),
+        character.reborrow(),
         Transform::default(),
-        model_cache.into(),
-        meshes.into(),
-        materials.into(),
-        &assets,
     );
 
     let mut equipped_slots = vec![None; 8];
 
-    for equip in equipment {
+    for (equip, item) in equipment.iter().zip(&template_asset.equipment) {
         let rot = map_vec3(equip.bind_pose_rotation_euler) + vec3(0., std::f32::consts::PI, 0.);
         let rot = Quat::from_rotation_x(rot.x)
             * Quat::from_rotation_y(rot.y)
             * Quat::from_rotation_z(rot.z);
-        let resolved_path =
-            crate::magicka_assets::resolve_relative_path(content_path, &equip.item.path);
-        let asset_path =
-            std::path::Path::new(resolved_path.resolved_path.as_ref() as &OsStr).to_owned();
-        let item_entity = player
+        let item_entity = character
             .commands_mut()
             .spawn((
-                ChildOf(player_entity),
+                ChildOf(character_entity),
                 Name::new(format!("Item Slot {}", equip.character_slot)),
-                ItemInstance(assets.load_builder().override_unapproved().load(asset_path)),
+                ItemInstance(item.clone()),
                 DeferredAttachedItem {
                     bone_name: equip.bone_name.clone(),
                     skeleton: attached_model.skeleton,
@@ -292,11 +337,11 @@ pub(crate) fn spawn_character(
         }
     }
 
-    player.insert(CharacterEquipment {
+    character.insert(CharacterEquipment {
         slots: equipped_slots,
     });
 
-    player.insert(CharacterAnimationState {
+    character.insert(CharacterAnimationState {
         base_animation: "idle",
         force_animation: None,
         animation_skeleton: attached_model.skeleton,
@@ -305,8 +350,6 @@ pub(crate) fn spawn_character(
         // HACK: The character should really refer to the character template with an asset handle instead
         animation_sets: animation_sets.clone(),
     });
-
-    Ok(player_entity)
 }
 
 fn select_animation(
@@ -449,34 +492,68 @@ fn character_walk(
         Entity,
         &Character,
         &CharacterDesiredMovement,
+        &ColliderAabb,
         &mut LinearVelocity,
+        &mut CharacterGround,
     )>,
+    grounds: Query<(&RigidBody, &Position, &LinearVelocity, &AngularVelocity), Without<Character>>,
+    colliders: Query<(Option<&ColliderOf>, Option<&CollisionMaterial>)>,
+    spatial_query: SpatialQuery,
     time: Res<Time>,
-    gravity: Res<Gravity>,
-    // collisions: Collisions,
+    mut commands: Commands,
 ) {
-    let up_dir = -gravity.0.normalize_or_zero();
-    for (char_ent, char, movement, mut velocity) in characters {
-        // TODO: Target velocity should be our desired velocity plus the velocity of what we're standing on
-        /*
-        for manifold in collisions
-            .collisions_with(char_ent)
-            .flat_map(|pair| pair.manifolds.iter().map(|m| (pair, m)))
-            .max_by(|(_, manifold1), (_, manifold2)| {
-                manifold1
-                    .normal
-                    .dot(up_dir)
-                    .partial_cmp(manifold2.normal.up_dir)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
-        {
-            let relative_velocity =
-        }
-        */
-        let target_velocity = movement.movement * char.speed;
+    /// How far below the feet the ground is looked for.
+    const GROUND_DISTANCE: f32 = 0.2;
+
+    for (char_ent, char, movement, aabb, mut velocity, mut ground) in characters {
+        let feet = Vec3::new(
+            (aabb.min.x + aabb.max.x) * 0.5,
+            aabb.min.y + GROUND_DISTANCE,
+            (aabb.min.z + aabb.max.z) * 0.5,
+        );
+        let filter = SpatialQueryFilter::from_mask(crate::magicka_level_model::Layers::Level)
+            .with_excluded_entities([char_ent]);
+        let hit = spatial_query.cast_ray(feet, Dir3::NEG_Y, GROUND_DISTANCE * 2., false, &filter);
+
+        let (ground_material, ground_velocity) = match hit {
+            Some(hit) => {
+                let (collider_of, material) = colliders.get(hit.entity).unwrap_or_default();
+                let body = collider_of.map_or(hit.entity, |c| c.body);
+                // Move along with what we're standing on, like the moving parts of a level
+                let point = feet + Vec3::NEG_Y * hit.distance;
+                let ground_velocity = grounds
+                    .get(body)
+                    .ok()
+                    .filter(|(rigid_body, ..)| rigid_body.is_kinematic())
+                    .map(|(_, position, linear, angular)| {
+                        linear.0 + angular.0.cross(point - position.0)
+                    })
+                    .unwrap_or_default();
+                (material.copied().unwrap_or_default(), ground_velocity)
+            }
+            None => (CollisionMaterial::default(), Vec3::ZERO),
+        };
+        ground.entity = hit.map(|hit| hit.entity);
+        ground.material = ground_material;
+
+        let target_velocity =
+            movement.movement * char.speed * ground_material.speed_multiplier() + ground_velocity;
+        let accel = char.accel * ground_material.traction();
         let target_velocity = **velocity
-            + (target_velocity - **velocity).clamp_length_max(char.accel * time.delta_secs());
+            + (target_velocity - **velocity).clamp_length_max(accel * time.delta_secs());
         velocity.x = target_velocity.x;
         velocity.z = target_velocity.z;
+
+        if ground.entity.is_some() {
+            let relative_velocity = (**velocity - ground_velocity).with_y(0.);
+            ground.stride += relative_velocity.length() * time.delta_secs();
+            if ground.stride >= STRIDE_LENGTH {
+                ground.stride = 0.;
+                commands.trigger(Footstep {
+                    character: char_ent,
+                    material: ground_material,
+                });
+            }
+        }
     }
 }
@@ -18,6 +18,8 @@ pub fn plugin(app: &mut App) {
     app.add_systems(FixedUpdate, face_move_dir.before(turn_to_direction));
 }
 
+pub(crate) const FOLLOWER_TEMPLATE: &str = "Wizard_Reddit";
+
 pub fn spawn_follower(
     (In(level_entity), InRef(spawn_point_basename), In(target_entity)): (
         In<Option<Entity>>,
@@ -39,19 +41,17 @@ pub fn spawn_follower(
     let follower = world.run_system_cached_with::<_, Result<_>, _, _>(
         spawn_character,
         &CharacterArgs {
-            type_name: "Wizard_Reddit".to_owned(),
+            type_name: FOLLOWER_TEMPLATE.to_owned(),
             spawn_transform,
             spawn_anchor: default(),
             scene_entity: level_entity,
             model_index: None,
             start_as_agent: true,
+            trigger_type_name: None,
+            accel: Some(12.),
         },
     )??;
 
-    let mut character_world = world.entity_mut(follower);
-    let mut character_component = character_world.get_mut::<Character>().unwrap();
-    character_component.accel = 12.;
-
     world.entity_mut(follower).insert((
         Name::new("Follower"),
         super::player::PlayerCharacter {
@@ -1,7 +1,7 @@
 use crate::{
     camera::{CameraGroupMember, PointerRay, project_pointer_camera_rays},
     character::{
-        Character, CharacterArgs, CharacterDesiredMovement, spawn_character, turn_to_direction,
+        CharacterArgs, CharacterDesiredMovement, spawn_character, turn_to_direction,
     },
     magicka_level_model::Locator,
 };
@@ -22,6 +22,8 @@ pub fn plugin(app: &mut App) {
     app.add_systems(FixedUpdate, face_pointer_ray.before(turn_to_direction));
 }
 
+pub(crate) const PLAYER_TEMPLATE: &str = "Wizard";
+
 #[derive(Component, Debug)]
 pub struct PlayerCharacter {
     pub index: u8,
@@ -44,23 +46,23 @@ pub fn spawn_player_character(
     let player_entity = world.run_system_cached_with::<_, Result<_>, _, _>(
         spawn_character,
         &CharacterArgs {
-            type_name: "Wizard".to_owned(),
+            type_name: PLAYER_TEMPLATE.to_owned(),
             spawn_transform,
             spawn_anchor: default(),
             scene_entity: level_entity,
             model_index: None,
             start_as_agent: false,
+            trigger_type_name: Some("wizard".to_owned()), // For scene triggers
+            accel: None,
         },
     )??;
 
-    let mut character_world = world.entity_mut(player_entity);
-    let mut character_component = character_world.get_mut::<Character>().unwrap();
-    character_component.type_name = "wizard".to_string(); // For scene triggers
-
+    let keybinds = &world.resource::<crate::settings::Settings>().keybinds;
+    let bindings = crate::spelling::bindings_m1(keybinds);
     world.entity_mut(player_entity).insert((
         Name::new(format!("Player {player_index}")),
         crate::spelling::bundle_m1(),
-        crate::spelling::bindings_m1(),
+        bindings,
         CameraGroupMember,
         PlayerCharacter {
             index: player_index,
@@ -92,18 +94,20 @@ pub(super) fn find_spawn_transform(
 fn player_walk(
     players: Query<&mut CharacterDesiredMovement, With<crate::PlayerControlled>>,
     kb_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<crate::settings::Settings>,
 ) {
+    let keybinds = &settings.keybinds;
     let mut direction = Vec2::ZERO;
-    if kb_input.pressed(KeyCode::KeyW) {
+    if kb_input.pressed(keybinds.move_up) {
         direction.y -= 1.;
     }
-    if kb_input.pressed(KeyCode::KeyS) {
+    if kb_input.pressed(keybinds.move_down) {
         direction.y += 1.;
     }
-    if kb_input.pressed(KeyCode::KeyA) {
+    if kb_input.pressed(keybinds.move_left) {
         direction.x -= 1.;
     }
-    if kb_input.pressed(KeyCode::KeyD) {
+    if kb_input.pressed(keybinds.move_right) {
         direction.x += 1.;
     }
 
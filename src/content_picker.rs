@@ -0,0 +1,165 @@
+//! Asks the player to locate Magicka when its Content directory can't be found,
+//! on first run or when the configured one stops being valid.
+
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use typed_path::PlatformPathBuf;
+
+use crate::{magicka_assets, settings::Settings};
+
+/// Whether content can be loaded yet. Anything loading content should wait for
+/// [`ContentDirState::Ready`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, States, Debug)]
+pub(crate) enum ContentDirState {
+    #[default]
+    Picking,
+    Ready,
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(ContentDirState::Picking), spawn_picker);
+    app.add_systems(
+        Update,
+        (receive_picked_folder, receive_dropped_folder).run_if(in_state(ContentDirState::Picking)),
+    );
+}
+
+/// The folder dialog opened with the browse button.
+#[derive(Resource)]
+struct FolderPick(Task<Option<PathBuf>>);
+
+#[derive(Component)]
+struct PickerStatus;
+
+fn spawn_picker(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Content Directory Picker"),
+            DespawnOnExit(ContentDirState::Picking),
+            Node {
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: px(16),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Locate Magicka"), TextFont::from_font_size(32.)));
+            parent.spawn(Text::new(
+                "Content is loaded from your copy of Magicka, which couldn't be found.\n\
+                Select the Content folder of your Magicka install, or drop it onto this window.",
+            ));
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(px(16), px(8)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.25, 0.3)),
+                    children![Text::new("Browse…")],
+                ))
+                .observe(open_folder_dialog);
+            parent.spawn((
+                PickerStatus,
+                Text::default(),
+                TextColor(Color::srgb(1., 0.4, 0.4)),
+            ));
+        });
+}
+
+fn open_folder_dialog(
+    _click: On<Pointer<Click>>,
+    mut commands: Commands,
+    pick: Option<Res<FolderPick>>,
+) {
+    if pick.is_some() {
+        return;
+    }
+    let task = AsyncComputeTaskPool::get().spawn(async {
+        rfd::AsyncFileDialog::new()
+            .set_title("Select the Magicka Content folder")
+            .pick_folder()
+            .await
+            .map(|folder| folder.path().to_owned())
+    });
+    commands.insert_resource(FolderPick(task));
+}
+
+fn receive_picked_folder(
+    mut commands: Commands,
+    pick: Option<ResMut<FolderPick>>,
+    settings: ResMut<Settings>,
+    status: Single<&mut Text, With<PickerStatus>>,
+    next_state: ResMut<NextState<ContentDirState>>,
+) {
+    let Some(mut pick) = pick else {
+        return;
+    };
+    let Some(folder) = block_on(future::poll_once(&mut pick.0)) else {
+        return;
+    };
+    commands.remove_resource::<FolderPick>();
+    if let Some(folder) = folder {
+        try_use_folder(&folder, settings, status, next_state);
+    }
+}
+
+fn receive_dropped_folder(
+    mut drops: MessageReader<FileDragAndDrop>,
+    settings: ResMut<Settings>,
+    status: Single<&mut Text, With<PickerStatus>>,
+    next_state: ResMut<NextState<ContentDirState>>,
+) {
+    let Some(folder) = drops.read().find_map(|drop| match drop {
+        FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.clone()),
+        _ => None,
+    }) else {
+        return;
+    };
+    try_use_folder(&folder, settings, status, next_state);
+}
+
+fn try_use_folder(
+    folder: &Path,
+    mut settings: ResMut<Settings>,
+    mut status: Single<&mut Text, With<PickerStatus>>,
+    mut next_state: ResMut<NextState<ContentDirState>>,
+) {
+    // Accept the install folder too, it's an easy mistake to make
+    let content_dir = if magicka_assets::validate_content_dir(folder).is_err()
+        && let Ok(content_dir) = magicka_assets::find_path_ignore_ascii_case(&folder.join("Content"))
+        && magicka_assets::validate_content_dir(&content_dir).is_ok()
+    {
+        content_dir
+    } else {
+        folder.to_owned()
+    };
+
+    if let Err(e) = magicka_assets::validate_content_dir(&content_dir) {
+        status.0 = e.to_string();
+        return;
+    }
+    let Ok(platform_dir) = PlatformPathBuf::try_from(content_dir.clone()) else {
+        status.0 = format!("{content_dir:?} is not a valid path");
+        return;
+    };
+    if magicka_assets::set_content_root(platform_dir).is_err() {
+        // Content has already been loaded from elsewhere, changing it needs a restart
+        status.0 = "The Content folder is already set, restart to change it".to_owned();
+        return;
+    }
+
+    settings.content_dir = Some(content_dir);
+    if let Err(e) = settings.save() {
+        warn!("Failed to remember the Magicka Content directory: {e}");
+    }
+    next_state.set(ContentDirState::Ready);
+}
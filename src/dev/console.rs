@@ -8,7 +8,11 @@ use bevy_console::{
 };
 
 use crate::camera::PointerRay;
-use crate::magicka_level_model::Layers;
+use crate::magicka_level_model::{
+    Layers, LevelInstance,
+    collision::{CollisionMode, LevelCollisionModes, level_name},
+};
+use crate::script_triggers::variables::{SceneVariables, VariableScope, VariableValue};
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(ConsolePlugin)
@@ -20,7 +24,9 @@ pub fn plugin(app: &mut App) {
         })
         .add_console_command::<SceneCommand, _>(scene_command)
         .add_console_command::<TriggerCommand, _>(trigger_command)
-        .add_console_command::<SpawnCharacterCommand, _>(spawn_character_command);
+        .add_console_command::<SpawnCharacterCommand, _>(spawn_character_command)
+        .add_console_command::<CollisionCommand, _>(collision_command)
+        .add_console_command::<VariableCommand, _>(variable_command);
 }
 
 /// Load a scene.
@@ -132,9 +138,110 @@ fn spawn_character_command(
                     scene_entity: None,
                     model_index: None,
                     start_as_agent: true,
+                    trigger_type_name: None,
+                    accel: None,
                 },
             )
             .unwrap()
             .unwrap();
     });
 }
+
+/// Switch the spawned levels between one-sided and double-sided collision, to compare them.
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "collision")]
+struct CollisionCommand {
+    /// Use one-sided collision instead of double-sided.
+    #[arg(long)]
+    one_sided: bool,
+
+    /// Change the default for all levels, instead of just the spawned ones.
+    #[arg(long)]
+    all: bool,
+}
+
+fn collision_command(
+    mut command: ConsoleCommand<CollisionCommand>,
+    levels: Query<&LevelInstance>,
+    mut modes: ResMut<LevelCollisionModes>,
+) {
+    let Some(Ok(CollisionCommand { one_sided, all })) = command.take() else {
+        return;
+    };
+    let mode = if one_sided {
+        CollisionMode::OneSided
+    } else {
+        CollisionMode::DoubleSided
+    };
+    if all {
+        modes.default = mode;
+        modes.levels.clear();
+        reply_ok!(command, "All levels use {mode:?} collision");
+        return;
+    }
+    let names: Vec<_> = levels
+        .iter()
+        .filter_map(|level| level.level.path().map(level_name))
+        .collect();
+    if names.is_empty() {
+        command.reply_failed("No level is spawned");
+        return;
+    }
+    reply_ok!(command, "{} use {mode:?} collision", names.join(", "));
+    for name in names {
+        modes.levels.insert(name, mode);
+    }
+}
+
+/// Show or set scene variables.
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "var")]
+struct VariableCommand {
+    /// The name of the variable. If omitted, shows all of them.
+    name: Option<String>,
+
+    /// The value to set it to, like 3, 0.5 or true. If omitted, shows it.
+    value: Option<String>,
+
+    /// Keep the variable after leaving the level.
+    #[arg(long)]
+    global: bool,
+}
+
+fn variable_command(
+    mut command: ConsoleCommand<VariableCommand>,
+    mut variables: ResMut<SceneVariables>,
+) {
+    let Some(Ok(VariableCommand {
+        name,
+        value,
+        global,
+    })) = command.take()
+    else {
+        return;
+    };
+    let Some(name) = name else {
+        let mut any = false;
+        for (name, value, scope) in variables.iter() {
+            reply_ok!(command, "{name} = {value} ({scope:?})");
+            any = true;
+        }
+        if !any {
+            reply_ok!(command, "No variables are set");
+        }
+        return;
+    };
+    let Some(value) = value else {
+        match (variables.get(&name), variables.scope(&name)) {
+            (Some(value), Some(scope)) => reply_ok!(command, "{name} = {value} ({scope:?})"),
+            _ => reply_ok!(command, "{name} isn't set"),
+        }
+        return;
+    };
+    let Some(value) = VariableValue::parse(&value) else {
+        command.reply_failed("Can't parse value, expected an integer, a float, true or false");
+        return;
+    };
+    variables.set(&name, value, global.then_some(VariableScope::Global));
+    reply_ok!(command, "{name} = {value}");
+}
@@ -1 +1,61 @@
 // Once you can start dialogs, update script_triggers::condition::dialog_done
+
+use bevy::prelude::*;
+
+pub fn plugin(app: &mut App) {
+    app.add_observer(show_caption);
+    app.add_systems(Update, expire_captions);
+}
+
+/// Triggers to show a line of text at the bottom of the screen for a while, replacing any shown
+/// before.
+#[derive(Event, Debug, Clone)]
+pub struct ShowCaption {
+    pub text: String,
+    /// In seconds.
+    pub duration: f32,
+}
+
+#[derive(Component, Debug)]
+struct Caption(Timer);
+
+fn show_caption(
+    event: On<ShowCaption>,
+    captions: Query<Entity, With<Caption>>,
+    mut commands: Commands,
+) {
+    for caption in &captions {
+        commands.entity(caption).despawn();
+    }
+    commands.spawn((
+        Name::new("Caption"),
+        Caption(Timer::from_seconds(event.duration, TimerMode::Once)),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            bottom: percent(12),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            Node {
+                padding: UiRect::axes(px(12), px(6)),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.6)),
+            children![Text::new(event.text.clone())],
+        )],
+    ));
+}
+
+fn expire_captions(
+    captions: Query<(Entity, &mut Caption)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut caption) in captions {
+        if caption.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
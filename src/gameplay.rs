@@ -1,9 +1,13 @@
 use bevy::prelude::*;
 
 pub mod damage;
+pub mod decal;
 pub mod damage_over_time;
+pub mod liquid;
 
 pub fn plugin(app: &mut App) {
     app.add_plugins(damage::plugin);
+    app.add_plugins(decal::plugin);
     app.add_plugins(damage_over_time::plugin);
+    app.add_plugins(liquid::plugin);
 }
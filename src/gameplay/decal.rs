@@ -0,0 +1,436 @@
+//! Marks projected onto the level, like blood, scorch marks and frost, which fade away after a
+//! while.
+
+use crate::{
+    character::Footstep,
+    gameplay::damage::{Damage, Die},
+    magicka_assets::source::asset_path,
+    magicka_level_model::{Layers, collision::CollisionMaterial},
+    scene::Scene,
+    settings::Settings,
+    spelling::element::{Element, Magnitudes},
+};
+use avian3d::prelude::{SpatialQuery, SpatialQueryFilter};
+use bevy::{
+    math::{Affine2, vec2},
+    pbr::decal::{ForwardDecal, ForwardDecalMaterial, ForwardDecalMaterialExt},
+    prelude::*,
+};
+use remagic::xnb_readers::magicka_common::{
+    DecalEvent, EventCollection, EventCondition, EventStorage,
+};
+use std::collections::VecDeque;
+use typed_path::PlatformPathBuf;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LiveDecals>();
+    app.add_observer(spawn_decal);
+    app.add_observer(decals_on_damage);
+    app.add_observer(decals_on_death);
+    app.add_observer(footprints);
+    app.add_systems(Update, fade_decals);
+}
+
+/// How long decals take to fade out at the end of their lifetime, in seconds.
+const FADE_TIME: f32 = 2.;
+
+/// How far above and below a point to look for ground to put a decal on.
+const GROUND_SEARCH_DISTANCE: f32 = 3.;
+
+/// How much decals fade out where they meet geometry at an angle, lower is softer.
+const DEPTH_FADE_FACTOR: f32 = 8.;
+
+const FOOTPRINT_SIZE: f32 = 0.4;
+
+/// Magicka's decals are tiles of one texture atlas, with a row for each value of its decal enum
+/// and a column for each variant of a decal. The atlas's size in tiles hasn't been checked against
+/// Content yet.
+const ATLAS_ROWS: u32 = 8;
+const ATLAS_COLUMNS: u32 = 8;
+
+/// The decal texture atlas in Content.
+fn atlas_path() -> PlatformPathBuf {
+    let mut path: PlatformPathBuf = ["EffectTextures", "Decals"].iter().collect();
+    path.set_extension("xnb");
+    path
+}
+
+/// A value of Magicka's decal enum, the decal's row in the atlas.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect)]
+pub struct DecalKind(pub u32);
+
+impl DecalKind {
+    // The decals spawned by spells and footsteps rather than by content events. Which rows of the
+    // atlas they are hasn't been checked against Content yet.
+    pub const SCORCH: Self = Self(1);
+    pub const FROST: Self = Self(2);
+    pub const CRATER: Self = Self(3);
+    pub const FOOTPRINT: Self = Self(4);
+
+    /// The part of the atlas with the decal's `variant`, as a transform of its UVs, if the atlas
+    /// has it.
+    fn atlas_uv_transform(self, variant: u32) -> Option<Affine2> {
+        if self.0 >= ATLAS_ROWS || variant >= ATLAS_COLUMNS {
+            return None;
+        }
+        let tile_size = vec2(1. / ATLAS_COLUMNS as f32, 1. / ATLAS_ROWS as f32);
+        Some(Affine2::from_scale_angle_translation(
+            tile_size,
+            0.,
+            vec2(variant as f32, self.0 as f32) * tile_size,
+        ))
+    }
+
+    /// How long the decal stays, including fading out, in seconds.
+    fn lifetime(self) -> f32 {
+        if self == Self::FOOTPRINT { 6. } else { 30. }
+    }
+}
+
+/// The decal a spell leaves where it hits the level, if any.
+pub fn impact_decal_kind(elements: &Magnitudes) -> Option<DecalKind> {
+    if elements.contains(Element::Earth) {
+        Some(DecalKind::CRATER)
+    } else if elements.contains(Element::Fire) || elements.contains(Element::Lightning) {
+        Some(DecalKind::SCORCH)
+    } else if elements.contains(Element::Cold) || elements.contains(Element::Ice) {
+        Some(DecalKind::FROST)
+    } else {
+        None
+    }
+}
+
+/// Triggers to project a decal onto whatever is around [`position`](Self::position).
+#[derive(Event, Debug, Clone)]
+pub struct SpawnDecal {
+    pub kind: DecalKind,
+    /// Which variant of the kind to use, its column in the atlas.
+    pub index: u32,
+    pub position: Vec3,
+    /// The surface to project onto faces this way.
+    pub normal: Dir3,
+    /// Width in world units.
+    pub size: f32,
+}
+
+impl SpawnDecal {
+    /// A decal on the level ground below `position`, if there is any close enough.
+    pub fn on_ground(
+        spatial_query: &SpatialQuery,
+        kind: DecalKind,
+        index: u32,
+        position: Vec3,
+        size: f32,
+    ) -> Option<Self> {
+        let origin = position + Vec3::Y * GROUND_SEARCH_DISTANCE;
+        let hit = spatial_query.cast_ray(
+            origin,
+            Dir3::NEG_Y,
+            2. * GROUND_SEARCH_DISTANCE,
+            true,
+            &SpatialQueryFilter::from_mask(Layers::Level),
+        )?;
+        Some(Self {
+            kind,
+            index,
+            position: origin - Vec3::Y * hit.distance,
+            normal: Dir3::new(hit.normal).unwrap_or(Dir3::Y),
+            size,
+        })
+    }
+}
+
+/// A spawned decal.
+#[derive(Component, Debug, Reflect)]
+pub struct Decal {
+    pub kind: DecalKind,
+    age: f32,
+}
+
+/// Live decals, oldest first, to remove the oldest once there are too many.
+#[derive(Resource, Default, Debug)]
+struct LiveDecals(VecDeque<Entity>);
+
+/// The decals a character leaves from its template's events.
+#[derive(Component, Default, Debug)]
+pub struct EventDecals {
+    pub damaged: Vec<DecalEvent>,
+    pub killed: Vec<DecalEvent>,
+}
+
+impl EventDecals {
+    /// Collects the decals spawned by `event_conditions`, if there are any.
+    pub fn from_event_conditions(event_conditions: &[EventCollection]) -> Option<Self> {
+        let mut decals = Self::default();
+        for collection in event_conditions {
+            let condition = collection.condition.condition_type;
+            let events = collection
+                .event_storages
+                .iter()
+                .filter_map(|event| match event {
+                    EventStorage::SpawnDecal(decal) => Some(*decal),
+                    EventStorage::Other => None,
+                });
+            for decal in events {
+                if condition & EventCondition::DAMAGED != 0 {
+                    decals.damaged.push(decal);
+                }
+                if condition & (EventCondition::KILLED | EventCondition::OVERKILLED) != 0 {
+                    decals.killed.push(decal);
+                }
+            }
+        }
+        (!decals.damaged.is_empty() || !decals.killed.is_empty()).then_some(decals)
+    }
+}
+
+fn spawn_decal(
+    event: On<SpawnDecal>,
+    settings: Res<Settings>,
+    assets: Res<AssetServer>,
+    scenes: Query<Entity, With<Scene>>,
+    mut atlas: Local<Option<Handle<Image>>>,
+    mut materials: ResMut<Assets<ForwardDecalMaterial<StandardMaterial>>>,
+    mut live_decals: ResMut<LiveDecals>,
+    mut commands: Commands,
+) {
+    use rand::distr::uniform::SampleRange as _;
+
+    let SpawnDecal {
+        kind,
+        index,
+        position,
+        normal,
+        size,
+    } = *event;
+
+    let Some(uv_transform) = kind.atlas_uv_transform(index) else {
+        debug!("decal {kind:?} variant {index} is outside of the decal atlas");
+        return;
+    };
+
+    let limit = settings.graphics.max_decals;
+    if limit == 0 {
+        return;
+    }
+    while live_decals.0.len() >= limit
+        && let Some(oldest) = live_decals.0.pop_front()
+    {
+        commands.entity(oldest).try_despawn();
+    }
+
+    let texture = atlas
+        .get_or_insert_with(|| assets.load(asset_path(&atlas_path())))
+        .clone();
+    // Each decal has its own material to fade out on its own
+    let material = materials.add(ForwardDecalMaterial {
+        base: StandardMaterial {
+            base_color_texture: Some(texture),
+            uv_transform,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        },
+        extension: ForwardDecalMaterialExt {
+            depth_fade_factor: DEPTH_FADE_FACTOR,
+        },
+    });
+
+    let mut rng: rand::rngs::SmallRng = rand::make_rng();
+    let spin = (0.0..std::f32::consts::TAU)
+        .sample_single(&mut rng)
+        .unwrap();
+    let mut decal_commands = commands.spawn((
+        Name::new(format!("Decal - {kind:?}")),
+        Decal { kind, age: 0. },
+        ForwardDecal,
+        MeshMaterial3d(material),
+        Transform {
+            translation: position,
+            rotation: Quat::from_rotation_arc(Vec3::Y, *normal) * Quat::from_rotation_y(spin),
+            scale: Vec3::splat(size),
+        },
+    ));
+    // Gone with the scene
+    if let Some(scene) = scenes.iter().next() {
+        decal_commands.insert(ChildOf(scene));
+    }
+    live_decals.0.push_back(decal_commands.id());
+}
+
+fn fade_decals(
+    mut decals: Query<(
+        Entity,
+        &mut Decal,
+        &MeshMaterial3d<ForwardDecalMaterial<StandardMaterial>>,
+    )>,
+    mut materials: ResMut<Assets<ForwardDecalMaterial<StandardMaterial>>>,
+    mut live_decals: ResMut<LiveDecals>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut decal, material) in &mut decals {
+        decal.age += time.delta_secs();
+        let remaining = decal.kind.lifetime() - decal.age;
+        if remaining <= 0. {
+            commands.entity(entity).despawn();
+        } else if remaining < FADE_TIME
+            && let Some(mut material) = materials.get_mut(&material.0)
+        {
+            material.base.base_color.set_alpha(remaining / FADE_TIME);
+        }
+    }
+    // Forget decals despawned with their scene
+    live_decals.0.retain(|&decal| decals.contains(decal));
+}
+
+fn spawn_event_decals(
+    decals: &[DecalEvent],
+    position: Vec3,
+    spatial_query: &SpatialQuery,
+    commands: &mut Commands,
+) {
+    for decal in decals {
+        let Ok(kind) = u32::try_from(decal.decal_type).map(DecalKind) else {
+            debug!("invalid decal type {}", decal.decal_type);
+            continue;
+        };
+        let Ok(index) = u32::try_from(decal.index) else {
+            debug!("invalid decal index {}", decal.index);
+            continue;
+        };
+        let size = decal.scale as f32 / 100.;
+        if let Some(spawn) = SpawnDecal::on_ground(spatial_query, kind, index, position, size) {
+            commands.trigger(spawn);
+        }
+    }
+}
+
+fn decals_on_damage(
+    event: On<Damage>,
+    characters: Query<(&EventDecals, &GlobalTransform)>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    if event.damage.silent || event.damage.amount <= 0. {
+        return;
+    }
+    let Ok((decals, transform)) = characters.get(event.recipient) else {
+        return;
+    };
+    spawn_event_decals(
+        &decals.damaged,
+        transform.translation(),
+        &spatial_query,
+        &mut commands,
+    );
+}
+
+fn decals_on_death(
+    event: On<Die>,
+    characters: Query<(&EventDecals, &GlobalTransform)>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    let Ok((decals, transform)) = characters.get(event.subject) else {
+        return;
+    };
+    spawn_event_decals(
+        &decals.killed,
+        transform.translation(),
+        &spatial_query,
+        &mut commands,
+    );
+}
+
+/// Leaves footprints in soft ground.
+fn footprints(
+    event: On<Footstep>,
+    characters: Query<&GlobalTransform>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    if !matches!(
+        event.material,
+        CollisionMaterial::Snow | CollisionMaterial::Mud
+    ) {
+        return;
+    }
+    let Ok(transform) = characters.get(event.character) else {
+        return;
+    };
+    if let Some(spawn) = SpawnDecal::on_ground(
+        &spatial_query,
+        DecalKind::FOOTPRINT,
+        0,
+        transform.translation(),
+        FOOTPRINT_SIZE,
+    ) {
+        commands.trigger(spawn);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DecalKind, EventDecals};
+    use std::path::Path;
+
+    /// The decal atlas is in Content, and the decal events of the character templates in Content
+    /// are all tiles of it.
+    #[test]
+    #[ignore = "needs Magicka Content"]
+    fn content_decal_events_in_atlas() {
+        assert!(
+            crate::magicka_assets::init_content_root(None),
+            "Magicka Content not found"
+        );
+        let content = Path::new(crate::magicka_assets::content_root().as_ref() as &std::ffi::OsStr);
+        crate::magicka_assets::find_path_ignore_ascii_case(
+            &content.join("EffectTextures").join("Decals.xnb"),
+        )
+        .expect("decal atlas not found");
+
+        let characters = crate::magicka_assets::find_path_ignore_ascii_case(
+            &content.join("Data").join("Characters"),
+        )
+        .unwrap();
+        let mut decals = Vec::new();
+        for entry in std::fs::read_dir(characters).unwrap() {
+            let path = entry.unwrap().path();
+            if !path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("xnb"))
+            {
+                continue;
+            }
+            let bytes = std::fs::read(&path).unwrap();
+            let Ok(asset) = remagic::parse_character(&bytes) else {
+                continue;
+            };
+            let Some(template) = asset.inner() else {
+                continue;
+            };
+            if let Some(events) = EventDecals::from_event_conditions(&template.event_conditions) {
+                decals.extend(
+                    events
+                        .damaged
+                        .into_iter()
+                        .chain(events.killed)
+                        .map(|d| (path.clone(), d)),
+                );
+            }
+        }
+
+        assert!(!decals.is_empty(), "no character spawns decals");
+        for (path, decal) in decals {
+            let tile = u32::try_from(decal.decal_type)
+                .ok()
+                .zip(u32::try_from(decal.index).ok())
+                .and_then(|(row, column)| DecalKind(row).atlas_uv_transform(column));
+            assert!(
+                tile.is_some(),
+                "{}: {decal:?} isn't in the atlas",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! What happens to characters in liquids, and to liquids hit by cold.
+
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    character::Character,
+    gameplay::damage::{Damage, DamagePayload, DamageType, Health},
+    magicka_level_model::{
+        Layers,
+        collision::CollisionMaterial,
+        liquid::{Liquid, LiquidKind},
+    },
+    spelling::{
+        element::Element,
+        spells::spray::{self, SpraySpell},
+        status::Wet,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<InLiquid>();
+    app.register_type::<Drying>();
+    app.register_type::<Drowning>();
+    app.add_systems(Startup, init_ice_assets);
+    app.add_systems(
+        FixedUpdate,
+        (
+            find_liquid_depth,
+            (wet_characters, drown_characters, burn_characters_in_lava),
+            freeze_liquids,
+            thaw_ice,
+        )
+            .chain(),
+    );
+}
+
+/// How far below its feet a character can be found in a liquid.
+const MAX_DEPTH: f32 = 10.;
+
+/// How long characters stay wet after leaving water.
+const WET_DURATION: Duration = Duration::from_secs(5);
+
+/// How much of a character must be under water before it starts to drown.
+const DROWN_DEPTH_FRACTION: f32 = 0.75;
+
+/// How long a character can be under water before it drowns.
+const DROWN_DURATION: Duration = Duration::from_secs(1);
+
+const LAVA_DAMAGE_PER_SECOND: f32 = 500.;
+
+/// How long ice lasts before it thaws.
+const ICE_DURATION: Duration = Duration::from_secs(10);
+
+const ICE_PATCH_RADIUS: f32 = 1.;
+
+const ICE_PATCH_THICKNESS: f32 = 0.2;
+
+/// The liquid a character is standing in.
+#[derive(Component, Debug, Reflect)]
+pub struct InLiquid {
+    pub liquid: Entity,
+    pub kind: LiquidKind,
+    /// How far the surface is above the character's feet.
+    pub depth: f32,
+}
+
+/// A [`Wet`] character out of water, which dries when the timer finishes.
+#[derive(Component, Debug, Reflect)]
+pub struct Drying {
+    pub timer: Timer,
+}
+
+/// A character under water, which drowns when the timer finishes.
+#[derive(Component, Debug, Reflect)]
+pub struct Drowning {
+    pub timer: Timer,
+}
+
+/// Ice on a liquid that can be walked on until it thaws. Either a patch frozen by a spell, or
+/// covering the whole of a liquid that freezes all at once.
+#[derive(Component, Debug)]
+pub struct Ice {
+    pub thaw: Timer,
+}
+
+/// The ice covering a whole liquid, when it has frozen all at once.
+#[derive(Component, Debug)]
+#[relationship(relationship_target = FrozenOver)]
+struct IceCover(Entity);
+
+#[derive(Component, Debug)]
+#[relationship_target(relationship = IceCover)]
+struct FrozenOver(Entity);
+
+#[derive(Resource)]
+struct IceAssets {
+    patch_mesh: Handle<Mesh>,
+    fallback_material: Handle<StandardMaterial>,
+}
+
+fn init_ice_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(IceAssets {
+        patch_mesh: meshes.add(Cylinder::new(ICE_PATCH_RADIUS, ICE_PATCH_THICKNESS)),
+        fallback_material: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.8, 0.9, 1.),
+            perceptual_roughness: 0.2,
+            ..default()
+        }),
+    });
+}
+
+fn find_liquid_depth(
+    characters: Query<(Entity, &ColliderAabb, Option<&mut InLiquid>), With<Character>>,
+    liquids: Query<&Liquid>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    let filter = SpatialQueryFilter::from_mask(Layers::Liquid);
+    for (character, aabb, in_liquid) in characters {
+        // Liquid meshes are just a surface, look for it above the feet
+        let feet = Vec3::new(
+            (aabb.min.x + aabb.max.x) * 0.5,
+            aabb.min.y,
+            (aabb.min.z + aabb.max.z) * 0.5,
+        );
+        let hit = spatial_query
+            .cast_ray(feet, Dir3::Y, MAX_DEPTH, false, &filter)
+            .and_then(|hit| Some((hit, liquids.get(hit.entity).ok()?)));
+        match (hit, in_liquid) {
+            (Some((hit, liquid)), Some(mut in_liquid)) => {
+                in_liquid.liquid = hit.entity;
+                in_liquid.kind = liquid.kind;
+                in_liquid.depth = hit.distance;
+            }
+            (Some((hit, liquid)), None) => {
+                commands.entity(character).insert(InLiquid {
+                    liquid: hit.entity,
+                    kind: liquid.kind,
+                    depth: hit.distance,
+                });
+            }
+            (None, Some(_)) => {
+                commands.entity(character).remove::<InLiquid>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn wet_characters(
+    characters: Query<(Entity, Option<&InLiquid>, Has<Wet>, Option<&mut Drying>), With<Character>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (character, in_liquid, wet, drying) in characters {
+        let in_water = in_liquid.is_some_and(|in_liquid| in_liquid.kind == LiquidKind::Water);
+        if in_water {
+            if !wet {
+                commands.entity(character).insert(Wet);
+            }
+            if drying.is_some() {
+                commands.entity(character).remove::<Drying>();
+            }
+        } else if let Some(mut drying) = drying {
+            if drying.timer.tick(time.delta()).just_finished() {
+                commands.entity(character).remove::<(Wet, Drying)>();
+            }
+        } else if wet {
+            commands.entity(character).insert(Drying {
+                timer: Timer::new(WET_DURATION, TimerMode::Once),
+            });
+        }
+    }
+}
+
+fn drown_characters(
+    characters: Query<
+        (
+            Entity,
+            &ColliderAabb,
+            &Health,
+            Option<&InLiquid>,
+            Option<&mut Drowning>,
+        ),
+        With<Character>,
+    >,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (character, aabb, health, in_liquid, drowning) in characters {
+        let height = aabb.max.y - aabb.min.y;
+        let under_water = health.alive()
+            && in_liquid.is_some_and(|in_liquid| {
+                in_liquid.kind == LiquidKind::Water
+                    && in_liquid.depth > height * DROWN_DEPTH_FRACTION
+            });
+        match drowning {
+            Some(mut drowning) if under_water => {
+                drowning.timer.tick(time.delta());
+                if drowning.timer.just_finished() {
+                    commands.trigger(Damage {
+                        recipient: character,
+                        damage: DamagePayload {
+                            damage_type: DamageType::True,
+                            amount: health.current(),
+                            source: None,
+                            silent: false,
+                        },
+                    });
+                }
+            }
+            Some(_) => {
+                commands.entity(character).remove::<Drowning>();
+            }
+            None if under_water => {
+                commands.entity(character).insert(Drowning {
+                    timer: Timer::new(DROWN_DURATION, TimerMode::Once),
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+fn burn_characters_in_lava(
+    characters: Query<(Entity, &InLiquid)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (character, in_liquid) in characters {
+        if in_liquid.kind != LiquidKind::Lava {
+            continue;
+        }
+        commands.trigger(Damage {
+            recipient: character,
+            damage: DamagePayload {
+                damage_type: DamageType::Element(Element::Fire),
+                amount: LAVA_DAMAGE_PER_SECOND * time.delta_secs(),
+                source: Some(in_liquid.liquid),
+                silent: true,
+            },
+        });
+    }
+}
+
+/// Cold sprays freeze freezable water where they reach it.
+fn freeze_liquids(
+    sprays: Query<(&SpraySpell, &GlobalTransform)>,
+    liquids: Query<(
+        &Liquid,
+        &GlobalTransform,
+        &Mesh3d,
+        &Collider,
+        Option<&FrozenOver>,
+    )>,
+    mut ice: Query<(&mut Ice, &GlobalTransform)>,
+    ice_assets: Res<IceAssets>,
+    spatial_query: SpatialQuery,
+    mut commands: Commands,
+) {
+    let filter = SpatialQueryFilter::from_mask(Layers::Liquid);
+    for (spray, spray_transform) in sprays {
+        if !spray.elements.contains(Element::Cold) && !spray.elements.contains(Element::Ice) {
+            continue;
+        }
+
+        let range = spray::current_range(spray);
+        let origin = spray_transform.translation();
+        let forward = spray_transform.forward().with_y(0.).normalize_or_zero();
+        let spacing = ICE_PATCH_RADIUS * 1.5;
+        let mut distance = spacing;
+        while distance <= range {
+            for angle in [-0.5, 0., 0.5].map(|f| f * spray::ARC_ANGLE) {
+                let point = origin + Quat::from_rotation_y(angle) * forward * distance;
+                let Some(hit) = spatial_query.cast_ray(
+                    point + Vec3::Y * MAX_DEPTH * 0.5,
+                    Dir3::NEG_Y,
+                    MAX_DEPTH,
+                    false,
+                    &filter,
+                ) else {
+                    continue;
+                };
+                let Ok((liquid, liquid_transform, mesh, collider, frozen_over)) =
+                    liquids.get(hit.entity)
+                else {
+                    continue;
+                };
+                if liquid.kind != LiquidKind::Water || !liquid.freezable {
+                    continue;
+                }
+                let material = liquid
+                    .ice_material
+                    .clone()
+                    .unwrap_or_else(|| ice_assets.fallback_material.clone());
+
+                if liquid.auto_freeze {
+                    if let Some(frozen_over) = frozen_over {
+                        if let Ok((mut ice, _)) = ice.get_mut(frozen_over.0) {
+                            ice.thaw.reset();
+                        }
+                    } else {
+                        commands.spawn((
+                            Name::new("Ice"),
+                            Ice {
+                                thaw: Timer::new(ICE_DURATION, TimerMode::Once),
+                            },
+                            IceCover(hit.entity),
+                            ChildOf(hit.entity),
+                            Transform::default(),
+                            mesh.clone(),
+                            MeshMaterial3d(material),
+                            RigidBody::Static,
+                            collider.clone(),
+                            CollisionMaterial::Ice,
+                            CollisionLayers::new(Layers::Level, LayerMask::ALL),
+                        ));
+                    }
+                    continue;
+                }
+
+                let surface = point + Vec3::Y * (MAX_DEPTH * 0.5 - hit.distance);
+                if let Some((mut ice, _)) = ice.iter_mut().find(|(_, transform)| {
+                    transform.translation().distance_squared(surface) < spacing * spacing * 0.25
+                }) {
+                    ice.thaw.reset();
+                    continue;
+                }
+                let local = liquid_transform
+                    .affine()
+                    .inverse()
+                    .transform_point3(surface);
+                commands.spawn((
+                    Name::new("Ice"),
+                    Ice {
+                        thaw: Timer::new(ICE_DURATION, TimerMode::Once),
+                    },
+                    ChildOf(hit.entity),
+                    Transform::from_translation(local),
+                    Mesh3d(ice_assets.patch_mesh.clone()),
+                    MeshMaterial3d(material),
+                    RigidBody::Static,
+                    Collider::cylinder(ICE_PATCH_RADIUS, ICE_PATCH_THICKNESS),
+                    CollisionMaterial::Ice,
+                    CollisionLayers::new(Layers::Level, LayerMask::ALL),
+                ));
+            }
+            distance += spacing;
+        }
+    }
+}
+
+fn thaw_ice(ice: Query<(Entity, &mut Ice)>, time: Res<Time>, mut commands: Commands) {
+    for (entity, mut ice) in ice {
+        if ice.thaw.tick(time.delta()).just_finished() {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use avian3d::prelude::*;
-use bevy::{asset::AsAssetId, prelude::*};
-use typed_path::PlatformPath;
+use bevy::{
+    asset::{AsAssetId, AssetPath},
+    prelude::*,
+};
+use typed_path::PlatformPathBuf;
 
 use crate::{
     magicka_assets::{item::Item as ItemAsset, skinned_model::Bone},
-    magicka_level_model::{Layers, animated_parts::spawn_xna_model},
+    magicka_level_model::Layers,
 };
 
 pub fn plugin(app: &mut App) {
@@ -40,6 +43,13 @@ impl AsAssetId for ItemInstance {
     }
 }
 
+/// The item of a type, as scene triggers name them.
+pub(crate) fn item_asset_path(item_type: &str) -> AssetPath<'static> {
+    let mut content_path: PlatformPathBuf = ["Data", "Items", item_type].iter().collect();
+    content_path.set_extension("xnb");
+    crate::magicka_assets::source::asset_path(&content_path)
+}
+
 #[derive(Component, Default, Debug, Reflect)]
 #[require(RigidBodyDisabled, ColliderDisabled)]
 pub struct AttachedItem;
@@ -71,11 +81,7 @@ type AssetHandleChanged<C> = Or<(Changed<C>, AssetChanged<C>)>;
 fn spawn_new_item_models(
     changed_items: Query<(Entity, &ItemInstance), AssetHandleChanged<ItemInstance>>,
     item_assets: Res<Assets<ItemAsset>>,
-
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    assets: Res<AssetServer>,
 ) {
     for (item, asset_handle) in changed_items {
         let asset = item_assets.get(asset_handle.as_asset_id());
@@ -85,36 +91,12 @@ fn spawn_new_item_models(
         let Some(asset) = asset else {
             continue;
         };
-        let Some(from_content_path) =
-            crate::magicka_assets::content_path_from_handle(&asset_handle.0)
-        else {
-            warn!(
-                "No content path to resolve item {:?} model (from {:?})",
-                asset.item.name,
-                asset_handle.0.path()
-            );
+        let Some(model) = asset.model.clone() else {
             continue;
         };
-        let spawn_result = load_and_spawn_xna_model(
-            &asset.item.model.path,
-            from_content_path,
-            commands.reborrow(),
-            meshes.reborrow(),
-            materials.reborrow(),
-            &assets,
-        );
-        let model_entity = match spawn_result {
-            Ok(e) => e,
-            Err(()) => {
-                warn!(
-                    "Failed to spawn item {:?} model {:?}",
-                    asset.item.name, asset.item.model.path
-                );
-                continue;
-            }
-        };
-        commands.entity(model_entity).insert((
+        commands.spawn((
             ChildOf(item),
+            WorldAssetRoot(model),
             Transform::from_scale(Vec3::splat(asset.item.scale)),
             if asset.item.hide_model {
                 Visibility::Hidden
@@ -125,41 +107,6 @@ fn spawn_new_item_models(
     }
 }
 
-fn load_and_spawn_xna_model(
-    relative_path: &str,
-    content_path: &PlatformPath,
-    commands: Commands,
-    mut meshes: Mut<Assets<Mesh>>,
-    mut materials: Mut<Assets<StandardMaterial>>,
-    assets: &AssetServer,
-) -> Result<Entity, ()> {
-    let resolved_path = crate::magicka_assets::resolve_relative_path(content_path, relative_path);
-    let file_path =
-        std::path::Path::new(resolved_path.resolved_path.as_ref() as &std::ffi::OsStr).to_owned();
-    let bytes = std::fs::read(&file_path).map_err(|e| {
-        warn!("Failed to read model file {file_path:?}: {e}");
-    })?;
-    let xnb_asset = remagic::parse_model(&bytes).map_err(|e| {
-        warn!(
-            "Failed to parse model file {file_path:?}: {}",
-            e.into_inner()
-        );
-    })?;
-    let xna_model = xnb_asset.inner().as_ref().ok_or_else(|| {
-        warn!("Model is null in file {file_path:?}");
-    })?;
-    let model_entity = spawn_xna_model(
-        xna_model,
-        &xnb_asset,
-        &resolved_path.transitive_content_path,
-        commands,
-        &mut meshes,
-        &mut materials,
-        assets,
-    );
-    Ok(model_entity)
-}
-
 fn attach_items_to_skeleton(
     waiting_items: Query<(Entity, &DeferredAttachedItem)>,
     skeletons: Query<Option<&bevy::world_serialization::WorldInstance>>,
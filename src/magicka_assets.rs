@@ -1,12 +1,18 @@
 pub mod character_template;
+pub mod content_stack;
 pub mod image;
 pub mod item;
+pub mod level;
+pub mod scene;
 pub mod skinned_model;
+pub mod source;
 pub mod visual_effect;
 
-use bevy::{asset::AssetPath, prelude::*};
+use bevy::{
+    asset::{AssetPath, LoadContext},
+    prelude::*,
+};
 use std::{
-    ffi::OsStr,
     fs, io,
     path::{Component, Path, PathBuf},
     sync::OnceLock,
@@ -19,34 +25,186 @@ pub fn plugin(app: &mut App) {
     app.init_asset::<character_template::CharacterTemplate>();
     app.init_asset_loader::<character_template::CharacterTemplateLoader>();
 
+    app.init_asset_loader::<skinned_model::SkinnedModelLoader>();
+
+    app.init_asset::<level::MagickaLevel>();
+    app.init_asset::<crate::magicka_level_model::collision::CollisionMesh>();
+    app.init_asset_loader::<level::LevelLoader>();
+
+    app.init_asset::<scene::MagickaScene>();
+    app.init_asset_loader::<scene::SceneLoader>();
+
     app.init_asset::<item::Item>();
     app.init_asset_loader::<item::ItemLoader>();
 
     app.init_asset::<visual_effect::VisualEffect>();
     app.init_asset_loader::<visual_effect::VisualEffectLoader>();
+    app.add_plugins(visual_effect::plugin);
+
+    app.add_systems(
+        OnEnter(crate::content_picker::ContentDirState::Ready),
+        content_stack::log_content_stack,
+    );
+}
+
+/// Where the assets created while translating Magicka content go.
+///
+/// This lets the same translation code run both in systems, adding to the [`AssetServer`],
+/// and in [`AssetLoader`](bevy::asset::AssetLoader)s, adding labeled sub-assets.
+pub trait AssetCreator {
+    fn add<A: Asset>(&mut self, asset: A) -> Handle<A>;
+
+    fn load<A: Asset>(&mut self, path: AssetPath<'static>) -> Handle<A>;
+
+    /// Loads a texture holding non-color data, such as a normal map.
+    fn load_linear_image(&mut self, path: AssetPath<'static>) -> Handle<Image>;
+}
+
+impl AssetCreator for &AssetServer {
+    fn add<A: Asset>(&mut self, asset: A) -> Handle<A> {
+        AssetServer::add(self, asset)
+    }
+
+    fn load<A: Asset>(&mut self, path: AssetPath<'static>) -> Handle<A> {
+        AssetServer::load(self, path)
+    }
+
+    fn load_linear_image(&mut self, path: AssetPath<'static>) -> Handle<Image> {
+        self.load_builder()
+            .with_settings(|s: &mut image::MagickaTexture2dLoaderSettings| {
+                s.is_srgb = false;
+            })
+            .load(path)
+    }
+}
+
+/// Adds created assets as numbered labeled sub-assets of the asset being loaded.
+pub struct LabeledAssetCreator<'a, 'ctx> {
+    load_context: &'a mut LoadContext<'ctx>,
+    label_prefix: String,
+    next_index: usize,
+}
+
+impl<'a, 'ctx> LabeledAssetCreator<'a, 'ctx> {
+    pub fn new(load_context: &'a mut LoadContext<'ctx>, label_prefix: impl Into<String>) -> Self {
+        Self {
+            load_context,
+            label_prefix: label_prefix.into(),
+            next_index: 0,
+        }
+    }
+}
+
+impl AssetCreator for LabeledAssetCreator<'_, '_> {
+    fn add<A: Asset>(&mut self, asset: A) -> Handle<A> {
+        let label = format!(
+            "{}{}{}",
+            self.label_prefix,
+            A::short_type_path(),
+            self.next_index
+        );
+        self.next_index += 1;
+        self.load_context.add_labeled_asset(label, asset)
+    }
+
+    fn load<A: Asset>(&mut self, path: AssetPath<'static>) -> Handle<A> {
+        self.load_context.load(path)
+    }
+
+    fn load_linear_image(&mut self, path: AssetPath<'static>) -> Handle<Image> {
+        self.load_context
+            .load_builder()
+            .with_settings(|s: &mut image::MagickaTexture2dLoaderSettings| {
+                s.is_srgb = false;
+            })
+            .load(path)
+    }
 }
 
 static CONTENT_DIR: OnceLock<PlatformPathBuf> = OnceLock::new();
 
 const CONTENT_DIR_ENV_VAR: &str = "MAGICKA_CONTENT_DIR";
 
+/// The base game's Content directory, at the bottom of the [`content_stack::ContentStack`].
+///
+/// Must only be used once it has been set with [`init_content_root`] or [`set_content_root`].
 pub fn content_root() -> &'static PlatformPath {
-    CONTENT_DIR.get_or_init(|| {
-        content_dir_from_env().or_else(content_dir_from_steam).unwrap_or_else(||
-            panic!(
-                "\n\nError: Can't find Steam install of Magicka. Ensure it is installed, or set the {CONTENT_DIR_ENV_VAR} environment variable to the Content subdirectory of your Magicka install.\n\n"
-            )
-        )
-    })
+    try_content_root().expect("Magicka Content directory used before it was configured")
+}
+
+pub fn try_content_root() -> Option<&'static PlatformPath> {
+    CONTENT_DIR.get().map(|dir| dir.as_path())
+}
+
+/// Sets the Content directory from the first valid one of the configured directory,
+/// the `MAGICKA_CONTENT_DIR` environment variable, and the Steam install.
+/// Returns whether one was found.
+pub fn init_content_root(configured: Option<&Path>) -> bool {
+    let configured = configured.and_then(|dir| match PlatformPathBuf::try_from(dir.to_owned()) {
+        Ok(dir) => Some(dir),
+        Err(dir) => {
+            warn!("Configured Magicka Content directory is not a valid path: {dir:?}");
+            None
+        }
+    });
+    let candidates = [
+        (configured, "settings"),
+        (content_dir_from_env(), CONTENT_DIR_ENV_VAR),
+    ];
+    for (candidate, source) in candidates {
+        let Some(dir) = candidate else {
+            continue;
+        };
+        match validate_content_dir(Path::new(dir.as_ref() as &std::ffi::OsStr)) {
+            Ok(()) => return set_content_root(dir).is_ok(),
+            Err(e) => warn!("Ignoring Magicka Content directory from {source}: {e}"),
+        }
+    }
+    if let Some(dir) = content_dir_from_steam() {
+        match validate_content_dir(Path::new(dir.as_ref() as &std::ffi::OsStr)) {
+            Ok(()) => return set_content_root(dir).is_ok(),
+            Err(e) => warn!("Can't use Steam install of Magicka: {e}"),
+        }
+    }
+    false
+}
+
+/// Sets the Content directory, which can only be done once.
+pub fn set_content_root(dir: PlatformPathBuf) -> Result<(), PlatformPathBuf> {
+    CONTENT_DIR.set(dir)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidContentDir {
+    #[error("{0:?} is not a directory")]
+    NotADirectory(PathBuf),
+    #[error("{0:?} has no {1} folder, it isn't the Content folder of a Magicka install")]
+    MissingFolder(PathBuf, &'static str),
+}
+
+/// Checks that a directory looks like the Content folder of a Magicka install.
+pub fn validate_content_dir(dir: &Path) -> Result<(), InvalidContentDir> {
+    if !dir.is_dir() {
+        return Err(InvalidContentDir::NotADirectory(dir.to_owned()));
+    }
+    for folder in ["Levels", "Data/Characters", "Effects"] {
+        if !find_path_ignore_ascii_case(&dir.join(folder)).is_ok_and(|path| path.is_dir()) {
+            return Err(InvalidContentDir::MissingFolder(dir.to_owned(), folder));
+        }
+    }
+    Ok(())
 }
 
 fn content_dir_from_env() -> Option<PlatformPathBuf> {
     let dir = std::env::var_os(CONTENT_DIR_ENV_VAR)?;
     match PlatformPathBuf::try_from(PathBuf::from(dir)) {
         Ok(dir) => Some(dir),
-        Err(dir) => panic!(
-            "Magicka Content directory configured with {CONTENT_DIR_ENV_VAR} is not a valid path: {dir:?}"
-        ),
+        Err(dir) => {
+            warn!(
+                "Magicka Content directory configured with {CONTENT_DIR_ENV_VAR} is not a valid path: {dir:?}"
+            );
+            None
+        }
     }
 }
 
@@ -83,8 +241,8 @@ fn locate_magicka_install() -> Result<Option<PathBuf>, steamlocate::Error> {
 
 #[derive(Debug)]
 pub struct ResolvedPath {
-    /// The absolute path of the asset file
-    pub resolved_path: PlatformPathBuf,
+    /// The `magicka://` asset path of the asset file
+    pub asset_path: AssetPath<'static>,
     /// The content path to resolve further assets referenced relative from the resolved asset
     pub transitive_content_path: PlatformPathBuf,
 }
@@ -94,26 +252,20 @@ pub fn resolve_relative_path(
     relative_path: &str,
 ) -> ResolvedPath {
     let relative_path = typed_path::WindowsPathBuf::from(relative_path);
-    let resolved_content_path = from_content_path.parent().unwrap().join(
-        relative_path
-            .with_platform_encoding_checked()
-            .unwrap()
-            .as_bytes(),
-    );
-    let mut absolute_path = crate::magicka_assets::content_root()
-        .join_checked(&resolved_content_path)
-        .unwrap();
-    absolute_path.set_extension("xnb");
-    if !matches!(std::fs::exists(absolute_path.as_ref() as &OsStr), Ok(true))
-        && let Ok(found_path) = crate::magicka_assets::find_path_ignore_ascii_case(
-            std::path::Path::new(absolute_path.as_ref() as &OsStr),
+    let resolved_content_path = from_content_path
+        .parent()
+        .unwrap()
+        .join(
+            relative_path
+                .with_platform_encoding_checked()
+                .unwrap()
+                .as_bytes(),
         )
-    {
-        absolute_path =
-            typed_path::PlatformPath::new(found_path.as_os_str().as_encoded_bytes()).to_owned();
-    }
+        .normalize();
+    let mut file_content_path = resolved_content_path.clone();
+    file_content_path.set_extension("xnb");
     ResolvedPath {
-        resolved_path: absolute_path,
+        asset_path: source::asset_path(&file_content_path),
         transitive_content_path: resolved_content_path,
     }
 }
@@ -193,9 +345,9 @@ pub(crate) fn content_path_from_handle<A: Asset>(
 pub(crate) fn content_path_from_asset_path<'p, 'r: 'p>(
     asset_path: &'r AssetPath<'p>,
 ) -> Option<&'p PlatformPath> {
-    let asset_path = asset_path.path();
-    let content_path = PlatformPath::new(asset_path.as_os_str().as_encoded_bytes())
-        .strip_prefix(content_root())
-        .ok()?;
+    if asset_path.source().as_str() != Some(source::SOURCE) {
+        return None;
+    }
+    let content_path = PlatformPath::new(asset_path.path().as_os_str().as_encoded_bytes());
     Some(content_path)
 }
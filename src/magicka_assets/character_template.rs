@@ -1,10 +1,10 @@
-// TODO: Load some of the models as dependencies - at least the skeleton
-
 use bevy::{
     asset::{AssetLoader, LoadContext},
     prelude::*,
 };
 use remagic::xnb_readers::magicka_character::CharacterTemplate as MagickaCharacterTemplate;
+
+use crate::magicka_assets::{item::Item, resolve_relative_path};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use typed_path::PlatformPathBuf;
@@ -16,6 +16,15 @@ pub struct CharacterTemplate {
     pub template: MagickaCharacterTemplate,
     #[reflect(ignore)]
     pub content_path: PlatformPathBuf,
+    /// The visible models, in the same order as the template's skinned models.
+    #[dependency]
+    pub models: Vec<Handle<WorldAsset>>,
+    /// The invisible model that plays the animations for the visible ones.
+    #[dependency]
+    pub skeleton: Handle<WorldAsset>,
+    /// The items to equip, in the same order as the template's equipment.
+    #[dependency]
+    pub equipment: Vec<Handle<Item>>,
 }
 
 #[derive(Default, TypePath)]
@@ -32,12 +41,12 @@ impl AssetLoader for CharacterTemplateLoader {
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
         _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> std::result::Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
 
-        let _template = match remagic::parse_character(&bytes).map(|x| x.into_inner()) {
+        let template = match remagic::parse_character(&bytes).map(|x| x.into_inner()) {
             Ok(o) => o.ok_or(CharacterTemplateLoaderError::Null)?,
             Err(e) => {
                 error!("failed to parse character template .xnb: {}", e.inner());
@@ -45,9 +54,35 @@ impl AssetLoader for CharacterTemplateLoader {
             }
         };
 
-        //let asset = CharacterTemplate { template };
-        //Ok(asset)
-        todo!()
+        let content_path =
+            crate::magicka_assets::content_path_from_asset_path(load_context.path())
+                .ok_or(CharacterTemplateLoaderError::NotContent)?
+                .to_owned();
+
+        let (visible_models, skeleton_model) = &template.skinned_models;
+        let models = visible_models
+            .iter()
+            .map(|(model, _, _)| {
+                load_context.load(resolve_relative_path(&content_path, &model.path).asset_path)
+            })
+            .collect();
+        let skeleton = load_context
+            .load(resolve_relative_path(&content_path, &skeleton_model.path).asset_path);
+        let equipment = template
+            .equipment
+            .iter()
+            .map(|equip| {
+                load_context.load(resolve_relative_path(&content_path, &equip.item.path).asset_path)
+            })
+            .collect();
+
+        Ok(CharacterTemplate {
+            template,
+            content_path,
+            models,
+            skeleton,
+            equipment,
+        })
     }
 
     fn extensions(&self) -> &[&str] {
@@ -67,6 +102,8 @@ pub enum CharacterTemplateLoaderError {
     Parse, //(#[from] remagic::InnerError),
     #[error("No object in file (null)")]
     Null,
+    #[error("Not loaded from the magicka:// asset source, can't resolve referenced models")]
+    NotContent,
 }
 
 /// Settings for loading a [`CharacterTemplate`] using [`CharacterTemplateLoader`].
@@ -0,0 +1,287 @@
+//! The ordered stack of directories content is looked up in.
+//!
+//! The base game's Content directory is at the bottom, then any DLC content directories, then mods
+//! in their load order. A file in a higher layer replaces the file at the same path in the layers
+//! below it, and files that only exist in a higher layer add new content.
+//!
+//! Each mod is a folder in the mods directory containing a `mod.xml` manifest and a `Content`
+//! folder laid out like the game's:
+//!
+//! ```xml
+//! <Mod name="Bigger Fireballs" version="1.0.0" loadOrder="10" />
+//! ```
+
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use bevy::prelude::*;
+use thiserror::Error;
+use xml::{EventReader, reader::XmlEvent};
+
+use super::{content_root, find_path_ignore_ascii_case};
+
+const DLC_DIRS_ENV_VAR: &str = "MAGICKA_DLC_DIRS";
+const MODS_DIR_ENV_VAR: &str = "MAGICKA_MODS_DIR";
+const DEFAULT_MODS_DIR: &str = "mods";
+const MANIFEST_FILE_NAME: &str = "mod.xml";
+
+/// The content directories in order of increasing precedence.
+#[derive(Debug)]
+pub struct ContentStack {
+    layers: Vec<ContentLayer>,
+}
+
+#[derive(Debug)]
+pub struct ContentLayer {
+    pub kind: ContentLayerKind,
+    pub name: String,
+    pub version: Option<String>,
+    /// The directory laid out like the game's Content directory.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentLayerKind {
+    Base,
+    Dlc,
+    Mod,
+}
+
+pub fn content_stack() -> &'static ContentStack {
+    static STACK: OnceLock<ContentStack> = OnceLock::new();
+    STACK.get_or_init(ContentStack::discover)
+}
+
+impl ContentStack {
+    fn discover() -> Self {
+        let mut layers = vec![ContentLayer {
+            kind: ContentLayerKind::Base,
+            name: "Magicka".to_owned(),
+            version: None,
+            path: Path::new(content_root().as_ref() as &std::ffi::OsStr).to_owned(),
+        }];
+
+        if let Some(dirs) = std::env::var_os(DLC_DIRS_ENV_VAR) {
+            layers.extend(std::env::split_paths(&dirs).map(|path| ContentLayer {
+                kind: ContentLayerKind::Dlc,
+                name: path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .into_owned(),
+                version: None,
+                path,
+            }));
+        }
+
+        let mods_dir = std::env::var_os(MODS_DIR_ENV_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_MODS_DIR));
+        layers.extend(discover_mods(&mods_dir));
+
+        Self { layers }
+    }
+
+    /// The layers in order of increasing precedence, starting with the base game.
+    pub fn layers(&self) -> &[ContentLayer] {
+        &self.layers
+    }
+
+    /// Finds the file or directory at a path relative to the Content directory in the highest
+    /// layer that has it, ignoring ASCII case where needed.
+    pub fn find(&self, content_path: &Path) -> Result<PathBuf, io::Error> {
+        for layer in self.layers.iter().rev() {
+            match find_path_ignore_ascii_case(&layer.path.join(content_path)) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                r => return r,
+            }
+        }
+        Err(io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    /// Lists the names of the entries of a directory relative to the Content directory, merged
+    /// across every layer that has it.
+    pub fn read_dir(&self, content_path: &Path) -> Result<Vec<OsString>, io::Error> {
+        let mut found = false;
+        let mut seen = HashSet::new();
+        let mut names = Vec::new();
+        for layer in self.layers.iter().rev() {
+            let dir = match find_path_ignore_ascii_case(&layer.path.join(content_path)) {
+                Ok(dir) => dir,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            found = true;
+            for entry in fs::read_dir(dir)? {
+                let name = entry?.file_name();
+                if seen.insert(name.to_ascii_lowercase()) {
+                    names.push(name);
+                }
+            }
+        }
+        if found {
+            Ok(names)
+        } else {
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        }
+    }
+}
+
+fn discover_mods(mods_dir: &Path) -> Vec<ContentLayer> {
+    let entries = match fs::read_dir(mods_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            warn!("Can't read mods directory {mods_dir:?}: {e}");
+            return Vec::new();
+        }
+    };
+
+    let mut mods = Vec::new();
+    for entry in entries.flatten() {
+        let mod_dir = entry.path();
+        if !mod_dir.is_dir() {
+            continue;
+        }
+        let manifest = match find_path_ignore_ascii_case(&mod_dir.join(MANIFEST_FILE_NAME))
+            .and_then(fs::File::open)
+        {
+            Ok(file) => read_manifest(BufReader::new(file)),
+            Err(e) => {
+                warn!("Skipping mod {mod_dir:?}: Can't open {MANIFEST_FILE_NAME}: {e}");
+                continue;
+            }
+        };
+        let manifest = match manifest {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Skipping mod {mod_dir:?}: Invalid {MANIFEST_FILE_NAME}: {e}");
+                continue;
+            }
+        };
+        let content_dir = match find_path_ignore_ascii_case(&mod_dir.join("Content")) {
+            Ok(content_dir) => content_dir,
+            Err(e) => {
+                warn!("Skipping mod {mod_dir:?}: Can't find its Content folder: {e}");
+                continue;
+            }
+        };
+        mods.push((manifest, content_dir));
+    }
+
+    // Folder name breaks ties so the order doesn't depend on the file system
+    mods.sort_by(|(a, a_dir), (b, b_dir)| a.load_order.cmp(&b.load_order).then(a_dir.cmp(b_dir)));
+    mods.into_iter()
+        .map(|(manifest, path)| ContentLayer {
+            kind: ContentLayerKind::Mod,
+            name: manifest.name,
+            version: manifest.version,
+            path,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct ModManifest {
+    name: String,
+    version: Option<String>,
+    /// Mods with a higher load order take precedence.
+    load_order: i32,
+}
+
+#[derive(Debug, Error)]
+enum ManifestError {
+    #[error("Error reading XML data: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("There is no root <Mod> element in the XML data.")]
+    NoModElement,
+    #[error("The <Mod> element has no name attribute.")]
+    NoName,
+    #[error("The loadOrder attribute is not an integer: {0:?}")]
+    LoadOrder(String),
+}
+
+fn read_manifest(reader: impl BufRead) -> Result<ModManifest, ManifestError> {
+    let mut parser = EventReader::new(reader);
+    loop {
+        match parser.next()? {
+            XmlEvent::EndDocument => return Err(ManifestError::NoModElement),
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name.eq_ignore_ascii_case("mod") && name.namespace.is_none() => {
+                let mut mod_name = None;
+                let mut version = None;
+                let mut load_order = 0;
+                for attribute in attributes {
+                    if attribute.name.namespace.is_some() {
+                        continue;
+                    }
+                    let key = &attribute.name.local_name;
+                    if key.eq_ignore_ascii_case("name") {
+                        mod_name = Some(attribute.value);
+                    } else if key.eq_ignore_ascii_case("version") {
+                        version = Some(attribute.value);
+                    } else if key.eq_ignore_ascii_case("loadorder") {
+                        load_order = attribute
+                            .value
+                            .trim()
+                            .parse()
+                            .map_err(|_| ManifestError::LoadOrder(attribute.value))?;
+                    }
+                }
+                return Ok(ModManifest {
+                    name: mod_name.ok_or(ManifestError::NoName)?,
+                    version,
+                    load_order,
+                });
+            }
+            XmlEvent::StartElement { .. } => parser.skip()?,
+            _ => {}
+        }
+    }
+}
+
+pub(super) fn log_content_stack() {
+    let layers = content_stack().layers();
+    info!("Content directories, in order of precedence:");
+    for layer in layers.iter().rev() {
+        let kind = match layer.kind {
+            ContentLayerKind::Base => "base",
+            ContentLayerKind::Dlc => "DLC",
+            ContentLayerKind::Mod => "mod",
+        };
+        match &layer.version {
+            Some(version) => info!("  {} {version} ({kind}): {:?}", layer.name, layer.path),
+            None => info!("  {} ({kind}): {:?}", layer.name, layer.path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_mod_manifest() {
+        let manifest = read_manifest(
+            br#"<?xml version="1.0" encoding="utf-8"?>
+            <Mod name="Bigger Fireballs" version="1.0.0" loadOrder="-5" />"#
+                .as_slice(),
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            ModManifest {
+                name: "Bigger Fireballs".to_owned(),
+                version: Some("1.0.0".to_owned()),
+                load_order: -5,
+            }
+        );
+    }
+}
@@ -2,11 +2,13 @@ use bevy::{
     asset::{AssetLoader, LoadContext, RenderAssetUsages},
     image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
     prelude::*,
-    render::render_resource::Extent3d,
+    render::render_resource::{Extent3d, TextureFormat, TextureViewDescriptor, TextureViewDimension},
 };
+use remagic::xnb_readers::xna_tex::{SurfaceFormat, Texture};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// Loads both 2D and cube textures, since they share the `.xnb` extension.
 #[derive(Default, TypePath)]
 pub(crate) struct MagickaTexture2dLoader;
 
@@ -26,59 +28,77 @@ impl AssetLoader for MagickaTexture2dLoader {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
 
-        let texture_2d = match remagic::parse_texture_2d(&bytes).map(|x| x.into_inner()) {
-            Ok(o) => o.ok_or(MagickaTexture2dLoaderError::Null)?,
+        let (texture, format_version) = match remagic::parse_texture(&bytes) {
+            Ok(o) => {
+                let format_version = o.format_version();
+                (
+                    o.into_inner().ok_or(MagickaTexture2dLoaderError::Null)?,
+                    format_version,
+                )
+            }
             Err(e) => {
                 error!("failed to parse texture .xnb: {}", e.inner());
                 return Err(MagickaTexture2dLoaderError::Parse);
             }
         };
 
-        let texture_format = match texture_2d.format {
-            remagic::xnb_readers::xna_tex::SurfaceFormat::Dxt1 => {
-                if settings.is_srgb {
-                    bevy::render::render_resource::TextureFormat::Bc1RgbaUnormSrgb
-                } else {
-                    bevy::render::render_resource::TextureFormat::Bc1RgbaUnorm
-                }
-            }
-            remagic::xnb_readers::xna_tex::SurfaceFormat::Dxt5 => {
-                if settings.is_srgb {
-                    bevy::render::render_resource::TextureFormat::Bc3RgbaUnormSrgb
-                } else {
-                    bevy::render::render_resource::TextureFormat::Bc3RgbaUnorm
+        let mut image = match texture {
+            Texture::Texture2d(texture_2d) => {
+                if texture_2d.data_levels.len() != 1 {
+                    debug!(
+                        "unhandled image multi-levels: {}",
+                        texture_2d.data_levels.len()
+                    );
                 }
+
+                Image::new(
+                    Extent3d {
+                        width: texture_2d.width.try_into().unwrap(),
+                        height: texture_2d.height.try_into().unwrap(),
+                        // depth_or_array_layers: texture_2d.data_levels.len().try_into().unwrap(),
+                        depth_or_array_layers: 1,
+                    },
+                    bevy::render::render_resource::TextureDimension::D2,
+                    texture_2d.data_levels.first().unwrap().clone(),
+                    texture_format(texture_2d.format, format_version, settings.is_srgb),
+                    settings.asset_usage,
+                )
             }
-            remagic::xnb_readers::xna_tex::SurfaceFormat::Color => {
-                let format = bevy::render::render_resource::TextureFormat::Bgra8Unorm;
-                if settings.is_srgb {
-                    format.add_srgb_suffix()
-                } else {
-                    format.remove_srgb_suffix()
-                }
+            Texture::TextureCube(texture_cube) => {
+                let size = texture_cube.size.try_into().unwrap();
+                let level_count = texture_cube.faces[0].len();
+                let top_levels = texture_cube
+                    .faces
+                    .iter()
+                    .map(|levels| {
+                        levels
+                            .first()
+                            .map(Vec::as_slice)
+                            .ok_or(MagickaTexture2dLoaderError::Null)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut image = Image::new(
+                    Extent3d {
+                        width: size,
+                        height: size,
+                        depth_or_array_layers: 6,
+                    },
+                    bevy::render::render_resource::TextureDimension::D2,
+                    top_levels.concat(),
+                    texture_format(texture_cube.format, format_version, settings.is_srgb),
+                    settings.asset_usage,
+                );
+                // Blurrier levels are sampled for less sharp reflections, faces are already stored
+                // with all of their levels in a row as expected
+                image.data = Some(texture_cube.faces.concat().concat());
+                image.texture_descriptor.mip_level_count = level_count.try_into().unwrap();
+                image.texture_view_descriptor = Some(TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::Cube),
+                    ..default()
+                });
+                image
             }
-            _ => unimplemented!("texture format {:?}", texture_2d.format),
         };
-
-        if texture_2d.data_levels.len() != 1 {
-            debug!(
-                "unhandled image multi-levels: {}",
-                texture_2d.data_levels.len()
-            );
-        }
-
-        let mut image = Image::new(
-            Extent3d {
-                width: texture_2d.width.try_into().unwrap(),
-                height: texture_2d.height.try_into().unwrap(),
-                // depth_or_array_layers: texture_2d.data_levels.len().try_into().unwrap(),
-                depth_or_array_layers: 1,
-            },
-            bevy::render::render_resource::TextureDimension::D2,
-            texture_2d.data_levels.first().unwrap().clone(),
-            texture_format,
-            settings.asset_usage,
-        );
         image.sampler = settings.sampler.clone();
 
         Ok(image)
@@ -89,6 +109,42 @@ impl AssetLoader for MagickaTexture2dLoader {
     }
 }
 
+fn texture_format(
+    format: SurfaceFormat,
+    format_version: remagic::xnb::FormatVersion,
+    is_srgb: bool,
+) -> TextureFormat {
+    match format {
+        SurfaceFormat::Dxt1 => {
+            if is_srgb {
+                TextureFormat::Bc1RgbaUnormSrgb
+            } else {
+                TextureFormat::Bc1RgbaUnorm
+            }
+        }
+        SurfaceFormat::Dxt5 => {
+            if is_srgb {
+                TextureFormat::Bc3RgbaUnormSrgb
+            } else {
+                TextureFormat::Bc3RgbaUnorm
+            }
+        }
+        SurfaceFormat::Color => {
+            // XNA 4.0 changed Color from packed ARGB to RGBA byte order
+            let format = match format_version {
+                remagic::xnb::FormatVersion::Xna31 => TextureFormat::Bgra8Unorm,
+                remagic::xnb::FormatVersion::Xna40 => TextureFormat::Rgba8Unorm,
+            };
+            if is_srgb {
+                format.add_srgb_suffix()
+            } else {
+                format.remove_srgb_suffix()
+            }
+        }
+        _ => unimplemented!("texture format {:?}", format),
+    }
+}
+
 /// An error when loading an image using [`MagickaTexture2dLoader`].
 #[non_exhaustive]
 #[derive(Debug, Error)]
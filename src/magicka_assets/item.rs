@@ -1,10 +1,12 @@
-// TODO: Load some of the models as dependencies
-
 use bevy::{
-    asset::{AssetLoader, LoadContext},
+    asset::{AssetLoader, LoadContext, ReadAssetBytesError},
     prelude::*,
 };
 use remagic::xnb_readers::magicka_item::Item as MagickaItem;
+
+use crate::{
+    magicka_assets::LabeledAssetCreator, magicka_level_model::animated_parts::spawn_xna_model,
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -13,6 +15,8 @@ use thiserror::Error;
 pub struct Item {
     #[reflect(ignore)]
     pub item: MagickaItem,
+    /// The item's model, a labeled sub-asset since models aren't shared between items.
+    pub model: Option<Handle<WorldAsset>>,
 }
 
 #[derive(Default, TypePath)]
@@ -29,7 +33,7 @@ impl AssetLoader for ItemLoader {
         &self,
         reader: &mut dyn bevy::asset::io::Reader,
         _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> std::result::Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
@@ -42,7 +46,16 @@ impl AssetLoader for ItemLoader {
             }
         };
 
-        let asset = Item { item: item_data };
+        let model = if item_data.model.path.is_empty() {
+            None
+        } else {
+            Some(load_model(&item_data.model.path, load_context).await?)
+        };
+
+        let asset = Item {
+            item: item_data,
+            model,
+        };
 
         Ok(asset)
     }
@@ -52,7 +65,41 @@ impl AssetLoader for ItemLoader {
     }
 }
 
-/// An error when loading a character template image using [`CharacterTemplateLoader`].
+async fn load_model(
+    relative_path: &str,
+    load_context: &mut LoadContext<'_>,
+) -> Result<Handle<WorldAsset>, ItemLoaderError> {
+    let content_path = crate::magicka_assets::content_path_from_asset_path(load_context.path())
+        .ok_or(ItemLoaderError::NotContent)?
+        .to_owned();
+    let resolved_path = crate::magicka_assets::resolve_relative_path(&content_path, relative_path);
+    let bytes = load_context
+        .read_asset_bytes(resolved_path.asset_path)
+        .await?;
+
+    let xnb_asset = match remagic::parse_model(&bytes) {
+        Ok(o) => o,
+        Err(e) => {
+            error!("failed to parse item model .xnb: {}", e.inner());
+            return Err(ItemLoaderError::Parse);
+        }
+    };
+    let xna_model = xnb_asset.inner().as_ref().ok_or(ItemLoaderError::Null)?;
+
+    let mut world = World::default();
+    let mut assets = LabeledAssetCreator::new(load_context, "model/");
+    spawn_xna_model(
+        xna_model,
+        &xnb_asset,
+        &resolved_path.transitive_content_path,
+        world.commands(),
+        &mut assets,
+    );
+    world.flush();
+    Ok(load_context.add_labeled_asset("model".to_owned(), WorldAsset::new(world)))
+}
+
+/// An error when loading an item using [`ItemLoader`].
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum ItemLoaderError {
@@ -64,8 +111,13 @@ pub enum ItemLoaderError {
     Parse, //(#[from] remagic::InnerError),
     #[error("No object in file (null)")]
     Null,
+    /// An error occurred while trying to load the item's model.
+    #[error("Failed to load model: {0}")]
+    Model(#[from] ReadAssetBytesError),
+    #[error("Not loaded from the magicka:// asset source, can't resolve referenced model")]
+    NotContent,
 }
 
-/// Settings for loading a [`CharacterTemplate`] using [`CharacterTemplateLoader`].
+/// Settings for loading an [`Item`] using [`ItemLoader`].
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ItemLoaderSettings;
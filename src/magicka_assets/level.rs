@@ -0,0 +1,209 @@
+use avian3d::prelude::Collider;
+use bevy::{
+    asset::{AssetLoader, LoadContext},
+    prelude::*,
+};
+use bevy_landmass::NavMesh3d;
+use remagic::xnb_readers::magicka_content::Level;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::{
+    magicka_assets::{
+        LabeledAssetCreator, content_path_from_asset_path,
+        visual_effect::{VisualEffect, find_effect},
+    },
+    magicka_level_model::{
+        animated_parts::{AnimatedPartAssets, load_animated_part},
+        bitree::{BiTreeNodeAssets, load_bitree_model},
+        collision::{CollisionMaterial, CollisionMesh, to_collider},
+        force_field::{ForceFieldAssets, load_force_field},
+        liquid::{LiquidAssets, load_liquid},
+        nav_mesh,
+    },
+    settings::Settings,
+};
+
+/// A level model, with everything needed to spawn it created up front as labeled sub-assets:
+/// - `bitree/…` for the meshes and materials of the static geometry
+/// - `part/<name>` for the model of each animated part, and `part/<name>/…` for its other assets
+/// - `clip/<name>` for the animation of each animated part
+/// - `liquid/…` and `forcefield/…` for the meshes and materials of liquids and force fields
+/// - `collision/N` for each collision slot
+/// - `navmesh` for the navigation mesh
+///
+/// The visual effects it uses are loaded as dependencies.
+#[derive(Asset, TypePath, Debug)]
+pub struct MagickaLevel {
+    pub level: Level,
+    pub(crate) bitrees: Vec<BiTreeNodeAssets>,
+    pub(crate) animated_parts: Vec<AnimatedPartAssets>,
+    pub(crate) liquids: Vec<Option<LiquidAssets>>,
+    pub(crate) force_fields: Vec<Option<ForceFieldAssets>>,
+    /// In the same order as [`Level::collision`], `None` for the empty slots.
+    pub collision: Vec<Option<Handle<CollisionMesh>>>,
+    pub camera_mesh: Option<Collider>,
+    pub nav_mesh: Option<Handle<NavMesh3d>>,
+    /// The visual effects used by the level, by lowercase name.
+    pub effects: HashMap<String, Handle<VisualEffect>>,
+}
+
+#[derive(TypePath)]
+pub(crate) struct LevelLoader {
+    /// See [`load_bitree_model`].
+    batch_bitree_leaves: bool,
+}
+
+impl FromWorld for LevelLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            batch_bitree_leaves: world
+                .get_resource::<Settings>()
+                .is_some_and(|settings| settings.graphics.batch_level_meshes),
+        }
+    }
+}
+
+impl AssetLoader for LevelLoader {
+    type Asset = MagickaLevel;
+
+    type Settings = LevelLoaderSettings;
+
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn bevy::asset::io::Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let content_path = content_path_from_asset_path(load_context.path())
+            .ok_or(LevelLoaderError::NotContent)?
+            .to_owned();
+
+        let level_asset = match remagic::parse_level(&bytes) {
+            Ok(o) => o,
+            Err(e) => {
+                error!("failed to parse level .xnb: {}", e.inner());
+                return Err(LevelLoaderError::Parse);
+            }
+        };
+        let level = level_asset.inner().as_ref().ok_or(LevelLoaderError::Null)?;
+
+        let bitrees = match &level.model {
+            Some(model) => load_bitree_model(
+                model,
+                &content_path,
+                self.batch_bitree_leaves,
+                &mut LabeledAssetCreator::new(load_context, "bitree/"),
+            ),
+            None => Vec::new(),
+        };
+
+        let animated_parts = level
+            .animated_parts
+            .iter()
+            .map(|part| load_animated_part(part, &level_asset, &content_path, load_context))
+            .collect();
+
+        let mut assets = LabeledAssetCreator::new(load_context, "liquid/");
+        let liquids = level
+            .waters
+            .iter()
+            .map(|liquid| load_liquid(liquid, &content_path, &mut assets))
+            .collect();
+
+        let mut assets = LabeledAssetCreator::new(load_context, "forcefield/");
+        let force_fields = level
+            .force_fields
+            .iter()
+            .map(|force_field| load_force_field(force_field, &content_path, &mut assets))
+            .collect();
+
+        let colliders: Vec<_> = level.collision.iter().map(to_collider).collect();
+
+        let camera_mesh = level.camera_mesh.as_ref().and_then(to_collider);
+
+        let ground: Vec<_> = colliders.iter().flatten().collect();
+        let nav_mesh = nav_mesh::load(&level.nav_mesh, &ground)
+            .map(|nav_mesh| load_context.add_labeled_asset("navmesh".to_owned(), nav_mesh));
+
+        let collision = colliders
+            .into_iter()
+            .enumerate()
+            .map(|(i, collider)| {
+                Some(load_context.add_labeled_asset(
+                    format!("collision/{i}"),
+                    CollisionMesh {
+                        collider: collider?,
+                        material: CollisionMaterial::from_index(i),
+                    },
+                ))
+            })
+            .collect();
+
+        let mut effects = HashMap::new();
+        let mut effect_names: Vec<_> = level.visual_effects.iter().map(|e| &e.effect).collect();
+        let mut parts: Vec<_> = level.animated_parts.iter().collect();
+        while let Some(part) = parts.pop() {
+            effect_names.extend(part.effects.iter().map(|e| &e.effect));
+            parts.extend(&part.children);
+        }
+        for name in effect_names {
+            let name = name.to_ascii_lowercase();
+            if effects.contains_key(&name) {
+                continue;
+            }
+            match find_effect(&name) {
+                Some(path) => {
+                    effects.insert(name, load_context.load(path));
+                }
+                None => warn!("Level uses visual effect {name:?} which can't be found"),
+            }
+        }
+
+        let level = level_asset
+            .into_inner()
+            .expect("level was checked to be non-null");
+
+        Ok(MagickaLevel {
+            level,
+            bitrees,
+            animated_parts,
+            liquids,
+            force_fields,
+            collision,
+            camera_mesh,
+            nav_mesh,
+            effects,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xnb"]
+    }
+}
+
+/// An error when loading a level using [`LevelLoader`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    /// An error occurred while trying to load the file bytes.
+    #[error("Failed to load file bytes: {0}")]
+    Io(#[from] std::io::Error),
+    /// An error occurred while trying to decode the file bytes.
+    #[error("Could not parse file")] // : {0}
+    Parse, //(#[from] remagic::InnerError),
+    #[error("No object in file (null)")]
+    Null,
+    #[error("Not loaded from the magicka:// asset source, can't resolve referenced textures")]
+    NotContent,
+}
+
+/// Settings for loading a [`MagickaLevel`] using [`LevelLoader`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LevelLoaderSettings;
@@ -0,0 +1,132 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::{
+    character::template_asset_path,
+    item::item_asset_path,
+    magicka_assets::{
+        character_template::CharacterTemplate, content_path_from_asset_path, item::Item,
+        level::MagickaLevel, resolve_relative_path,
+    },
+    magicka_scene::{SceneConfig, SceneError, read_scene},
+    script_triggers::action::TriggerActionBehavior,
+};
+
+/// A scene config, with the assets it needs loaded as dependencies so it can be spawned without
+/// waiting on anything:
+/// - the level model
+/// - the sky texture, if any
+/// - the character templates spawned by its triggers
+/// - the items spawned by its triggers
+#[derive(Asset, TypePath, Debug)]
+pub struct MagickaScene {
+    pub config: SceneConfig,
+    pub level: Handle<MagickaLevel>,
+    pub sky_texture: Option<Handle<Image>>,
+    /// By lowercase name.
+    pub templates: HashMap<String, Handle<CharacterTemplate>>,
+    /// By lowercase type.
+    pub items: HashMap<String, Handle<Item>>,
+}
+
+#[derive(Default, TypePath)]
+pub(crate) struct SceneLoader;
+
+impl AssetLoader for SceneLoader {
+    type Asset = MagickaScene;
+
+    type Settings = SceneLoaderSettings;
+
+    type Error = SceneLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn bevy::asset::io::Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let content_path = content_path_from_asset_path(load_context.path())
+            .ok_or(SceneLoaderError::NotContent)?
+            .to_owned();
+
+        let config = read_scene(bytes.as_slice())?;
+
+        // The model is named relative to the scene's folder, without extension
+        let model = config.model.as_ref().ok_or(SceneLoaderError::NoModel)?;
+        let mut model_path = content_path.clone();
+        model_path.pop();
+        model_path.push(model);
+        model_path.set_extension("xnb");
+        let level = load_context.load(super::source::asset_path(&model_path));
+
+        // So is the sky texture
+        let sky_texture = config
+            .sky
+            .as_ref()
+            .and_then(|sky| sky.texture.as_deref())
+            .map(|texture| {
+                load_context.load(resolve_relative_path(&content_path, texture).asset_path)
+            });
+
+        let mut templates = HashMap::new();
+        let mut items = HashMap::new();
+        let actions = config.triggers.iter().flat_map(|trigger| &trigger.actions);
+        for action in actions {
+            match &action.behavior {
+                TriggerActionBehavior::SpawnCharacter(spawn) => {
+                    templates
+                        .entry(spawn.type_name.to_ascii_lowercase())
+                        .or_insert_with(|| {
+                            load_context.load(template_asset_path(&spawn.type_name))
+                        });
+                }
+                TriggerActionBehavior::SpawnItem(spawn) => {
+                    items
+                        .entry(spawn.item_type.to_ascii_lowercase())
+                        .or_insert_with(|| load_context.load(item_asset_path(&spawn.item_type)));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(MagickaScene {
+            config,
+            level,
+            sky_texture,
+            templates,
+            items,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xml"]
+    }
+}
+
+/// An error when loading a scene using [`SceneLoader`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SceneLoaderError {
+    /// An error occurred while trying to load the file bytes.
+    #[error("Failed to load file bytes: {0}")]
+    Io(#[from] std::io::Error),
+    /// An error occurred while trying to parse the file XML.
+    #[error("Failed to read scene: {0}")]
+    Scene(#[from] SceneError),
+    #[error("Scene has no level model")]
+    NoModel,
+    #[error("Not loaded from the magicka:// asset source, can't resolve the level model")]
+    NotContent,
+}
+
+/// Settings for loading a [`MagickaScene`] using [`SceneLoader`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SceneLoaderSettings;
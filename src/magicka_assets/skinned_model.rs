@@ -2,13 +2,19 @@ use std::collections::HashMap;
 
 use bevy::{
     animation::{AnimatedBy, AnimationTargetId},
+    asset::{AssetLoader, LoadContext},
     mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
     prelude::*,
 };
 use remagic::SharedResources;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use typed_path::PlatformPath;
 
-use crate::magicka_level_model::map_mat4;
+use crate::{
+    magicka_assets::{AssetCreator, LabeledAssetCreator},
+    magicka_level_model::map_mat4,
+};
 
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
@@ -20,13 +26,81 @@ pub struct AnimationLibrary {
 #[reflect(Component)]
 pub struct Bone;
 
+/// Loads a skinned model `.xnb` as a [`WorldAsset`] rooted at its [`AnimationPlayer`].
+#[derive(Default, TypePath)]
+pub(crate) struct SkinnedModelLoader;
+
+impl AssetLoader for SkinnedModelLoader {
+    type Asset = WorldAsset;
+
+    type Settings = SkinnedModelLoaderSettings;
+
+    type Error = SkinnedModelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn bevy::asset::io::Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let content_path =
+            crate::magicka_assets::content_path_from_asset_path(load_context.path())
+                .ok_or(SkinnedModelLoaderError::NotContent)?
+                .to_owned();
+
+        let xnb_asset = match remagic::parse_skinned_model(&bytes) {
+            Ok(o) => o,
+            Err(e) => {
+                error!("failed to parse skinned model .xnb: {}", e.inner());
+                return Err(SkinnedModelLoaderError::Parse);
+            }
+        };
+        let model = xnb_asset
+            .inner()
+            .as_ref()
+            .ok_or(SkinnedModelLoaderError::Null)?;
+
+        Ok(load_skinned_model(
+            model,
+            &xnb_asset,
+            &content_path,
+            &mut LabeledAssetCreator::new(load_context, ""),
+        ))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xnb"]
+    }
+}
+
+/// An error when loading a skinned model using [`SkinnedModelLoader`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SkinnedModelLoaderError {
+    /// An error occurred while trying to load the file bytes.
+    #[error("Failed to load file bytes: {0}")]
+    Io(#[from] std::io::Error),
+    /// An error occurred while trying to decode the file bytes.
+    #[error("Could not parse file")] // : {0}
+    Parse, //(#[from] remagic::InnerError),
+    #[error("No object in file (null)")]
+    Null,
+    #[error("Not loaded from the magicka:// asset source, can't resolve referenced textures")]
+    NotContent,
+}
+
+/// Settings for loading a skinned model using [`SkinnedModelLoader`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SkinnedModelLoaderSettings;
+
 pub fn load_skinned_model(
     model: &remagic::xnb_readers::skinning::SkinnedModel,
     shared_resources: &impl SharedResources,
     content_path: &PlatformPath,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
 ) -> WorldAsset {
     let mut world = World::default();
 
@@ -125,8 +199,6 @@ pub fn load_skinned_model(
             shared_resources,
             content_path,
             world.commands(),
-            meshes,
-            materials,
             assets,
             Some(&skinned_mesh),
         );
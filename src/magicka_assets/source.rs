@@ -0,0 +1,120 @@
+//! The `magicka://` asset source, serving the Magicka Content directory overlaid with DLC and mods.
+//!
+//! Content references paths written on Windows, so their casing often doesn't match the files on
+//! disk. Paths are resolved through the [`ContentStack`], ignoring ASCII case where needed.
+//!
+//! [`ContentStack`]: super::content_stack::ContentStack
+
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::{
+        AssetPath,
+        io::{
+            AssetReader, AssetReaderError, AssetSourceBuilder, AssetSourceId, PathStream, Reader,
+            VecReader,
+        },
+    },
+    prelude::*,
+    tasks::futures_lite::stream,
+};
+use typed_path::PlatformPath;
+
+use super::content_stack::content_stack;
+
+/// The name of the asset source serving files from the Content directory.
+pub const SOURCE: &str = "magicka";
+
+/// Must be added before [`AssetPlugin`], which builds the registered sources.
+pub fn plugin(app: &mut App) {
+    let source = AssetSourceBuilder::new(|| Box::new(MagickaAssetReader));
+
+    #[cfg(feature = "dev")]
+    let source = source.with_watcher(|sender| {
+        // Watchers are created on startup, so when the Content directory is picked in game
+        // there's nothing to watch yet
+        super::try_content_root()?;
+        // Events are reported with the casing on disk, so only loads using that casing reload
+        let watchers = content_stack()
+            .layers()
+            .iter()
+            .filter_map(|layer| {
+                bevy::asset::io::AssetSource::get_default_watcher(
+                    layer.path.to_string_lossy().into_owned(),
+                    std::time::Duration::from_millis(300),
+                )(sender.clone())
+            })
+            .collect();
+        Some(Box::new(StackWatcher(watchers)))
+    });
+
+    app.register_asset_source(SOURCE, source);
+}
+
+/// The asset path of a file in the Content directory, given as a path relative to it.
+pub fn asset_path(content_path: &PlatformPath) -> AssetPath<'static> {
+    let path = Path::new(content_path.as_ref() as &std::ffi::OsStr).to_owned();
+    AssetPath::from_path_buf(path).with_source(AssetSourceId::Name(SOURCE.into()))
+}
+
+/// Watches every layer of the content stack. Paths are reported relative to their layer.
+#[cfg(feature = "dev")]
+struct StackWatcher(
+    #[expect(dead_code, reason = "only held to keep the watchers running")]
+    Vec<Box<dyn bevy::asset::io::AssetWatcher>>,
+);
+
+#[cfg(feature = "dev")]
+impl bevy::asset::io::AssetWatcher for StackWatcher {}
+
+/// Reads files through the content stack, ignoring ASCII case where needed.
+struct MagickaAssetReader;
+
+impl MagickaAssetReader {
+    fn resolve(path: &Path) -> Result<PathBuf, AssetReaderError> {
+        map_not_found(content_stack().find(path), path)
+    }
+}
+
+fn map_not_found<T>(result: std::io::Result<T>, path: &Path) -> Result<T, AssetReaderError> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(AssetReaderError::NotFound(path.to_owned()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl AssetReader for MagickaAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        let full_path = Self::resolve(path)?;
+        let bytes = std::fs::read(&full_path)?;
+        Ok(VecReader::new(bytes))
+    }
+
+    async fn read_meta<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<impl Reader + 'a, AssetReaderError> {
+        // Content has no .meta files, and we don't want to add them to the game install
+        Err::<VecReader, _>(AssetReaderError::NotFound(path.to_owned()))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        // Directories are merged across layers, so mods can add files next to the game's
+        let entries: Vec<_> = map_not_found(content_stack().read_dir(path), path)?
+            .into_iter()
+            .map(|name| path.join(name))
+            .collect();
+        Ok(Box::new(stream::iter(entries)))
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        let full_path = Self::resolve(path)?;
+        Ok(std::fs::metadata(full_path)?.is_dir())
+    }
+}
@@ -1,12 +1,13 @@
-use std::{io::Read, str::FromStr};
+use std::{collections::HashMap, io::Read, path::Path, str::FromStr, sync::OnceLock};
 
 use bevy::{
-    asset::{AssetLoader, LoadContext},
+    asset::{AssetLoader, AssetPath, LoadContext},
     prelude::*,
 };
-use bevy_hanabi::{EffectAsset, ParticleEffect};
+use bevy_hanabi::{EffectAsset, EffectMaterial, ParticleEffect};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use typed_path::PlatformPathBuf;
 use xml::{EventReader, ParserConfig, reader::XmlEvent};
 
 #[derive(Asset, Reflect, Debug)]
@@ -75,13 +76,74 @@ pub enum VisualEffectLoaderError {
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct VisualEffectLoaderSettings;
 
+/// Finds a visual effect by name in the Effects folders, where they're sorted into subfolders.
+pub fn find_effect(name: &str) -> Option<AssetPath<'static>> {
+    static INDEX: OnceLock<HashMap<String, PlatformPathBuf>> = OnceLock::new();
+    let index = INDEX.get_or_init(|| {
+        let mut index = HashMap::new();
+        // Higher layers go last, so their effects replace ones with the same name
+        for layer in super::content_stack::content_stack().layers() {
+            let root = &layer.path;
+            match super::find_path_ignore_ascii_case(&root.join("Effects")) {
+                Ok(effects_dir) => index_effects(root, &effects_dir, &mut index),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Can't find visual effects folder in {root:?}: {e}"),
+            }
+        }
+        index
+    });
+    index
+        .get(&name.to_ascii_lowercase())
+        .map(|content_path| super::source::asset_path(content_path))
+}
+
+fn index_effects(root: &Path, dir: &Path, index: &mut HashMap<String, PlatformPathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Can't read visual effects folder {dir:?}: {e}");
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            index_effects(root, &path, index);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+            && let Some(name) = path.file_stem()
+            && let Ok(content_path) = path.strip_prefix(root)
+            && let Ok(content_path) = PlatformPathBuf::try_from(content_path.to_owned())
+        {
+            index.insert(name.to_string_lossy().to_ascii_lowercase(), content_path);
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<EffectTimeline>();
+    app.register_type::<EmitterSpawnRate>();
+    app.register_type::<EffectLight>();
+    app.add_systems(
+        PostUpdate,
+        (
+            advance_effect_timelines,
+            (animate_emitter_spawn_rates, animate_effect_lights),
+        )
+            .chain(),
+    );
+}
+
 fn create_scene(effect: &Effect, load_context: &mut LoadContext) -> WorldAsset {
     let mut world = World::default();
 
-    // TODO:
-    // If effect_type is Single, then the timeline pos is lifetime, and the effect should stop when lifetime exceeds duration
-    // If effect_type is Looping, then the timeline pos is lifetime % duration
-    // If effect_type is Infinite, then the timeline pos is min(lifetime, duration)
+    let timeline = EffectTimeline {
+        effect_type: effect.effect_type,
+        duration: effect.duration,
+        keyframes_per_second: effect.keyframes_per_second,
+        elapsed: 0.,
+    };
 
     for (i, emitter) in effect.emitters.iter().enumerate() {
         let emitter_asset = load_context
@@ -89,14 +151,52 @@ fn create_scene(effect: &Effect, load_context: &mut LoadContext) -> WorldAsset {
                 Ok(create_emitter_effect(emitter, load_context))
             })
             .expect("does not error");
-        world.spawn((
-            // TODO: Use the name from the .xml
-            Name::new(format!("emitter{i}")),
+        let mut emitter_entity = world.spawn((
+            Name::new(
+                emitter
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("emitter{i}")),
+            ),
+            Transform::default(),
             ParticleEffect::new(emitter_asset),
+            timeline.clone(),
         ));
+        if let EmitterKind::Continuous { spawn_rate } = &emitter.kind {
+            emitter_entity.insert(EmitterSpawnRate {
+                keyframes: spawn_rate.clone(),
+                current: None,
+            });
+        }
+        if let Some(texture) = &emitter.texture {
+            if let Some(content_path) =
+                super::content_path_from_asset_path(load_context.path()).map(|p| p.to_owned())
+            {
+                let texture_path = super::resolve_relative_path(&content_path, texture);
+                emitter_entity.insert(EffectMaterial {
+                    images: vec![load_context.load(texture_path.asset_path)],
+                });
+            } else {
+                warn!("Can't resolve visual effect texture {texture:?} outside of magicka://");
+            }
+        }
     }
-    for _light in &effect.lights {
-        // TODO: Spawn light
+
+    for (i, light) in effect.lights.iter().enumerate() {
+        world.spawn((
+            Name::new(light.name.clone().unwrap_or_else(|| format!("light{i}"))),
+            Transform::from_translation(light.position),
+            PointLight {
+                shadow_maps_enabled: false,
+                ..default()
+            },
+            timeline.clone(),
+            EffectLight {
+                colors: light.colors.clone(),
+                intensities: light.intensities.clone(),
+                radii: light.radii.clone(),
+            },
+        ));
     }
 
     WorldAsset::new(world)
@@ -107,51 +207,246 @@ fn create_emitter_effect(emitter: &Emitter, load_context: &mut LoadContext) -> E
 
     let writer = ExprWriter::new();
 
-    let init_pos = SetPositionCircleModifier {
-        center: writer.lit(Vec3::ZERO).expr(),
-        axis: writer.lit(Vec3::Y).expr(),
-        radius: writer.lit(30.).expr(),
-        dimension: ShapeDimension::Volume,
-    };
+    let init_pos = SetAttributeModifier::new(Attribute::POSITION, writer.lit(Vec3::ZERO).expr());
 
-    let zero = writer.lit(0.);
-    let y = writer.lit(140.).uniform(writer.lit(160.));
-    let v = zero.clone().vec3(y, zero);
-    let init_vel = SetAttributeModifier::new(Attribute::VELOCITY, v.expr());
+    // Visual effects point along -Z, spread randomly in a cone around that
+    let random_offset = (writer.rand(VectorType::VEC3F) * writer.lit(2.) - writer.lit(1.))
+        * writer.lit(emitter.spread);
+    let direction = (writer.lit(Vec3::NEG_Z) + random_offset).normalized();
+    let speed = writer
+        .lit(emitter.speed.0)
+        .uniform(writer.lit(emitter.speed.1));
+    let init_vel = SetAttributeModifier::new(Attribute::VELOCITY, (direction * speed).expr());
 
     let age = writer.lit(0.).expr();
     let init_age = SetAttributeModifier::new(Attribute::AGE, age);
 
-    let lifetime = writer.lit(0.8).uniform(writer.lit(1.2)).expr();
+    let lifetime = writer
+        .lit(emitter.lifetime.0)
+        .uniform(writer.lit(emitter.lifetime.1))
+        .expr();
     let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
 
-    let spawner = SpawnerSettings::rate(10.0.into());
-    EffectAsset::new(32, spawner, writer.finish())
-        // .with_name("")
+    let accel = AccelModifier::new(writer.lit(Vec3::NEG_Y * emitter.gravity).expr());
+
+    let texture_slot = writer.lit(0u32).expr();
+
+    let mut module = writer.finish();
+    if emitter.texture.is_some() {
+        module.add_texture_slot("color");
+    }
+
+    let mut color_gradient = bevy_hanabi::Gradient::new();
+    for (t, color) in keyframe_times(emitter.colors.len()).zip(&emitter.colors) {
+        color_gradient.add_key(t, *color);
+    }
+    let mut size_gradient = bevy_hanabi::Gradient::new();
+    for (t, size) in keyframe_times(emitter.sizes.len()).zip(&emitter.sizes) {
+        size_gradient.add_key(t, Vec3::splat(*size));
+    }
+
+    let spawner = match emitter.kind {
+        // The rate is animated along the effect timeline by animate_emitter_spawn_rates
+        EmitterKind::Continuous { ref spawn_rate } => {
+            SpawnerSettings::rate(spawn_rate.first().copied().unwrap_or(0.).into())
+        }
+        EmitterKind::Pulse { count, interval } if interval > 0. => {
+            SpawnerSettings::burst(count.into(), interval.into())
+        }
+        EmitterKind::Pulse { count, .. } => SpawnerSettings::once(count.into()),
+    };
+
+    let max_rate = match &emitter.kind {
+        EmitterKind::Continuous { spawn_rate } => spawn_rate.iter().copied().fold(0., f32::max),
+        EmitterKind::Pulse { count, .. } => *count,
+    };
+    let capacity = ((max_rate * emitter.lifetime.1).ceil() as u32).clamp(32, 16384);
+
+    let mut asset = EffectAsset::new(capacity, spawner, module)
+        .with_alpha_mode(match emitter.blend {
+            BlendMode::Additive => bevy_hanabi::AlphaMode::Add,
+            BlendMode::Alpha => bevy_hanabi::AlphaMode::Blend,
+        })
         .init(init_pos)
         .init(init_vel)
         .init(init_age)
         .init(init_lifetime)
+        .update(accel)
+        .render(ColorOverLifetimeModifier::new(color_gradient))
+        .render(SizeOverLifetimeModifier {
+            gradient: size_gradient,
+            screen_space_size: false,
+        })
+        .render(OrientModifier::new(OrientMode::FaceCameraPosition));
+    if emitter.texture.is_some() {
+        asset = asset.render(ParticleTextureModifier {
+            texture_slot,
+            sample_mapping: ImageSampleMapping::Modulate,
+        });
+    }
+    asset
+}
+
+/// Evenly spaced times in `[0, 1]` for keyframes over a particle's lifetime.
+fn keyframe_times(count: usize) -> impl Iterator<Item = f32> {
+    let last = count.saturating_sub(1).max(1) as f32;
+    (0..count).map(move |i| i as f32 / last)
+}
+
+/// Where an instance of a visual effect is on its timeline.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct EffectTimeline {
+    effect_type: EffectType,
+    duration: f32,
+    keyframes_per_second: f32,
+    elapsed: f32,
+}
+
+impl EffectTimeline {
+    /// The current keyframe position, fractional between keyframes.
+    /// `None` once a [`EffectType::Single`] effect has finished.
+    fn keyframe(&self) -> Option<f32> {
+        let time = match self.effect_type {
+            EffectType::Single => (self.elapsed <= self.duration).then_some(self.elapsed)?,
+            EffectType::Looping if self.duration > 0. => self.elapsed % self.duration,
+            EffectType::Looping => 0.,
+            EffectType::Infinite => self.elapsed.min(self.duration),
+        };
+        Some(time * self.keyframes_per_second)
+    }
+}
+
+/// Animates the spawn rate of a continuous emitter along its [`EffectTimeline`].
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct EmitterSpawnRate {
+    keyframes: Vec<f32>,
+    current: Option<f32>,
+}
+
+/// Animates a point light along its [`EffectTimeline`].
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct EffectLight {
+    colors: Vec<Vec3>,
+    intensities: Vec<f32>,
+    radii: Vec<f32>,
+}
+
+fn advance_effect_timelines(timelines: Query<&mut EffectTimeline>, time: Res<Time>) {
+    for mut timeline in timelines {
+        timeline.elapsed += time.delta_secs();
+    }
+}
+
+fn animate_emitter_spawn_rates(
+    emitters: Query<(
+        &EffectTimeline,
+        &mut EmitterSpawnRate,
+        &mut bevy_hanabi::EffectSpawner,
+    )>,
+) {
+    for (timeline, mut spawn_rate, mut spawner) in emitters {
+        let rate = timeline
+            .keyframe()
+            .map_or(0., |k| sample_keyframes(&spawn_rate.keyframes, k));
+        if spawn_rate.current != Some(rate) {
+            spawn_rate.current = Some(rate);
+            spawner.settings = bevy_hanabi::SpawnerSettings::rate(rate.into());
+        }
+    }
+}
+
+fn animate_effect_lights(lights: Query<(&EffectTimeline, &EffectLight, &mut PointLight)>) {
+    use crate::magicka_level_model::light::MAGICKA_TO_LUMINOUS_INTENSITY;
+
+    for (timeline, light, mut point_light) in lights {
+        let Some(k) = timeline.keyframe() else {
+            if point_light.intensity != 0. {
+                point_light.intensity = 0.;
+            }
+            continue;
+        };
+        let color = sample_keyframes(&light.colors, k);
+        let intensity = sample_keyframes(&light.intensities, k);
+        let (color, magnitude) = color.normalize_and_length();
+        point_light.color = Color::linear_rgb(color.x, color.y, color.z);
+        point_light.intensity = magnitude * intensity * MAGICKA_TO_LUMINOUS_INTENSITY;
+        point_light.range = sample_keyframes(&light.radii, k);
+    }
+}
+
+/// Linearly interpolates between keyframes, holding the last one.
+fn sample_keyframes<T>(keyframes: &[T], keyframe: f32) -> T
+where
+    T: Copy + Default + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    let Some(last) = keyframes.len().checked_sub(1) else {
+        return T::default();
+    };
+    let keyframe = keyframe.max(0.);
+    let index = (keyframe.floor() as usize).min(last);
+    let next = (index + 1).min(last);
+    let t = (keyframe - index as f32).clamp(0., 1.);
+    keyframes[index] * (1. - t) + keyframes[next] * t
 }
 
 #[derive(Debug, Clone)]
 pub struct Effect {
     pub effect_type: EffectType,
     pub duration: f32,
-    // pub keyframes_per_second: i32,
+    pub keyframes_per_second: f32,
     // pub version: i32,
     pub emitters: Vec<Emitter>,
     pub lights: Vec<Light>,
 }
 
 #[derive(Debug, Clone)]
-pub enum Emitter {
-    Continuous,
-    Pulse,
+pub struct Emitter {
+    pub name: Option<String>,
+    pub kind: EmitterKind,
+    /// Seconds, (min, max)
+    pub lifetime: (f32, f32),
+    /// Units per second, (min, max)
+    pub speed: (f32, f32),
+    /// How far particles may stray from the effect direction, 0 being straight and 1 being 45°
+    pub spread: f32,
+    pub gravity: f32,
+    /// Over the lifetime of each particle
+    pub colors: Vec<Vec4>,
+    /// Over the lifetime of each particle
+    pub sizes: Vec<f32>,
+    pub texture: Option<String>,
+    pub blend: BlendMode,
 }
 
 #[derive(Debug, Clone)]
-pub struct Light;
+pub enum EmitterKind {
+    /// Particles per second, one per keyframe of the effect
+    Continuous { spawn_rate: Vec<f32> },
+    /// `count` particles at once, every `interval` seconds if positive
+    Pulse { count: f32, interval: f32 },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum BlendMode {
+    #[default]
+    Additive,
+    Alpha,
+}
+
+#[derive(Debug, Clone)]
+pub struct Light {
+    pub name: Option<String>,
+    pub position: Vec3,
+    /// One per keyframe of the effect
+    pub colors: Vec<Vec3>,
+    /// One per keyframe of the effect
+    pub intensities: Vec<f32>,
+    /// One per keyframe of the effect
+    pub radii: Vec<f32>,
+}
 
 fn parse(xml: &mut EventReader<impl Read>) -> Result<Effect, VisualEffectLoaderError> {
     let attributes = loop {
@@ -219,20 +514,16 @@ fn parse(xml: &mut EventReader<impl Read>) -> Result<Effect, VisualEffectLoaderE
                 attributes,
                 namespace: _,
             } => {
-                let emitter_name = attributes
+                let element_name = attributes
                     .into_iter()
                     .find(|a| a.name.local_name.eq_ignore_ascii_case("name"))
                     .map(|a| a.value);
                 if name.local_name.eq_ignore_ascii_case("ContinuousEmitter") {
-                    emitters.push(parse_emitter_continuous(
-                        xml,
-                        version,
-                        keyframes_per_second,
-                    )?);
+                    emitters.push(parse_emitter_continuous(xml, element_name, version)?);
                 } else if name.local_name.eq_ignore_ascii_case("PulseEmitter") {
-                    emitters.push(parse_emitter_pulse(xml, version)?);
+                    emitters.push(parse_emitter_pulse(xml, element_name, version)?);
                 } else if name.local_name.eq_ignore_ascii_case("Light") {
-                    lights.push(parse_light(xml)?);
+                    lights.push(parse_light(xml, element_name, version)?);
                 } else {
                     xml.skip()?;
                 }
@@ -244,7 +535,7 @@ fn parse(xml: &mut EventReader<impl Read>) -> Result<Effect, VisualEffectLoaderE
     Ok(Effect {
         effect_type,
         duration,
-        // keyframes_per_second,
+        keyframes_per_second: keyframes_per_second as f32,
         // version,
         emitters,
         lights,
@@ -253,30 +544,276 @@ fn parse(xml: &mut EventReader<impl Read>) -> Result<Effect, VisualEffectLoaderE
 
 fn parse_emitter_continuous(
     xml: &mut EventReader<impl Read>,
+    name: Option<String>,
     version: i32,
-    keyframes_per_second: i32,
 ) -> Result<Emitter, VisualEffectLoaderError> {
-    eprintln!("Note: Visual effect <ContinuousEmitter> elements are not yet supported");
-    xml.skip()?;
-    Ok(Emitter::Continuous)
+    let properties = parse_properties(xml, version)?;
+    let spawn_rate = properties
+        .numbers("SpawnRate")
+        .map(scalar_keyframes)
+        .unwrap_or_else(|| vec![10.]);
+    parse_emitter(&properties, name, EmitterKind::Continuous { spawn_rate })
 }
 
 fn parse_emitter_pulse(
     xml: &mut EventReader<impl Read>,
+    name: Option<String>,
     version: i32,
 ) -> Result<Emitter, VisualEffectLoaderError> {
-    eprintln!("Note: Visual effect <PulseEmitter> elements are not yet supported");
-    xml.skip()?;
-    Ok(Emitter::Pulse)
+    let properties = parse_properties(xml, version)?;
+    let count = properties
+        .numbers("Count")
+        .and_then(|k| scalar_keyframes(k).first().copied())
+        .unwrap_or(10.);
+    let interval = properties
+        .numbers("Interval")
+        .and_then(|k| scalar_keyframes(k).first().copied())
+        .unwrap_or(0.);
+    parse_emitter(&properties, name, EmitterKind::Pulse { count, interval })
+}
+
+fn parse_emitter(
+    properties: &Properties,
+    name: Option<String>,
+    kind: EmitterKind,
+) -> Result<Emitter, VisualEffectLoaderError> {
+    let lifetime = properties
+        .numbers("Lifetime")
+        .map(range)
+        .unwrap_or((1., 1.));
+    let speed = properties.numbers("Speed").map(range).unwrap_or((1., 1.));
+    let spread = properties
+        .numbers("Spread")
+        .and_then(|k| scalar_keyframes(k).first().copied())
+        // Stored in degrees
+        .map_or(0., |degrees| degrees.to_radians().tan());
+    let gravity = properties
+        .numbers("Gravity")
+        .and_then(|k| scalar_keyframes(k).first().copied())
+        .unwrap_or(0.);
+    let colors = properties
+        .numbers("Color")
+        .map(color_keyframes)
+        .unwrap_or_else(|| vec![Vec4::ONE, Vec4::new(1., 1., 1., 0.)]);
+    let sizes = properties
+        .numbers("Size")
+        .map(scalar_keyframes)
+        .unwrap_or_else(|| vec![1.]);
+    let texture = properties.text("Texture").map(ToOwned::to_owned);
+    let blend = match properties.text("Blend") {
+        Some(s) if s.eq_ignore_ascii_case("Alpha") => BlendMode::Alpha,
+        Some(s) if s.eq_ignore_ascii_case("Additive") => BlendMode::Additive,
+        Some(_) => return Err(VisualEffectLoaderError::Parse("Invalid emitter blend mode")),
+        None => BlendMode::default(),
+    };
+
+    Ok(Emitter {
+        name,
+        kind,
+        lifetime,
+        speed,
+        spread,
+        gravity,
+        colors,
+        sizes,
+        texture,
+        blend,
+    })
+}
+
+fn parse_light(
+    xml: &mut EventReader<impl Read>,
+    name: Option<String>,
+    version: i32,
+) -> Result<Light, VisualEffectLoaderError> {
+    let properties = parse_properties(xml, version)?;
+    let position = properties
+        .numbers("Position")
+        .and_then(|k| k.first())
+        .map_or(Vec3::ZERO, |v| vec3_from(v));
+    let colors = properties
+        .numbers("Color")
+        .map(|k| k.iter().map(|v| vec3_from(v)).collect())
+        .unwrap_or_else(|| vec![Vec3::ONE]);
+    let intensities = properties
+        .numbers("Intensity")
+        .map(scalar_keyframes)
+        .unwrap_or_else(|| vec![1.]);
+    let radii = properties
+        .numbers("Radius")
+        .map(scalar_keyframes)
+        .unwrap_or_else(|| vec![5.]);
+    Ok(Light {
+        name,
+        position,
+        colors,
+        intensities,
+        radii,
+    })
+}
+
+/// The properties of an emitter or light, from its child elements.
+///
+/// Before version 2, a property element's text is its one value. From version 2, the property
+/// element instead holds one child element per keyframe, whose text is the value at that keyframe.
+/// Each value is a list of numbers separated by `,` or whitespace, or otherwise text.
+#[derive(Debug, Default)]
+struct Properties(Vec<(String, PropertyValue)>);
+
+#[derive(Debug)]
+enum PropertyValue {
+    Numbers(Vec<Vec<f32>>),
+    Text(String),
+}
+
+impl Properties {
+    fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.0
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    fn numbers(&self, name: &str) -> Option<&[Vec<f32>]> {
+        match self.get(name)? {
+            PropertyValue::Numbers(keyframes) => Some(keyframes),
+            PropertyValue::Text(text) => {
+                warn!("Visual effect property {name:?} is not numeric: {text:?}");
+                None
+            }
+        }
+    }
+
+    fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::Text(text) => Some(text),
+            PropertyValue::Numbers(_) => None,
+        }
+    }
+}
+
+fn parse_properties(
+    xml: &mut EventReader<impl Read>,
+    version: i32,
+) -> Result<Properties, VisualEffectLoaderError> {
+    let mut properties = Properties::default();
+    loop {
+        match xml.next()? {
+            XmlEvent::EndElement { .. } => break,
+            XmlEvent::EndDocument => unreachable!(),
+            XmlEvent::StartElement { name, .. } => {
+                let keyframes = if version < 2 {
+                    vec![read_text(xml)?]
+                } else {
+                    read_keyframes(xml)?
+                };
+                properties
+                    .0
+                    .push((name.local_name, property_value(keyframes)));
+            }
+            _ => {}
+        }
+    }
+    Ok(properties)
+}
+
+/// Reads the text of each keyframe element in a property element.
+fn read_keyframes(
+    xml: &mut EventReader<impl Read>,
+) -> Result<Vec<String>, VisualEffectLoaderError> {
+    let mut keyframes = Vec::new();
+    loop {
+        match xml.next()? {
+            XmlEvent::EndElement { .. } => break,
+            XmlEvent::EndDocument => unreachable!(),
+            XmlEvent::StartElement { .. } => keyframes.push(read_text(xml)?),
+            _ => {}
+        }
+    }
+    Ok(keyframes)
 }
 
-fn parse_light(xml: &mut EventReader<impl Read>) -> Result<Light, VisualEffectLoaderError> {
-    eprintln!("Note: Visual effect <Light> elements are not yet supported");
-    xml.skip()?;
-    Ok(Light)
+fn read_text(xml: &mut EventReader<impl Read>) -> Result<String, VisualEffectLoaderError> {
+    let mut text = String::new();
+    loop {
+        match xml.next()? {
+            XmlEvent::EndElement { .. } => break,
+            XmlEvent::EndDocument => unreachable!(),
+            XmlEvent::StartElement { .. } => {
+                return Err(VisualEffectLoaderError::Parse(
+                    "Unexpected element in visual effect value",
+                ));
+            }
+            XmlEvent::Characters(s) | XmlEvent::CData(s) => text.push_str(&s),
+            _ => {}
+        }
+    }
+    Ok(text.trim().to_owned())
+}
+
+fn property_value(keyframes: Vec<String>) -> PropertyValue {
+    let numbers: Option<Vec<Vec<f32>>> = keyframes
+        .iter()
+        .map(|keyframe| {
+            keyframe
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse().ok())
+                .collect()
+        })
+        .collect();
+    match numbers {
+        Some(numbers) if !numbers.is_empty() => PropertyValue::Numbers(numbers),
+        // Text, like a texture name, isn't animated
+        _ => PropertyValue::Text(keyframes.into_iter().next().unwrap_or_default()),
+    }
+}
+
+fn scalar_keyframes(keyframes: &[Vec<f32>]) -> Vec<f32> {
+    keyframes
+        .iter()
+        .filter_map(|values| values.first().copied())
+        .collect()
+}
+
+/// A (min, max) range, given as either one keyframe of two values or two keyframes.
+fn range(keyframes: &[Vec<f32>]) -> (f32, f32) {
+    match keyframes {
+        [values] => match values.as_slice() {
+            [min, max, ..] => (*min, *max),
+            [value] => (*value, *value),
+            [] => (0., 0.),
+        },
+        [min, max, ..] => (
+            min.first().copied().unwrap_or(0.),
+            max.first().copied().unwrap_or(0.),
+        ),
+        [] => (0., 0.),
+    }
+}
+
+fn vec3_from(values: &[f32]) -> Vec3 {
+    match values {
+        [x, y, z, ..] => vec3(*x, *y, *z),
+        [v] => Vec3::splat(*v),
+        _ => Vec3::ZERO,
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Colors are RGB or RGBA, from 0 to 1 like XNA's vectors.
+fn color_keyframes(keyframes: &[Vec<f32>]) -> Vec<Vec4> {
+    keyframes
+        .iter()
+        .map(|values| match values.as_slice() {
+            [r, g, b, a, ..] => vec4(*r, *g, *b, *a),
+            [r, g, b] => vec4(*r, *g, *b, 1.),
+            [v] => vec4(*v, *v, *v, 1.),
+            _ => Vec4::ONE,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Reflect)]
 pub enum EffectType {
     Single,
     Looping,
@@ -295,3 +832,150 @@ impl FromStr for EffectType {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{BlendMode, Effect, EffectType, EmitterKind, parse};
+    use bevy::math::{vec3, vec4};
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::Path;
+    use xml::{EventReader, ParserConfig};
+
+    fn read(xml: &str) -> Effect {
+        let mut xml = EventReader::new_with_config(
+            xml.as_bytes(),
+            ParserConfig::new().allow_multiple_root_elements(false),
+        );
+        parse(&mut xml).unwrap()
+    }
+
+    #[test]
+    fn parse_effect() {
+        let effect = read(
+            r#"
+            <Effect type="Looping" duration="2" keyFramesPerSecond="10" version="2">
+                <ContinuousEmitter name="flames">
+                    <Texture><Key>fire</Key></Texture>
+                    <Blend><Key>Additive</Key></Blend>
+                    <SpawnRate><Key>20</Key><Key>40</Key></SpawnRate>
+                    <Lifetime><Key>0.5, 1</Key></Lifetime>
+                    <Speed><Key>1</Key><Key>2</Key></Speed>
+                    <Spread><Key>45</Key></Spread>
+                    <Color><Key>1, 0.5, 0, 1</Key><Key>1, 0, 0, 0</Key></Color>
+                    <Size><Key>0.2</Key><Key>0.4</Key></Size>
+                </ContinuousEmitter>
+                <PulseEmitter name="sparks">
+                    <Count><Key>12</Key></Count>
+                    <Interval><Key>0.5</Key></Interval>
+                    <Blend><Key>Alpha</Key></Blend>
+                </PulseEmitter>
+                <Light name="glow">
+                    <Position><Key>0 1 0</Key></Position>
+                    <Color><Key>1 0.6 0.2</Key></Color>
+                    <Intensity><Key>2</Key><Key>1</Key></Intensity>
+                    <Radius><Key>4</Key></Radius>
+                </Light>
+            </Effect>
+        "#,
+        );
+        assert!(matches!(effect.effect_type, EffectType::Looping));
+        assert_eq!(effect.duration, 2.);
+        assert_eq!(effect.keyframes_per_second, 10.);
+
+        let [flames, sparks] = effect.emitters.as_slice() else {
+            panic!("expected 2 emitters, got {:?}", effect.emitters);
+        };
+        assert_eq!(flames.name.as_deref(), Some("flames"));
+        let EmitterKind::Continuous { spawn_rate } = &flames.kind else {
+            panic!("expected a continuous emitter, got {:?}", flames.kind);
+        };
+        assert_eq!(spawn_rate, &[20., 40.]);
+        assert_eq!(flames.lifetime, (0.5, 1.));
+        assert_eq!(flames.speed, (1., 2.));
+        assert!((flames.spread - 1.).abs() < 1e-6);
+        assert_eq!(flames.colors, [vec4(1., 0.5, 0., 1.), vec4(1., 0., 0., 0.)]);
+        assert_eq!(flames.sizes, [0.2, 0.4]);
+        assert_eq!(flames.texture.as_deref(), Some("fire"));
+        assert!(matches!(flames.blend, BlendMode::Additive));
+
+        let EmitterKind::Pulse { count, interval } = sparks.kind else {
+            panic!("expected a pulse emitter, got {:?}", sparks.kind);
+        };
+        assert_eq!((count, interval), (12., 0.5));
+        assert_eq!(sparks.lifetime, (1., 1.));
+        assert!(matches!(sparks.blend, BlendMode::Alpha));
+
+        let [glow] = effect.lights.as_slice() else {
+            panic!("expected 1 light, got {:?}", effect.lights);
+        };
+        assert_eq!(glow.position, vec3(0., 1., 0.));
+        assert_eq!(glow.colors, [vec3(1., 0.6, 0.2)]);
+        assert_eq!(glow.intensities, [2., 1.]);
+        assert_eq!(glow.radii, [4.]);
+    }
+
+    /// Before version 2, each property has one value instead of keyframes.
+    #[test]
+    fn parse_effect_version_1() {
+        let effect = read(
+            r#"
+            <Effect type="Single" duration="1">
+                <PulseEmitter name="puff">
+                    <Count>8</Count>
+                    <Color>1 1 1 0.5</Color>
+                    <Texture>smoke</Texture>
+                </PulseEmitter>
+            </Effect>
+        "#,
+        );
+        let [puff] = effect.emitters.as_slice() else {
+            panic!("expected 1 emitter, got {:?}", effect.emitters);
+        };
+        let EmitterKind::Pulse { count, .. } = puff.kind else {
+            panic!("expected a pulse emitter, got {:?}", puff.kind);
+        };
+        assert_eq!(count, 8.);
+        assert_eq!(puff.colors, [vec4(1., 1., 1., 0.5)]);
+        assert_eq!(puff.texture.as_deref(), Some("smoke"));
+    }
+
+    /// Every visual effect in Content can be parsed.
+    #[test]
+    #[ignore = "needs Magicka Content"]
+    fn parse_content_effects() {
+        assert!(
+            crate::magicka_assets::init_content_root(None),
+            "Magicka Content not found"
+        );
+        let content = crate::magicka_assets::content_root();
+        let effects = crate::magicka_assets::find_path_ignore_ascii_case(
+            &Path::new(content.as_ref() as &std::ffi::OsStr).join("Effects"),
+        )
+        .unwrap();
+
+        let mut dirs = vec![effects];
+        let mut count = 0;
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"))
+                {
+                    let mut xml = EventReader::new_with_config(
+                        BufReader::new(File::open(&path).unwrap()),
+                        ParserConfig::new().allow_multiple_root_elements(false),
+                    );
+                    if let Err(e) = parse(&mut xml) {
+                        panic!("{}: {e}", path.display());
+                    }
+                    count += 1;
+                }
+            }
+        }
+        assert!(count > 0, "no visual effects in Content");
+    }
+}
@@ -1,11 +1,12 @@
 pub(crate) mod animated_parts;
-mod bitree;
-mod collision;
+pub(crate) mod bitree;
+pub(crate) mod collision;
 mod d3dx;
 mod effect;
+pub(crate) mod force_field;
 pub(crate) mod light;
-mod liquid;
-mod nav_mesh;
+pub(crate) mod liquid;
+pub(crate) mod nav_mesh;
 mod visual_effects;
 mod xna_geom;
 
@@ -14,14 +15,18 @@ pub use collision::Layers;
 
 use bevy::{ecs::relationship::RelatedSpawnerCommands, prelude::*};
 use std::collections::HashMap;
-use typed_path::PlatformPath;
 
-use crate::magicka_assets::content_root;
+use crate::magicka_assets::level::MagickaLevel;
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins((nav_mesh::plugin, light::plugin));
+    app.add_plugins((nav_mesh::plugin, light::plugin, force_field::plugin));
+    app.register_type::<collision::CollisionMaterial>();
+    app.init_resource::<collision::LevelCollisionModes>();
+    app.register_type::<collision::LevelCollisionModes>();
     app.add_plugins(MaterialPlugin::<effect::CharacterMaterial>::default());
     app.register_asset_reflect::<effect::CharacterMaterial>();
+    app.add_plugins(MaterialPlugin::<effect::DeferredMaterial>::default());
+    app.register_asset_reflect::<effect::DeferredMaterial>();
 
     app.add_systems(
         PostUpdate,
@@ -33,31 +38,37 @@ pub fn plugin(app: &mut App) {
             debug_locators,
         ),
     );
+    app.add_systems(
+        PostUpdate,
+        bitree::cull_bitree_nodes
+            .after(bevy::camera::visibility::VisibilitySystems::UpdateFrusta)
+            .before(bevy::camera::visibility::VisibilitySystems::VisibilityPropagate),
+    );
+    app.add_systems(FixedUpdate, animated_parts::follow_animation);
+    app.add_systems(PreUpdate, respawn_modified_levels);
+    app.add_systems(Update, collision::apply_collision_modes);
     app.add_observer(trigger_area_enter);
     app.add_observer(trigger_area_leave);
 }
 
+/// A spawned [`MagickaLevel`]. Its content is respawned when the asset is modified.
+#[derive(Component, Debug)]
+pub struct LevelInstance {
+    pub level: Handle<MagickaLevel>,
+    archipelago: Entity,
+    content: Entity,
+}
+
+/// Spawns a level from its loaded asset.
 pub fn spawn_level(
-    InRef(level_path): InRef<PlatformPath>,
+    In(level_handle): In<Handle<MagickaLevel>>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut animation_clips: ResMut<Assets<AnimationClip>>,
-    mut animation_graphs: ResMut<Assets<AnimationGraph>>,
-    assets: Res<AssetServer>,
-    mut nav_mesh_params: nav_mesh::SpawnParams,
+    levels: Res<Assets<MagickaLevel>>,
+    collision_meshes: Res<Assets<collision::CollisionMesh>>,
 ) -> Result<Entity> {
-    // let content_root = NativePathBuf::from(crate::magicka_assets::content_root());
-    let content_path = level_path;
-    let level_path = content_root().join_checked(content_path).unwrap();
-    let level_bytes = std::fs::read(level_path.as_ref() as &std::ffi::OsStr)?;
-    let level_asset = remagic::parse_level(&level_bytes).map_err(|e| {
-        panic!("parsing failed: {}", e.inner());
-    })?;
-    let level_data = level_asset
-        .inner()
-        .as_ref()
-        .ok_or_else(|| todo!("null level"))?;
+    let level = levels
+        .get(&level_handle)
+        .ok_or("level asset is not loaded")?;
 
     let mut root = commands.spawn((
         Name::new("Level"),
@@ -65,9 +76,76 @@ pub fn spawn_level(
         Visibility::default(),
     ));
 
+    let mut spawned = None;
     root.with_children(|parent| {
         let nav_mesh_setup = nav_mesh::setup_for_level(parent);
+        let content = spawn_level_content(parent, level, &collision_meshes, &nav_mesh_setup);
+        spawned = Some((nav_mesh_setup.archipelago, content));
+    });
+    let (archipelago, content) = spawned.unwrap();
+    root.insert(LevelInstance {
+        level: level_handle,
+        archipelago,
+        content,
+    });
+
+    Ok(root.id())
+}
+
+fn respawn_modified_levels(
+    mut events: MessageReader<AssetEvent<MagickaLevel>>,
+    instances: Query<(Entity, &mut LevelInstance)>,
+    levels: Res<Assets<MagickaLevel>>,
+    collision_meshes: Res<Assets<collision::CollisionMesh>>,
+    mut commands: Commands,
+) {
+    let modified: Vec<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if modified.is_empty() {
+        return;
+    }
+
+    for (entity, mut instance) in instances {
+        if !modified.contains(&instance.level.id()) {
+            continue;
+        }
+        let Some(level) = levels.get(&instance.level) else {
+            continue;
+        };
+        info!("Respawning modified level {:?}", instance.level.path());
+
+        commands.entity(instance.content).despawn();
+        // Keep the archipelago, since characters refer to it
+        let nav_mesh_setup = nav_mesh::NavMeshSetup {
+            archipelago: instance.archipelago,
+        };
+        commands.entity(entity).with_children(|parent| {
+            instance.content =
+                spawn_level_content(parent, level, &collision_meshes, &nav_mesh_setup);
+        });
+    }
+}
+
+fn spawn_level_content(
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    level_assets: &MagickaLevel,
+    collision_meshes: &Assets<collision::CollisionMesh>,
+    nav_mesh_setup: &nav_mesh::NavMeshSetup,
+) -> Entity {
+    let level_data = &level_assets.level;
 
+    let mut content = parent.spawn((
+        Name::new("Level Content"),
+        Transform::default(),
+        Visibility::default(),
+    ));
+
+    content.with_children(|parent| {
         if let Some(model) = &level_data.model {
             let mut parent = parent.spawn((
                 Name::new("BiTreeModel"),
@@ -75,14 +153,7 @@ pub fn spawn_level(
                 Visibility::default(),
             ));
             parent.with_children(|parent| {
-                bitree::spawn_bitree_model(
-                    meshes.as_mut(),
-                    materials.as_mut(),
-                    &assets,
-                    parent,
-                    model,
-                    content_path,
-                );
+                bitree::spawn_bitree_model(parent, model, &level_assets.bitrees);
             });
         }
 
@@ -96,43 +167,47 @@ pub fn spawn_level(
             }
         }
 
-        for animated_part in &level_data.animated_parts {
+        for (animated_part, part_assets) in level_data
+            .animated_parts
+            .iter()
+            .zip(&level_assets.animated_parts)
+        {
             animated_parts::spawn_animated_part(
                 parent,
                 animated_part,
-                &level_asset,
-                content_path,
-                &mut meshes,
-                &mut materials,
-                animation_clips.reborrow(),
-                animation_graphs.reborrow(),
-                &assets,
+                part_assets,
+                collision_meshes,
+                &level_assets.effects,
                 &mut light_entities,
-                &mut nav_mesh_params,
-                &nav_mesh_setup,
+                nav_mesh_setup,
             );
         }
 
         for visual_effect in &level_data.visual_effects {
-            visual_effects::spawn_visual_effect(Spawner::Parent(parent), visual_effect, &assets);
+            visual_effects::spawn_visual_effect(
+                Spawner::Parent(parent),
+                visual_effect,
+                &level_assets.effects,
+            );
         }
 
         for physics_entity in &level_data.physics_entities {
             warn!("unhandled level {physics_entity:#?}");
         }
-        for liquid in &level_data.waters {
-            liquid::spawn_liquid(
+        for (liquid, liquid_assets) in level_data.waters.iter().zip(&level_assets.liquids) {
+            liquid::spawn_liquid(Spawner::Parent(parent), liquid, liquid_assets.as_ref());
+        }
+        for (force_field, force_field_assets) in level_data
+            .force_fields
+            .iter()
+            .zip(&level_assets.force_fields)
+        {
+            force_field::spawn_force_field(
                 Spawner::Parent(parent),
-                liquid,
-                content_path,
-                &mut meshes,
-                &mut materials,
-                &assets,
+                force_field,
+                force_field_assets.as_ref(),
             );
         }
-        for force_field in &level_data.force_fields {
-            warn!("unhandled level {force_field:#?}");
-        }
 
         // Collision
         parent
@@ -141,33 +216,37 @@ pub fn spawn_level(
                 avian3d::prelude::RigidBody::Static,
             ))
             .with_children(|parent| {
-                for layer in &level_data.collision {
-                    if let Some(collider) = collision::to_collider(layer) {
-                        parent.spawn((
-                            collider,
-                            avian3d::prelude::CollisionLayers::new(
-                                collision::Layers::Level,
-                                avian3d::prelude::LayerMask::ALL,
-                            ),
-                        ));
-                    }
+                for collision_mesh in level_assets
+                    .collision
+                    .iter()
+                    .flatten()
+                    .filter_map(|handle| collision_meshes.get(handle))
+                {
+                    parent.spawn((
+                        collision_mesh.collider.clone(),
+                        collision_mesh.material,
+                        collision::LevelCollider,
+                        avian3d::prelude::CollisionLayers::new(
+                            collision::Layers::Level,
+                            avian3d::prelude::LayerMask::ALL,
+                        ),
+                    ));
                 }
             });
 
-        if let Some(camera_mesh) = &level_data.camera_mesh {
-            let collider = collision::to_collider(camera_mesh);
-            if let Some(collider) = collider {
-                // Just used to store the Collider for direct raycasting.
-                parent.spawn((Name::new("Camera Mesh"), CameraMesh { collider }));
-            }
+        if let Some(collider) = &level_assets.camera_mesh {
+            // Just used to store the Collider for direct raycasting.
+            parent.spawn((
+                Name::new("Camera Mesh"),
+                CameraMesh {
+                    collider: collider.clone(),
+                },
+            ));
         }
 
-        nav_mesh::spawn(
-            parent,
-            &level_data.nav_mesh,
-            &mut nav_mesh_params,
-            &nav_mesh_setup,
-        );
+        if let Some(nav_mesh) = &level_assets.nav_mesh {
+            nav_mesh::spawn(parent, nav_mesh, nav_mesh_setup);
+        }
 
         // Spawn in areas used for trigger checks
         // Also spawn an extra global area that triggers use to check if entities exist anywhere
@@ -204,9 +283,7 @@ pub fn spawn_level(
         }
     });
 
-    let instance_id = root.id();
-
-    Ok(instance_id)
+    content.id()
 }
 
 enum Spawner<'w, /*'s,*/ 'r> {
@@ -250,6 +327,9 @@ impl TriggerArea {
     pub fn num_characters(&self) -> usize {
         self.characters.len()
     }
+    pub fn contains(&self, character: Entity) -> bool {
+        self.characters.contains_key(&character)
+    }
     pub fn num_characters_of_type(&self, type_name: &str) -> usize {
         self.characters
             .iter()
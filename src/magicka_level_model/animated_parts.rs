@@ -1,12 +1,23 @@
-use crate::magicka_level_model::{Spawner, spawn_locator, visual_effects::spawn_visual_effect};
+use crate::{
+    magicka_assets::{AssetCreator, LabeledAssetCreator},
+    magicka_level_model::{Spawner, spawn_locator, visual_effects::spawn_visual_effect},
+};
 
-use super::{d3dx, map_mat4, map_quat, map_vec3, xna_geom};
+use super::{
+    collision::{CollisionMaterial, CollisionMesh},
+    d3dx,
+    liquid::LiquidAssets,
+    map_mat4, map_quat, map_vec3, xna_geom,
+};
 use bevy::{
     animation::{AnimatedBy, AnimationTargetId, animated_field},
+    asset::LoadContext,
     ecs::relationship::RelatedSpawnerCommands,
+    light::NotShadowCaster,
     prelude::*,
     render::render_resource::Face,
 };
+use bevy_landmass::NavMesh3d;
 use remagic::xnb_readers::magicka_content::{AnimatedLevelPart, AnimationChannel};
 use std::collections::HashMap;
 use typed_path::PlatformPath;
@@ -17,13 +28,14 @@ pub(crate) fn debug_animated_parts(
             &GlobalTransform,
             Has<Mesh3d>,
             Has<MeshMaterial3d<StandardMaterial>>,
+            Has<MeshMaterial3d<super::effect::DeferredMaterial>>,
         ),
         With<AnimatedPart>,
     >,
     mut gizmos: Gizmos,
 ) {
-    for (transform, has_mesh, has_material) in parts {
-        if !has_mesh || !has_material {
+    for (transform, has_mesh, has_standard_material, has_deferred_material) in parts {
+        if !has_mesh || !(has_standard_material || has_deferred_material) {
             gizmos.axes(*transform, 1.);
         }
     }
@@ -32,18 +44,175 @@ pub(crate) fn debug_animated_parts(
 #[derive(Component)]
 pub struct AnimatedPart {}
 
-pub(crate) fn spawn_animated_part(
-    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+/// A part with collision is a kinematic body, so it can push and carry what's on it. Its animation
+/// moves this entity instead, which the part follows by velocity.
+#[derive(Component, Debug)]
+pub struct KinematicAnimationTarget(pub Entity);
+
+pub(crate) fn follow_animation(
+    parts: Query<(
+        &KinematicAnimationTarget,
+        &avian3d::prelude::Position,
+        &avian3d::prelude::Rotation,
+        &mut avian3d::prelude::LinearVelocity,
+        &mut avian3d::prelude::AngularVelocity,
+    )>,
+    transform_helper: TransformHelper,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    if delta <= 0. {
+        return;
+    }
+    for (target, position, rotation, mut linear_velocity, mut angular_velocity) in parts {
+        let Ok(target) = transform_helper.compute_global_transform(target.0) else {
+            continue;
+        };
+        let (_, target_rotation, target_translation) = target.to_scale_rotation_translation();
+
+        let linear = (target_translation - position.0) / delta;
+        let mut rotation_delta = target_rotation * rotation.0.inverse();
+        // Take the short way around
+        if rotation_delta.w < 0. {
+            rotation_delta = -rotation_delta;
+        }
+        let angular = rotation_delta.to_scaled_axis() / delta;
+
+        if linear_velocity.0 != linear {
+            linear_velocity.0 = linear;
+        }
+        if angular_velocity.0 != angular {
+            angular_velocity.0 = angular;
+        }
+    }
+}
+
+/// The assets of an [`AnimatedLevelPart`] and its children.
+#[derive(Debug)]
+pub(crate) struct AnimatedPartAssets {
+    model: Option<Handle<WorldAsset>>,
+    collision: Option<Handle<CollisionMesh>>,
+    liquids: Vec<Option<LiquidAssets>>,
+    nav_mesh: Option<Handle<NavMesh3d>>,
+    animation_graph: Handle<AnimationGraph>,
+    animation_node: AnimationNodeIndex,
+    children: Vec<AnimatedPartAssets>,
+}
+
+/// Hides the meshes of a part's model, or stops them casting shadows, by mesh name.
+fn apply_mesh_settings(world: &mut World, mesh_settings: &HashMap<String, (bool, bool)>) {
+    let meshes: Vec<_> = world
+        .query::<(Entity, &Name, &Children)>()
+        .iter(world)
+        .filter_map(|(entity, name, children)| {
+            let &(visible, cast_shadows) = mesh_settings.get(name.as_str())?;
+            let parts: Vec<_> = children
+                .iter()
+                .filter(|&part| world.get::<Mesh3d>(part).is_some())
+                .collect();
+            (!parts.is_empty()).then_some((entity, visible, cast_shadows, parts))
+        })
+        .collect();
+    for (entity, visible, cast_shadows, parts) in meshes {
+        if !visible {
+            world.entity_mut(entity).insert(Visibility::Hidden);
+        }
+        if !cast_shadows {
+            for part in parts {
+                world.entity_mut(part).insert(NotShadowCaster);
+            }
+        }
+    }
+}
+
+/// Adds the part's assets as sub-assets labeled by the part name, e.g. `part/<name>` for its model
+/// and `clip/<name>` for its animation.
+pub(crate) fn load_animated_part(
     animated_part: &AnimatedLevelPart,
     shared_resources: &impl remagic::SharedResources,
     content_path: &PlatformPath,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    mut animation_clips: Mut<Assets<AnimationClip>>,
-    mut animation_graphs: Mut<Assets<AnimationGraph>>,
-    assets: &AssetServer,
+    load_context: &mut LoadContext,
+) -> AnimatedPartAssets {
+    let name = &animated_part.name;
+
+    let model = animated_part.model.as_ref().map(|model| {
+        let mut world = World::default();
+        let mut assets = LabeledAssetCreator::new(load_context, format!("part/{name}/"));
+        spawn_xna_model(
+            model,
+            shared_resources,
+            content_path,
+            world.commands(),
+            &mut assets,
+        );
+        world.flush();
+        apply_mesh_settings(&mut world, &animated_part.mesh_settings);
+        load_context.add_labeled_asset(format!("part/{name}"), WorldAsset::new(world))
+    });
+
+    let collision = if let Some(remagic::xnb_readers::magicka_content::AnimatedLevelPartCollision {
+        material,
+        vertices: Some(vertices),
+        triangle_vertex_indices: indices,
+    }) = &animated_part.collision
+    {
+        Some(CollisionMesh {
+            collider: super::collision::to_collider_raw(vertices.0.as_slice(), indices),
+            material: CollisionMaterial::from_index(usize::from(*material)),
+        })
+    } else {
+        None
+    };
+
+    let mut assets = LabeledAssetCreator::new(load_context, format!("part/{name}/liquid/"));
+    let liquids = animated_part
+        .liquids
+        .iter()
+        .map(|liquid| super::liquid::load_liquid(liquid, content_path, &mut assets))
+        .collect();
+
+    let ground: Vec<_> = collision.iter().map(|mesh| &mesh.collider).collect();
+    let nav_mesh = animated_part
+        .nav_mesh
+        .as_ref()
+        .and_then(|nav_mesh| super::nav_mesh::load(nav_mesh, &ground))
+        .map(|nav_mesh| load_context.add_labeled_asset(format!("part/{name}/navmesh"), nav_mesh));
+
+    let collision = collision
+        .map(|mesh| load_context.add_labeled_asset(format!("part/{name}/collision"), mesh));
+
+    let target_id = AnimationTargetId::from_name(&Name::new("root"));
+    let mut clip = AnimationClip::default();
+    add_channel_to_clip(&mut clip, &animated_part.animation, target_id);
+    clip.set_duration(animated_part.animation_duration);
+    let clip = load_context.add_labeled_asset(format!("clip/{name}"), clip);
+    let (graph, animation_node) = AnimationGraph::from_clip(clip);
+    let animation_graph = load_context.add_labeled_asset(format!("clip/{name}/graph"), graph);
+
+    let children = animated_part
+        .children
+        .iter()
+        .map(|child| load_animated_part(child, shared_resources, content_path, load_context))
+        .collect();
+
+    AnimatedPartAssets {
+        model,
+        collision,
+        liquids,
+        nav_mesh,
+        animation_graph,
+        animation_node,
+        children,
+    }
+}
+
+pub(crate) fn spawn_animated_part(
+    parent: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    animated_part: &AnimatedLevelPart,
+    part_assets: &AnimatedPartAssets,
+    collision_meshes: &Assets<CollisionMesh>,
+    effects: &HashMap<String, Handle<crate::magicka_assets::visual_effect::VisualEffect>>,
     light_entities: &mut HashMap<&String, Entity>,
-    nav_mesh_params: &mut crate::magicka_level_model::nav_mesh::SpawnParams,
     nav_mesh_setup: &crate::magicka_level_model::nav_mesh::NavMeshSetup,
 ) {
     let transform = if let Some((_, pose1)) = animated_part.animation.keyframes.first() {
@@ -60,20 +229,6 @@ pub(crate) fn spawn_animated_part(
         "unhandled animated level part {:?} affect_shields={:?}",
         &animated_part.name, animated_part.affect_shields
     );
-    for (mesh_name, (visible, cast_shadows)) in &animated_part.mesh_settings {
-        if !visible {
-            warn!(
-                "unhandled animated level part {:?} setting for mesh {mesh_name:?}: visible=false",
-                &animated_part.name
-            );
-        }
-        if !cast_shadows {
-            warn!(
-                "unhandled animated level part {:?} setting for mesh {mesh_name:?}: cast_shadows=false",
-                &animated_part.name
-            );
-        }
-    }
     let mut entity_commands = parent.spawn((
         Name::new(animated_part.name.to_owned()),
         transform,
@@ -81,18 +236,20 @@ pub(crate) fn spawn_animated_part(
         AnimatedPart {},
     ));
 
-    if let Some(remagic::xnb_readers::magicka_content::AnimatedLevelPartCollision {
-        material,
-        vertices: Some(vertices),
-        triangle_vertex_indices: indices,
-    }) = &animated_part.collision
-    {
+    let collision_mesh = part_assets
+        .collision
+        .as_ref()
+        .and_then(|handle| collision_meshes.get(handle));
+    if let Some(collision_mesh) = collision_mesh {
         use avian3d::prelude::*;
         entity_commands.insert((
             RigidBody::Kinematic,
-            super::collision::to_collider_raw(vertices.0.as_slice(), indices),
+            TransformInterpolation,
+            collision_mesh.collider.clone(),
+            collision_mesh.material,
+            super::collision::LevelCollider,
             CollisionLayers::new(
-                super::collision::Layers::Trigger,
+                super::collision::Layers::Level,
                 LayerMask::ALL
                     & !LayerMask::from(
                         super::collision::Layers::Level.to_bits()
@@ -121,89 +278,61 @@ pub(crate) fn spawn_animated_part(
     }
 
     entity_commands.with_children(|parent| {
-        for liquid in &animated_part.liquids {
-            super::liquid::spawn_liquid(
-                Spawner::Parent(parent),
-                liquid,
-                content_path,
-                meshes,
-                materials,
-                assets,
-            );
+        if let Some(model) = &part_assets.model {
+            parent.spawn((WorldAssetRoot(model.clone()), Transform::default()));
+        }
+        for (liquid, liquid_assets) in animated_part.liquids.iter().zip(&part_assets.liquids) {
+            super::liquid::spawn_liquid(Spawner::Parent(parent), liquid, liquid_assets.as_ref());
         }
         for visual_effect in &animated_part.effects {
-            spawn_visual_effect(Spawner::Parent(parent), visual_effect, assets);
+            spawn_visual_effect(Spawner::Parent(parent), visual_effect, effects);
         }
         for (name, locator) in &animated_part.locators {
             spawn_locator(Spawner::Parent(parent), name.to_owned(), locator);
         }
 
-        if let Some(nav_mesh) = &animated_part.nav_mesh {
-            super::nav_mesh::spawn(parent, nav_mesh, nav_mesh_params, nav_mesh_setup);
+        if let Some(nav_mesh) = &part_assets.nav_mesh {
+            super::nav_mesh::spawn(parent, nav_mesh, nav_mesh_setup);
         }
 
-        for child in &animated_part.children {
+        for (child, child_assets) in animated_part.children.iter().zip(&part_assets.children) {
             spawn_animated_part(
                 parent,
                 child,
-                shared_resources,
-                content_path,
-                meshes,
-                materials,
-                animation_clips.reborrow(),
-                animation_graphs.reborrow(),
-                assets,
+                child_assets,
+                collision_meshes,
+                effects,
                 light_entities,
-                nav_mesh_params,
                 nav_mesh_setup,
             );
         }
     });
 
-    setup_animation(
-        animated_part,
-        entity_commands.reborrow(),
-        animation_clips,
-        animation_graphs,
-    );
-
-    let entity = entity_commands.id();
-    // TODO move this above children?
-    if let Some(model) = animated_part.model.as_ref() {
-        let model_entity = spawn_xna_model(
-            model,
-            shared_resources,
-            content_path,
-            parent.commands(),
-            meshes,
-            materials,
-            assets,
-        );
-        parent.commands().entity(entity).add_child(model_entity);
-    }
-}
-
-fn setup_animation(
-    part: &AnimatedLevelPart,
-    mut part_commands: EntityCommands<'_>,
-    mut animation_clips: Mut<Assets<AnimationClip>>,
-    mut animation_graphs: Mut<Assets<AnimationGraph>>,
-) {
     let target_id = AnimationTargetId::from_name(&Name::new("root"));
-    let mut clip = AnimationClip::default();
-    add_channel_to_clip(&mut clip, &part.animation, target_id);
-    clip.set_duration(part.animation_duration);
-    let clip = animation_clips.add(clip);
-    let (graph, node_index) = AnimationGraph::from_clip(clip);
-    let graph = animation_graphs.add(graph);
     let mut player = AnimationPlayer::default();
-    player.play(node_index).pause();
-    part_commands.insert((
+    player.play(part_assets.animation_node).pause();
+    let part_entity = entity_commands.id();
+    entity_commands.insert((
         player,
-        target_id,
-        AnimatedBy(part_commands.id()),
-        AnimationGraphHandle(graph),
+        AnimationGraphHandle(part_assets.animation_graph.clone()),
     ));
+    if collision_mesh.is_some() {
+        // A sibling, so its transform is relative to the same parent as the part's
+        let target = parent
+            .spawn((
+                Name::new(format!("{} Animation Target", animated_part.name)),
+                transform,
+                target_id,
+                AnimatedBy(part_entity),
+            ))
+            .id();
+        parent
+            .commands_mut()
+            .entity(part_entity)
+            .insert(KinematicAnimationTarget(target));
+    } else {
+        entity_commands.insert((target_id, AnimatedBy(part_entity)));
+    }
 }
 
 // TODO: Move this to magicka_assets
@@ -252,17 +381,13 @@ pub(crate) fn spawn_xna_model(
     shared_resources: &impl remagic::SharedResources,
     content_path: &PlatformPath,
     commands: Commands,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
 ) -> Entity {
     spawn_xna_model_detailed(
         xna_model,
         shared_resources,
         content_path,
         commands,
-        meshes,
-        materials,
         assets,
         None,
     )
@@ -273,9 +398,7 @@ pub(crate) fn spawn_xna_model_detailed(
     shared_resources: &impl remagic::SharedResources,
     content_path: &PlatformPath,
     mut commands: Commands,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
     skinned_mesh: Option<&bevy::mesh::skinning::SkinnedMesh>,
 ) -> Entity {
     let root_entity = commands
@@ -316,14 +439,6 @@ pub(crate) fn spawn_xna_model_detailed(
             .insert(ChildOf(*parent_bone_entity));
     }
     for xna_mesh in &xna_model.meshes {
-        let Some(vertex_buffer) = &xna_mesh.vertex_buffer else {
-            warn!("no vertex buffer for mesh");
-            continue;
-        };
-        let Some(index_buffer) = &xna_mesh.index_buffer else {
-            warn!("no index buffer for mesh");
-            continue;
-        };
         let mesh_parent_entity = {
             if let Some(parent_bone_index) = xna_mesh.parent_bone {
                 if let Some((_, parent_bone_entity)) = usize::try_from(parent_bone_index)
@@ -352,17 +467,36 @@ pub(crate) fn spawn_xna_model_detailed(
         }
         let mesh_entity = mesh_entity.id();
         for mesh_part in &xna_mesh.parts {
-            let Some(vertex_declaration) = usize::try_from(mesh_part.vertex_declaration_index)
-                .ok()
-                .and_then(|i| xna_model.vertex_declarations.get(i))
-                .and_then(|o| o.as_ref())
-            else {
+            // XNA 3.1 meshes own their buffers, XNA 4.0 mesh parts reference shared ones
+            let Some(vertex_buffer) = xna_mesh.vertex_buffer.as_ref().or_else(|| {
+                let reference = mesh_part.vertex_buffer.as_ref()?;
+                shared_resources.shared_resource(reference).ok().flatten()
+            }) else {
+                warn!("no vertex buffer for mesh part");
+                continue;
+            };
+            let Some(index_buffer) = xna_mesh.index_buffer.as_ref().or_else(|| {
+                let reference = mesh_part.index_buffer.as_ref()?;
+                shared_resources.shared_resource(reference).ok().flatten()
+            }) else {
+                warn!("no index buffer for mesh part");
+                continue;
+            };
+            let Some(vertex_declaration) = vertex_buffer.declaration.as_ref().or_else(|| {
+                usize::try_from(mesh_part.vertex_declaration_index)
+                    .ok()
+                    .and_then(|i| xna_model.vertex_declarations.get(i))
+                    .and_then(|o| o.as_ref())
+            }) else {
                 warn!("no vertex declaration for mesh part");
                 continue;
             };
 
             let stream = 0;
-            let vertex_stride = d3dx::vertex_size(&vertex_declaration.elements, 0);
+            let vertex_stride = match vertex_declaration.vertex_stride {
+                Some(stride) => stride.try_into().unwrap(),
+                None => d3dx::vertex_size(&vertex_declaration.elements, 0),
+            };
 
             let incorrect_vertex_count =
                 vertex_buffer.data.len().checked_div(vertex_stride).unwrap();
@@ -407,8 +541,10 @@ pub(crate) fn spawn_xna_model_detailed(
                 Face::Front
             };
 
+            #[expect(clippy::large_enum_variant)] // Only held until added as an asset
             enum MaterialType {
                 Standard(StandardMaterial),
+                Deferred(super::effect::DeferredMaterial),
                 Character(super::effect::CharacterMaterial),
             }
 
@@ -424,7 +560,7 @@ pub(crate) fn spawn_xna_model_detailed(
                             e.downcast_ref::<remagic::xnb_readers::magicka_effect::DeferredEffect>()
                         {
                             let (mat, col) = super::effect::translate_effect_deferred(e, content_path, back_face, assets);
-                            (mat.map(MaterialType::Standard), col)
+                            (mat.map(MaterialType::Deferred), col)
                         } else if let Some(e) =
                             e.downcast_ref::<remagic::xnb_readers::skinning::SkinnedModelBasicEffect>()
                         {
@@ -477,14 +613,17 @@ pub(crate) fn spawn_xna_model_detailed(
             let mut mesh_part_commands = commands.spawn((
                 ChildOf(mesh_entity),
                 Name::new("MeshPart"),
-                Mesh3d(meshes.add(mesh)),
+                Mesh3d(assets.add(mesh)),
             ));
             if let Some(skinned_mesh) = skinned_mesh {
                 mesh_part_commands.insert(skinned_mesh.clone());
             }
             match maybe_material {
                 Some(MaterialType::Standard(m)) => {
-                    mesh_part_commands.insert(MeshMaterial3d(materials.add(m)));
+                    mesh_part_commands.insert(MeshMaterial3d(assets.add(m)));
+                }
+                Some(MaterialType::Deferred(m)) => {
+                    mesh_part_commands.insert(MeshMaterial3d(assets.add(m)));
                 }
                 Some(MaterialType::Character(m)) => {
                     mesh_part_commands.insert(MeshMaterial3d(assets.add(m)));
@@ -1,66 +1,100 @@
-use super::xna_geom;
+use super::{effect::MaterialHandle, map_vec3, xna_geom};
+use crate::{camera::PrimaryView, magicka_assets::AssetCreator};
 use bevy::{
-    asset::RenderAssetUsages, ecs::relationship::RelatedSpawnerCommands, light::NotShadowCaster,
-    mesh::PrimitiveTopology, prelude::*,
+    asset::RenderAssetUsages,
+    camera::primitives::{Aabb, Frustum},
+    ecs::relationship::RelatedSpawnerCommands,
+    light::NotShadowCaster,
+    math::Vec3A,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
 };
 use remagic::xnb_readers::magicka_mesh::{BiTree, BiTreeModel, BiTreeNode};
 use typed_path::PlatformPath;
 
-pub(crate) fn spawn_bitree_model(
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
-    parent_commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+/// How far outside the view nodes are still drawn, so they can cast shadows into it.
+const CULLING_MARGIN: f32 = 10.;
+
+/// The assets of a [`BiTreeNode`] and its children.
+#[derive(Debug)]
+pub(crate) struct BiTreeNodeAssets {
+    mesh: Handle<Mesh>,
+    material: Option<MaterialHandle>,
+    bounds: Aabb,
+    children: Vec<BiTreeNodeAssets>,
+}
+
+/// A tree of a [`BiTreeModel`], with [`BiTreeBounds`] nodes as descendants.
+#[derive(Component, Debug)]
+pub struct BiTreeRoot;
+
+/// The bounds of a BiTree node and all its children, for culling them together.
+#[derive(Component, Debug)]
+pub struct BiTreeBounds(pub Aabb);
+
+/// Creates the assets of each tree in the model, in the same order as [`BiTreeModel::bitrees`].
+///
+/// With `batch_leaves`, nodes with only leaf children are merged with them into one mesh, for
+/// fewer draws but coarser culling.
+pub(crate) fn load_bitree_model(
     model: &BiTreeModel,
     content_path: &PlatformPath,
-) {
-    let mut i = 0;
-    for bitree in &model.bitrees {
-        let base_mesh = bitree_base_mesh(bitree);
-
-        let mut root_ent_commands = parent_commands.spawn((
-            Name::new("BiTreeRootNode"),
-            Transform::default(),
-            if bitree.visible {
-                Visibility::Inherited
-            } else {
-                Visibility::Hidden
-            },
-        ));
-        root_ent_commands.with_children(|parent| {
-            i = spawn_bitree_node(
-                parent,
+    batch_leaves: bool,
+    assets: &mut impl AssetCreator,
+) -> Vec<BiTreeNodeAssets> {
+    model
+        .bitrees
+        .iter()
+        .map(|bitree| {
+            let base_mesh = bitree_base_mesh(bitree);
+            load_bitree_node(
                 content_path,
                 &bitree.node,
                 bitree,
                 &base_mesh,
-                meshes,
-                materials,
+                batch_leaves,
                 assets,
-                i,
-            );
-        });
-    }
+            )
+        })
+        .collect()
 }
 
-fn spawn_bitree_node(
-    parent_commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+fn load_bitree_node(
     content_path: &PlatformPath,
     tree_node: &BiTreeNode,
     tree_root: &BiTree,
     tree_root_base_mesh: &Mesh,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
-    mut i: usize,
-) -> usize {
+    batch_leaves: bool,
+    assets: &mut impl AssetCreator,
+) -> BiTreeNodeAssets {
     let mut mesh = tree_root_base_mesh.clone();
 
-    let start_index: usize = tree_node.start_index.try_into().unwrap();
-    let primitive_count: usize = tree_node.primitive_count.try_into().unwrap();
+    let child_nodes: Vec<_> = [&tree_node.child_a, &tree_node.child_b]
+        .into_iter()
+        .flatten()
+        .collect();
+    let batch = batch_leaves
+        && !child_nodes.is_empty()
+        && child_nodes
+            .iter()
+            .all(|child| child.child_a.is_none() && child.child_b.is_none());
+    let batched_nodes = if batch { child_nodes.as_slice() } else { &[] };
+
     let index_buffer = tree_root.index_buffer.as_ref().unwrap();
-    let invert_winding =
-        xna_geom::assign_mesh_indices(&mut mesh, 0, start_index, primitive_count, index_buffer);
+    let mut indices = Vec::new();
+    let mut invert_winding = false;
+    for node in std::iter::once(tree_node).chain(batched_nodes.iter().map(|child| &***child)) {
+        let start_index: usize = node.start_index.try_into().unwrap();
+        let primitive_count: usize = node.primitive_count.try_into().unwrap();
+        invert_winding =
+            xna_geom::assign_mesh_indices(&mut mesh, 0, start_index, primitive_count, index_buffer);
+        if batch && let Some(node_indices) = mesh.indices() {
+            indices.extend(node_indices.iter().map(|i| i as u32));
+        }
+    }
+    if batch {
+        mesh.insert_indices(Indices::U32(indices));
+    }
 
     let (maybe_material, vertex_color_state) = super::effect::translate_effect(
         tree_root.effect.as_ref(),
@@ -76,46 +110,132 @@ fn spawn_bitree_node(
         mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
     }
 
-    let mesh_handle = meshes.add(mesh);
+    let mesh = assets.add(mesh);
+    let material = maybe_material.map(|material| material.add(assets));
 
-    let mut node_commands =
-        parent_commands.spawn((Name::new("BiTreeNode"), Mesh3d::from(mesh_handle)));
-    if let Some(material) = maybe_material {
-        node_commands.insert(MeshMaterial3d(materials.add(material)));
+    let children = if batch {
+        Vec::new()
+    } else {
+        child_nodes
+            .into_iter()
+            .map(|child| {
+                load_bitree_node(
+                    content_path,
+                    child,
+                    tree_root,
+                    tree_root_base_mesh,
+                    batch_leaves,
+                    assets,
+                )
+            })
+            .collect()
+    };
+
+    let (min, max) = tree_node.bounding_box;
+    BiTreeNodeAssets {
+        mesh,
+        material,
+        bounds: Aabb::from_min_max(map_vec3(min), map_vec3(max)),
+        children,
+    }
+}
+
+pub(crate) fn spawn_bitree_model(
+    parent_commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    model: &BiTreeModel,
+    model_assets: &[BiTreeNodeAssets],
+) {
+    for (bitree, root_assets) in model.bitrees.iter().zip(model_assets) {
+        let mut root_ent_commands = parent_commands.spawn((
+            Name::new("BiTreeRootNode"),
+            BiTreeRoot,
+            Transform::default(),
+            if bitree.visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+        ));
+        root_ent_commands.with_children(|parent| {
+            spawn_bitree_node(parent, bitree, root_assets);
+        });
+    }
+}
+
+fn spawn_bitree_node(
+    parent_commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+    tree_root: &BiTree,
+    node_assets: &BiTreeNodeAssets,
+) {
+    let mut node_commands = parent_commands.spawn((
+        Name::new("BiTreeNode"),
+        Mesh3d(node_assets.mesh.clone()),
+        BiTreeBounds(node_assets.bounds),
+    ));
+    match &node_assets.material {
+        Some(MaterialHandle::Standard(material)) => {
+            node_commands.insert(MeshMaterial3d(material.clone()));
+        }
+        Some(MaterialHandle::Deferred(material)) => {
+            node_commands.insert(MeshMaterial3d(material.clone()));
+        }
+        None => {}
     }
     if !tree_root.cast_shadows {
         node_commands.insert(NotShadowCaster);
     }
-    i += 1;
     node_commands.with_children(|parent_commands| {
-        if let Some(child) = &tree_node.child_a {
-            i = spawn_bitree_node(
-                parent_commands,
-                content_path,
-                child,
-                tree_root,
-                tree_root_base_mesh,
-                meshes,
-                materials,
-                assets,
-                i,
-            );
-        }
-        if let Some(child) = &tree_node.child_b {
-            i = spawn_bitree_node(
-                parent_commands,
-                content_path,
-                child,
-                tree_root,
-                tree_root_base_mesh,
-                meshes,
-                materials,
-                assets,
-                i,
-            );
+        for child_assets in &node_assets.children {
+            spawn_bitree_node(parent_commands, tree_root, child_assets);
         }
     });
-    i
+}
+
+/// Hides BiTree nodes outside the view of every active primary camera, without looking at the
+/// children of hidden nodes. Each node's mesh shares the vertices of its whole tree, so Bevy's own
+/// culling can't tell them apart.
+pub(crate) fn cull_bitree_nodes(
+    cameras: Query<(&Camera, &Frustum), With<PrimaryView>>,
+    roots: Query<&Children, With<BiTreeRoot>>,
+    mut nodes: Query<(
+        &BiTreeBounds,
+        &GlobalTransform,
+        &mut Visibility,
+        Option<&Children>,
+    )>,
+) {
+    let frusta: Vec<_> = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .map(|(_, frustum)| frustum)
+        .collect();
+    if frusta.is_empty() {
+        return;
+    }
+
+    let mut stack: Vec<Entity> = roots.iter().flat_map(|children| children.iter()).collect();
+    while let Some(node) = stack.pop() {
+        let Ok((BiTreeBounds(bounds), transform, mut visibility, children)) = nodes.get_mut(node)
+        else {
+            continue;
+        };
+        let bounds = Aabb {
+            center: bounds.center,
+            half_extents: bounds.half_extents + Vec3A::splat(CULLING_MARGIN),
+        };
+        let world_from_local = transform.affine();
+        let in_view = frusta
+            .iter()
+            .any(|frustum| frustum.intersects_obb(&bounds, &world_from_local, true, false));
+        visibility.set_if_neq(if in_view {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if in_view && let Some(children) = children {
+            stack.extend(children.iter());
+        }
+    }
 }
 
 fn bitree_base_mesh(bitree: &BiTree) -> Mesh {
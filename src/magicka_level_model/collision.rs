@@ -1,7 +1,16 @@
-use avian3d::prelude::{Collider, PhysicsLayer};
+use avian3d::{
+    parry::query::PointQueryWithLocation,
+    prelude::{
+        ActiveCollisionHooks, Collider, CollisionHooks, ContactPair, PhysicsLayer, Position,
+        Rotation,
+    },
+};
+use bevy::{
+    asset::AssetPath, ecs::system::SystemParam, platform::collections::HashMap, prelude::*,
+};
 use remagic::xnb_readers::magicka_content::GenericTriangleMesh;
 
-use crate::magicka_level_model::map_vec3;
+use crate::magicka_level_model::{LevelInstance, map_vec3};
 
 #[derive(PhysicsLayer, Clone, Copy, Debug, Default)]
 pub enum Layers {
@@ -10,6 +19,199 @@ pub enum Layers {
     Level,   // Layer 1 - static level collision
     Trigger, // Layer 2 - area triggers
     Shield,
+    Liquid, // Liquid surfaces, only hit by spatial queries
+}
+
+/// A triangle mesh collider loaded as part of a level.
+#[derive(Asset, TypePath, Debug)]
+pub struct CollisionMesh {
+    pub collider: Collider,
+    pub material: CollisionMaterial,
+}
+
+/// What a surface is made of, which affects how characters move over it.
+///
+/// Level collision has a slot per material, in the order of Magicka's `CollisionMaterials` enum
+/// from `Generic` to `Lava`.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Default, Debug, Reflect)]
+pub enum CollisionMaterial {
+    #[default]
+    Generic,
+    Gravel,
+    Grass,
+    Wood,
+    Snow,
+    Stone,
+    Mud,
+    /// Mirror-like surfaces, which spells bounce off in Magicka.
+    Reflect,
+    Water,
+    Lava,
+    /// Frozen liquids, which aren't a slot of level collision.
+    Ice,
+}
+
+impl CollisionMaterial {
+    pub fn from_index(index: usize) -> Self {
+        use CollisionMaterial::*;
+        match index {
+            0 => Generic,
+            1 => Gravel,
+            2 => Grass,
+            3 => Wood,
+            4 => Snow,
+            5 => Stone,
+            6 => Mud,
+            7 => Self::Reflect,
+            8 => Water,
+            9 => Lava,
+            _ => {
+                warn!("Unknown collision material {index}");
+                Generic
+            }
+        }
+    }
+
+    /// How much of their usual speed characters can reach.
+    pub fn speed_multiplier(self) -> f32 {
+        match self {
+            Self::Mud => 0.7,
+            Self::Snow | Self::Water => 0.85,
+            _ => 1.,
+        }
+    }
+
+    /// How quickly characters can change their velocity, compared to normal ground.
+    pub fn traction(self) -> f32 {
+        match self {
+            Self::Ice => 0.05,
+            Self::Snow => 0.5,
+            Self::Mud => 0.7,
+            _ => 1.,
+        }
+    }
+}
+
+/// Whether level collision blocks from both sides of its triangles, or only from the front.
+///
+/// Magicka's collision might be meant to be one-sided, since characters get stuck in places like
+/// the wc_s4 spawn when it's double-sided. Until that's confirmed, one-sided collision is opt-in
+/// per level through [`LevelCollisionModes`].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Reflect)]
+pub enum CollisionMode {
+    #[default]
+    DoubleSided,
+    OneSided,
+}
+
+/// Which [`CollisionMode`] levels are spawned with, for comparing them on a level.
+#[derive(Resource, Default, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct LevelCollisionModes {
+    pub default: CollisionMode,
+    /// Overrides by level name, the lowercase file stem of the level model.
+    pub levels: HashMap<String, CollisionMode>,
+}
+
+impl LevelCollisionModes {
+    pub fn get(&self, level: &str) -> CollisionMode {
+        self.levels.get(level).copied().unwrap_or(self.default)
+    }
+
+    pub fn for_path(&self, path: Option<&AssetPath>) -> CollisionMode {
+        path.map_or(self.default, |path| self.get(&level_name(path)))
+    }
+}
+
+/// The name of a level, as used by [`LevelCollisionModes`].
+pub fn level_name(path: &AssetPath) -> String {
+    path.path()
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase()
+}
+
+/// A collider made from a [`CollisionMesh`], which collides according to the level's
+/// [`CollisionMode`].
+#[derive(Component, Default, Debug)]
+pub struct LevelCollider;
+
+/// A triangle mesh collider that only collides with things in front of its triangles.
+#[derive(Component, Default, Debug)]
+#[require(ActiveCollisionHooks::MODIFY_CONTACTS)]
+pub struct OneSidedCollision;
+
+/// Drops contacts with the back of [`OneSidedCollision`] triangles, so what's behind them can
+/// pass through, and whatever spawns or is pushed inside the geometry can get out again.
+#[derive(SystemParam)]
+pub struct LevelCollisionHooks<'w, 's> {
+    one_sided: Query<
+        'w,
+        's,
+        (&'static Collider, &'static Position, &'static Rotation),
+        With<OneSidedCollision>,
+    >,
+}
+
+impl CollisionHooks for LevelCollisionHooks<'_, '_> {
+    fn modify_contacts(&self, contacts: &mut ContactPair, _commands: &mut Commands) -> bool {
+        // The manifold normals point from the first collider to the second
+        let (level, away_from_level) = if let Ok(level) = self.one_sided.get(contacts.collider1) {
+            (level, 1.)
+        } else if let Ok(level) = self.one_sided.get(contacts.collider2) {
+            (level, -1.)
+        } else {
+            return true;
+        };
+        let (collider, position, rotation) = level;
+        let Some(trimesh) = collider.shape_scaled().as_trimesh() else {
+            return true;
+        };
+        let to_local = rotation.inverse();
+
+        contacts.manifolds.retain_mut(|manifold| {
+            let away = to_local * manifold.normal * away_from_level;
+            manifold.points.retain(|point| {
+                let local_point = to_local * (point.point - position.0);
+                let (_, (triangle, _)) =
+                    trimesh.project_local_point_and_get_location(local_point, false);
+                // XNA's front faces are clockwise, the opposite of parry's
+                let front = trimesh.triangle(triangle).scaled_normal();
+                front.dot(away) < 0.
+            });
+            !manifold.points.is_empty()
+        });
+        !contacts.manifolds.is_empty()
+    }
+}
+
+pub(super) fn apply_collision_modes(
+    modes: Res<LevelCollisionModes>,
+    levels: Query<(Ref<LevelInstance>, Entity)>,
+    children: Query<&Children>,
+    colliders: Query<Has<OneSidedCollision>, With<LevelCollider>>,
+    mut commands: Commands,
+) {
+    for (instance, level) in levels {
+        if !modes.is_changed() && !instance.is_changed() {
+            continue;
+        }
+        let one_sided = modes.for_path(instance.level.path()) == CollisionMode::OneSided;
+        for entity in children.iter_descendants(level) {
+            match colliders.get(entity) {
+                Ok(false) if one_sided => {
+                    commands.entity(entity).insert(OneSidedCollision);
+                }
+                Ok(true) if !one_sided => {
+                    commands
+                        .entity(entity)
+                        .remove::<(OneSidedCollision, ActiveCollisionHooks)>();
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 pub fn to_collider(mesh: &GenericTriangleMesh) -> Option<Collider> {
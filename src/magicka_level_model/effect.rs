@@ -1,11 +1,17 @@
-use bevy::{pbr::ExtendedMaterial, prelude::*, render::render_resource::Face};
-use remagic::xnb_readers::{
-    magicka_effect::{AdditiveEffect, DeferredEffect, Effect},
-    skinning::SkinnedModelBasicEffect,
+use bevy::{
+    asset::AssetPath, pbr::ExtendedMaterial, prelude::*, render::render_resource::Face,
+};
+use remagic::{
+    xnb::types::ExternalReference,
+    xnb_readers::{
+        magicka_effect::{AdditiveEffect, DeferredEffect, DeferredEffectLayer, Effect},
+        skinning::SkinnedModelBasicEffect,
+        xna_mesh::Texture2d,
+    },
 };
 use typed_path::PlatformPath;
 
-use crate::magicka_level_model::map_vec3;
+use crate::{magicka_assets::AssetCreator, magicka_level_model::map_vec3};
 
 #[derive(Debug, Clone, Copy)]
 pub enum VertexColorState {
@@ -13,12 +19,34 @@ pub enum VertexColorState {
     Disabled,
 }
 
+/// A material translated from an [`Effect`], whose type depends on the kind of effect.
+#[expect(clippy::large_enum_variant)] // Only held until added as an asset
+pub(crate) enum TranslatedMaterial {
+    Standard(StandardMaterial),
+    Deferred(DeferredMaterial),
+}
+
+impl TranslatedMaterial {
+    pub(crate) fn add(self, assets: &mut impl AssetCreator) -> MaterialHandle {
+        match self {
+            Self::Standard(material) => MaterialHandle::Standard(assets.add(material)),
+            Self::Deferred(material) => MaterialHandle::Deferred(assets.add(material)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum MaterialHandle {
+    Standard(Handle<StandardMaterial>),
+    Deferred(Handle<DeferredMaterial>),
+}
+
 pub(crate) fn translate_effect(
     effect: Option<&Effect>,
     content_path: &PlatformPath,
     invert_winding: bool,
-    assets: &AssetServer,
-) -> (Option<StandardMaterial>, VertexColorState) {
+    assets: &mut impl AssetCreator,
+) -> (Option<TranslatedMaterial>, VertexColorState) {
     let Some(effect) = effect else {
         warn!("null effect");
         return (None, VertexColorState::Disabled);
@@ -33,9 +61,15 @@ pub(crate) fn translate_effect(
 
     match effect {
         Effect::Deferred(effect) => {
-            translate_effect_deferred(effect, content_path, back_face, assets)
+            let (material, vertex_color_state) =
+                translate_effect_deferred(effect, content_path, back_face, assets);
+            (material.map(TranslatedMaterial::Deferred), vertex_color_state)
+        }
+        Effect::Additive(effect) => {
+            let (material, vertex_color_state) =
+                translate_effect_additive(effect, content_path, assets);
+            (material.map(TranslatedMaterial::Standard), vertex_color_state)
         }
-        Effect::Additive(effect) => translate_effect_additive(effect, content_path, assets),
         _ => {
             warn!("unhandled effect {:#?}", effect);
             (None, VertexColorState::Disabled)
@@ -43,127 +77,174 @@ pub(crate) fn translate_effect(
     }
 }
 
-pub(crate) fn translate_effect_deferred(
-    effect: &DeferredEffect,
+/// Loads a texture of an effect, if it has one.
+fn load_effect_image(
+    texture: &ExternalReference<Texture2d>,
     content_path: &PlatformPath,
-    back_face: Face,
-    assets: &AssetServer,
-) -> (Option<StandardMaterial>, VertexColorState) {
-    let base_color_texture = if !effect.Layer0.DiffuseTexture0.path.is_empty() {
-        Some(assets.load_builder().override_unapproved().load(find_image(
-            effect.Layer0.DiffuseTexture0.path.as_str(),
-            content_path,
-        )))
+    linear: bool,
+    assets: &mut impl AssetCreator,
+) -> Option<Handle<Image>> {
+    if texture.path.is_empty() {
+        return None;
+    }
+    let path = find_image(texture.path.as_str(), content_path);
+    Some(if linear {
+        assets.load_linear_image(path)
     } else {
-        None
-    };
-    let normal_map_texture = if !effect.Layer0.NormalTexture0.path.is_empty() {
-        Some(
-            assets
-                .load_builder()
-                .with_settings(
-                    |s: &mut crate::magicka_assets::image::MagickaTexture2dLoaderSettings| {
-                        s.is_srgb = false;
-                    },
-                )
-                .override_unapproved()
-                .load(find_image(
-                    effect.Layer0.NormalTexture0.path.as_str(),
-                    content_path,
-                )),
-        )
+        assets.load(path)
+    })
+}
+
+/// Approximates Blinn-Phong specular amount and power as PBR reflectance and roughness.
+/// Must match `specular_to_pbr` in deferred_material.wgsl.
+pub(super) fn specular_to_pbr(amount: f32, power: f32) -> (f32, f32) {
+    let reflectance = (amount * 0.5).clamp(0., 1.);
+    let perceptual_roughness = (2. / (power.max(0.) + 2.)).sqrt().clamp(0.089, 1.);
+    (reflectance, perceptual_roughness)
+}
+
+/// The color of a layer without its texture, which is white when the texture provides it.
+fn layer_color(layer: &DeferredEffectLayer, has_texture: bool) -> LinearRgba {
+    if has_texture {
+        LinearRgba::WHITE
     } else {
-        None
-    };
-    if effect.Alpha < 1. {
-        warn!("unsupported: effect alpha {} < 1", effect.Alpha);
+        LinearRgba::rgb(
+            layer.DiffuseColor0.0,
+            layer.DiffuseColor0.1,
+            layer.DiffuseColor0.2,
+        )
     }
-    let material = StandardMaterial {
-        base_color: Color::WHITE.with_alpha(effect.Alpha),
-        cull_mode: if !effect.Layer0.AlphaMask0Enabled {
+}
+
+pub(crate) fn translate_effect_deferred(
+    effect: &DeferredEffect,
+    content_path: &PlatformPath,
+    back_face: Face,
+    assets: &mut impl AssetCreator,
+) -> (Option<DeferredMaterial>, VertexColorState) {
+    let layer0 = &effect.Layer0;
+    let base_color_texture = load_effect_image(&layer0.DiffuseTexture0, content_path, false, assets);
+    let normal_map_texture = load_effect_image(&layer0.NormalTexture0, content_path, true, assets);
+    let (reflectance, perceptual_roughness) =
+        specular_to_pbr(layer0.SpecAmount0, layer0.SpecPower0);
+    let layer0_color = layer_color(layer0, base_color_texture.is_some()).with_alpha(effect.Alpha);
+    let base = StandardMaterial {
+        base_color: layer0_color.into(),
+        cull_mode: if !layer0.AlphaMask0Enabled {
             None
         } else {
             Some(back_face)
         },
-        base_color_texture,
+        base_color_texture: base_color_texture.clone(),
         emissive: LinearRgba::new(
-            effect.Layer0.EmissiveAmount0,
-            effect.Layer0.EmissiveAmount0,
-            effect.Layer0.EmissiveAmount0,
+            layer0.EmissiveAmount0,
+            layer0.EmissiveAmount0,
+            layer0.EmissiveAmount0,
             1.,
         ),
-        // emissive_exposure_weight: todo!(),
-        // emissive_channel: todo!(),
-        // emissive_texture: todo!(),
-        // perceptual_roughness: todo!(),
-        // metallic: todo!(),
-        // metallic_roughness_channel: todo!(),
-        // metallic_roughness_texture: todo!(),
-        reflectance: effect.Layer0.Reflectiveness0,
-        // reflectance: todo!(),
-        // specular_tint: todo!(),
-        // diffuse_transmission: todo!(),
-        // specular_transmission: todo!(),
-        // thickness: todo!(),
-        // ior: todo!(),
-        // attenuation_distance: todo!(),
-        // attenuation_color: todo!(),
-        // normal_map_channel: todo!(),
-        normal_map_texture,
+        perceptual_roughness,
+        reflectance,
+        normal_map_texture: normal_map_texture.clone(),
         flip_normal_map_y: true,
-        // occlusion_channel: todo!(),
-        // occlusion_texture: todo!(),
-        // clearcoat: todo!(),
-        // clearcoat_perceptual_roughness: todo!(),
-        // anisotropy_strength: todo!(),
-        // anisotropy_rotation: todo!(),
-        // double_sided: todo!(),
-        double_sided: !effect.Layer0.AlphaMask0Enabled,
-        // unlit: todo!(),
-        // fog_enabled: todo!(),
-        alpha_mode: if !effect.Layer0.AlphaMask0Enabled
+        double_sided: !layer0.AlphaMask0Enabled,
+        alpha_mode: if effect.Alpha < 1. {
+            AlphaMode::Blend
+        } else if !layer0.AlphaMask0Enabled
         /* || !effect.Layer1.as_ref().is_some_and(|l| l.AlphaMask0Enabled)*/
         {
             AlphaMode::Mask(0.001)
         } else {
             AlphaMode::Opaque
         },
-        // depth_bias: todo!(),
-        // depth_map: todo!(),
-        // parallax_depth_scale: todo!(),
-        // parallax_mapping_method: todo!(),
-        // max_parallax_layer_count: todo!(),
-        // lightmap_exposure: todo!(),
-        // opaque_render_method: todo!(),
-        // deferred_lighting_pass_id: todo!(),
-        // uv_transform: todo!(),
         ..Default::default()
     };
-    (
-        Some(material),
-        if false
-        /* effect.VertexColorEnabled */
-        {
-            // This introduces artifacts, it is unclear when it should be used
-            VertexColorState::Enabled
-        } else {
-            VertexColorState::Disabled
+
+    let layer0_material = load_effect_image(&layer0.MaterialTexture0, content_path, true, assets);
+    let layer1 = effect.Layer1.as_ref();
+    let layer1_diffuse = layer1
+        .and_then(|layer1| load_effect_image(&layer1.DiffuseTexture0, content_path, false, assets));
+    let layer1_normal = layer1
+        .and_then(|layer1| load_effect_image(&layer1.NormalTexture0, content_path, true, assets));
+    let layer1_material = layer1
+        .and_then(|layer1| load_effect_image(&layer1.MaterialTexture0, content_path, true, assets));
+    let reflection_map = (!effect.ReflectionMap.path.is_empty())
+        .then(|| assets.load(find_image(&effect.ReflectionMap.path, content_path)));
+
+    let mut flags = 0;
+    for (enabled, flag) in [
+        (layer1.is_some(), DeferredExtension::LAYER1_ENABLED),
+        (
+            layer1.is_some_and(|layer1| layer1.AlphaMask0Enabled),
+            DeferredExtension::LAYER1_ALPHA_MASK,
+        ),
+        (effect.VertexColorEnabled, DeferredExtension::VERTEX_COLOR_BLEND),
+        (base_color_texture.is_some(), DeferredExtension::LAYER0_DIFFUSE_TEXTURE),
+        (layer1_diffuse.is_some(), DeferredExtension::LAYER1_DIFFUSE_TEXTURE),
+        (normal_map_texture.is_some(), DeferredExtension::LAYER0_NORMAL_TEXTURE),
+        (layer1_normal.is_some(), DeferredExtension::LAYER1_NORMAL_TEXTURE),
+        (layer0_material.is_some(), DeferredExtension::LAYER0_MATERIAL_TEXTURE),
+        (layer1_material.is_some(), DeferredExtension::LAYER1_MATERIAL_TEXTURE),
+        (reflection_map.is_some(), DeferredExtension::REFLECTION_MAP),
+        (
+            effect.UseMaterialTextureForReflectiveness,
+            DeferredExtension::MATERIAL_TEXTURE_REFLECTIVENESS,
+        ),
+        (
+            layer0.DiffuseTexture0AlphaDisabled,
+            DeferredExtension::LAYER0_DIFFUSE_ALPHA_DISABLED,
+        ),
+    ] {
+        if enabled {
+            flags |= flag;
+        }
+    }
+
+    let extension = DeferredExtension {
+        layers: DeferredLayersUniform {
+            layer0_color,
+            layer1_color: layer1
+                .map(|layer1| layer_color(layer1, layer1_diffuse.is_some()))
+                .unwrap_or_default(),
+            layer0_emissive: layer0.EmissiveAmount0,
+            layer1_emissive: layer1.map_or(0., |layer1| layer1.EmissiveAmount0),
+            layer0_spec_amount: layer0.SpecAmount0,
+            layer1_spec_amount: layer1.map_or(0., |layer1| layer1.SpecAmount0),
+            layer0_spec_power: layer0.SpecPower0,
+            layer1_spec_power: layer1.map_or(0., |layer1| layer1.SpecPower0),
+            layer0_reflectiveness: layer0.Reflectiveness0,
+            layer1_reflectiveness: layer1.map_or(0., |layer1| layer1.Reflectiveness0),
+            layer0_normal_power: layer0.NormalPower0,
+            layer1_normal_power: layer1.map_or(0., |layer1| layer1.NormalPower0),
+            sharpness: effect.Sharpness,
+            flags,
         },
-    )
+        layer0_diffuse: base_color_texture,
+        layer1_diffuse,
+        layer0_normal: normal_map_texture,
+        layer1_normal,
+        layer0_material,
+        layer1_material,
+        reflection_map,
+    };
+
+    // Vertex colors blend between the layers, the single layer effects look wrong with them
+    let vertex_color_state = if effect.Layer1.is_some() && effect.VertexColorEnabled {
+        VertexColorState::Enabled
+    } else {
+        VertexColorState::Disabled
+    };
+    (Some(DeferredMaterial { base, extension }), vertex_color_state)
 }
 
 pub(crate) fn translate_effect_additive(
     effect: &AdditiveEffect,
     content_path: &PlatformPath,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
 ) -> (Option<StandardMaterial>, VertexColorState) {
     let material = StandardMaterial {
         base_color: Srgba::from_vec3(map_vec3(effect.ColorTint)).into(),
         base_color_texture: effect.TextureEnabled.then(|| {
-            assets
-                .load_builder()
-                .override_unapproved()
-                .load(find_image(&effect.Texture.path, content_path))
+            assets.load(find_image(&effect.Texture.path, content_path))
         }),
         alpha_mode: AlphaMode::Add,
         ..default()
@@ -178,11 +259,15 @@ pub(crate) fn translate_effect_additive(
 
 pub type CharacterMaterial = ExtendedMaterial<StandardMaterial, CharacterExtension>;
 
+/// The material of level geometry, which blends a second layer over the base
+/// [`StandardMaterial`] when the effect has one.
+pub type DeferredMaterial = ExtendedMaterial<StandardMaterial, DeferredExtension>;
+
 pub(crate) fn translate_effect_skinned_model_basic(
     effect: &SkinnedModelBasicEffect,
     content_path: &PlatformPath,
     back_face: Face,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
 ) -> (Option<CharacterMaterial>, VertexColorState) {
     use remagic::xnb_readers::skinning::Technique;
     match effect.technique {
@@ -212,23 +297,13 @@ pub(crate) fn translate_effect_skinned_model_basic(
             let base = StandardMaterial {
                 base_color,
                 base_color_texture: if diffuse_map_0_enabled && let Some(tex_ref) = diffuse_map_0 {
-                    Some(
-                        assets
-                            .load_builder()
-                            .override_unapproved()
-                            .load(find_image(tex_ref.path.as_str(), content_path)),
-                    )
+                    Some(assets.load(find_image(tex_ref.path.as_str(), content_path)))
                 } else {
                     None
                 },
                 emissive,
                 normal_map_texture: if normal_map_enabled && let Some(tex_ref) = normal_map {
-                    Some(
-                        assets.load_builder()
-                            .with_settings(|s: &mut crate::magicka_assets::image::MagickaTexture2dLoaderSettings| { s.is_srgb = false; })
-                            .override_unapproved()
-                            .load(find_image(tex_ref.path.as_str(), content_path))
-                    )
+                    Some(assets.load_linear_image(find_image(tex_ref.path.as_str(), content_path)))
                 } else {
                     None
                 },
@@ -261,7 +336,7 @@ pub(crate) fn translate_effect_skinned_model_basic(
     }
 }
 
-fn blend_base_emissive(
+pub(super) fn blend_base_emissive(
     diffuse_color: remagic::xnb::types::Vector3,
     emissiveness: f32,
 ) -> (Color, LinearRgba) {
@@ -280,33 +355,15 @@ fn blend_base_emissive(
     (base_color, emissive)
 }
 
-pub(crate) fn find_image(relative_path: &str, from_path: &PlatformPath) -> String {
-    use std::ffi::OsStr;
-    use typed_path::Utf8WindowsPath;
-
-    let relative_path = Utf8WindowsPath::new(relative_path);
-    let image_content_path = from_path
-        .parent()
-        .unwrap()
-        .join(relative_path.with_platform_encoding().as_bytes_path());
-    let mut absolute_path = crate::magicka_assets::content_root()
-        .join_checked(&image_content_path)
-        .unwrap();
-    // TODO panic free
-    assert_eq!(None, absolute_path.extension());
-    assert!(absolute_path.set_extension("xnb"));
-    if !matches!(std::fs::exists(absolute_path.as_ref() as &OsStr), Ok(true)) {
-        let found_path = crate::magicka_assets::find_path_ignore_ascii_case(std::path::Path::new(
-            absolute_path.as_ref() as &OsStr,
-        ))
-        .unwrap();
-        absolute_path = PlatformPath::new(found_path.as_os_str().as_encoded_bytes()).to_owned();
-    }
-    let os_str = std::convert::AsRef::<OsStr>::as_ref(&absolute_path);
-    os_str.to_str().unwrap().to_owned()
+pub(crate) fn find_image(relative_path: &str, from_path: &PlatformPath) -> AssetPath<'static> {
+    crate::magicka_assets::resolve_relative_path(from_path, relative_path).asset_path
 }
 
-use bevy::{pbr::MaterialExtension, render::render_resource::AsBindGroup, shader::ShaderRef};
+use bevy::{
+    pbr::MaterialExtension,
+    render::render_resource::{AsBindGroup, ShaderType},
+    shader::ShaderRef,
+};
 
 #[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
 pub struct CharacterExtension {
@@ -335,3 +392,79 @@ impl MaterialExtension for CharacterExtension {
         SHADER_ASSET_PATH.into()
     }
 }
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct DeferredExtension {
+    #[uniform(100)]
+    layers: DeferredLayersUniform,
+    // The base material's textures are sampled again to blend them without vertex colors
+    #[texture(101)]
+    #[sampler(102)]
+    layer0_diffuse: Option<Handle<Image>>,
+    #[texture(103)]
+    #[sampler(104)]
+    layer1_diffuse: Option<Handle<Image>>,
+    #[texture(105)]
+    #[sampler(106)]
+    layer0_normal: Option<Handle<Image>>,
+    #[texture(107)]
+    #[sampler(108)]
+    layer1_normal: Option<Handle<Image>>,
+    /// Specular amount in red, specular power in green and reflectiveness in blue.
+    #[texture(109)]
+    #[sampler(110)]
+    layer0_material: Option<Handle<Image>>,
+    #[texture(111)]
+    #[sampler(112)]
+    layer1_material: Option<Handle<Image>>,
+    #[texture(113, dimension = "cube")]
+    #[sampler(114)]
+    reflection_map: Option<Handle<Image>>,
+}
+
+impl DeferredExtension {
+    // Flags must match deferred_material.wgsl
+    const LAYER1_ENABLED: u32 = 1 << 0;
+    const LAYER1_ALPHA_MASK: u32 = 1 << 1;
+    const VERTEX_COLOR_BLEND: u32 = 1 << 2;
+    const LAYER0_DIFFUSE_TEXTURE: u32 = 1 << 3;
+    const LAYER1_DIFFUSE_TEXTURE: u32 = 1 << 4;
+    const LAYER0_NORMAL_TEXTURE: u32 = 1 << 5;
+    const LAYER1_NORMAL_TEXTURE: u32 = 1 << 6;
+    const LAYER0_MATERIAL_TEXTURE: u32 = 1 << 7;
+    const LAYER1_MATERIAL_TEXTURE: u32 = 1 << 8;
+    const REFLECTION_MAP: u32 = 1 << 9;
+    const MATERIAL_TEXTURE_REFLECTIVENESS: u32 = 1 << 10;
+    const LAYER0_DIFFUSE_ALPHA_DISABLED: u32 = 1 << 11;
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone, Default)]
+struct DeferredLayersUniform {
+    layer0_color: LinearRgba,
+    layer1_color: LinearRgba,
+    layer0_emissive: f32,
+    layer1_emissive: f32,
+    layer0_spec_amount: f32,
+    layer1_spec_amount: f32,
+    layer0_spec_power: f32,
+    layer1_spec_power: f32,
+    layer0_reflectiveness: f32,
+    layer1_reflectiveness: f32,
+    layer0_normal_power: f32,
+    layer1_normal_power: f32,
+    /// How blurry reflections are, from 0 for fully blurred to 1 for mirror-like.
+    sharpness: f32,
+    flags: u32,
+}
+
+const DEFERRED_SHADER_ASSET_PATH: &str = "deferred_material.wgsl";
+
+impl MaterialExtension for DeferredExtension {
+    fn fragment_shader() -> ShaderRef {
+        DEFERRED_SHADER_ASSET_PATH.into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        DEFERRED_SHADER_ASSET_PATH.into()
+    }
+}
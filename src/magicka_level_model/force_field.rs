@@ -0,0 +1,248 @@
+use avian3d::prelude::{
+    Collider, CollisionEventsEnabled, CollisionLayers, CollisionStart, LayerMask, RigidBody,
+};
+use bevy::{
+    light::NotShadowCaster,
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+    shader::ShaderRef,
+};
+use typed_path::PlatformPath;
+
+use super::{Spawner, collision::Layers, effect::find_image, xna_geom};
+use crate::magicka_assets::AssetCreator;
+
+/// How many impacts can ripple across a force field at once. Must match the shader.
+const MAX_RIPPLES: usize = 8;
+
+const SHADER_ASSET_PATH: &str = "force_field_material.wgsl";
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(MaterialPlugin::<ForceFieldMaterial>::default());
+    app.register_type::<ForceField>();
+    app.add_observer(ripple_on_impact);
+    app.add_systems(PostUpdate, update_ripples);
+}
+
+/// A barrier that blocks characters and spells, rippling where they hit it.
+#[derive(Component, Reflect, Debug)]
+pub struct ForceField {
+    /// How long a ripple lasts, in seconds.
+    pub ttl: f32,
+    /// Impact points in world space, with the time since impact.
+    #[reflect(ignore)]
+    ripples: Vec<(Vec3, f32)>,
+}
+
+/// The assets of a [`ForceField`](remagic::xnb_readers::magicka_content::ForceField), if it has
+/// valid mesh data.
+#[derive(Debug)]
+pub(crate) struct ForceFieldAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ForceFieldMaterial>,
+    collider: Option<Collider>,
+}
+
+pub(crate) fn load_force_field(
+    force_field: &remagic::xnb_readers::magicka_content::ForceField,
+    content_path: &PlatformPath,
+    assets: &mut impl AssetCreator,
+) -> Option<ForceFieldAssets> {
+    let (
+        Some(vertices),
+        Some(indices),
+        Some(vertex_declaration),
+        Ok(vertex_stride),
+        Ok(num_vertices),
+        Ok(primitive_count),
+    ) = (
+        force_field.vertices.as_ref(),
+        force_field.indices.as_ref(),
+        force_field.vertex_declaration.as_ref(),
+        usize::try_from(force_field.vertex_stride),
+        usize::try_from(force_field.num_vertices),
+        usize::try_from(force_field.primitive_count),
+    )
+    else {
+        error!("force field has missing or invalid mesh data");
+        return None;
+    };
+
+    let stream = 0;
+    let mut mesh = xna_geom::init_mesh_from_xna_vertices(
+        vertex_declaration,
+        vertices,
+        vertex_stride,
+        num_vertices,
+        stream,
+    );
+    xna_geom::assign_mesh_indices(&mut mesh, 0, 0, primitive_count, indices);
+    // The rim is computed from normals, which force field meshes don't always have
+    if !mesh.contains_attribute(Mesh::ATTRIBUTE_NORMAL) {
+        mesh.compute_normals();
+    }
+    if !force_field.material_vertex_color_enabled {
+        mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+    }
+
+    let collider = Collider::trimesh_from_mesh(&mesh);
+    if collider.is_none() {
+        warn!("force field mesh can't be used for collision");
+    }
+
+    let displacement_map = &force_field.material_displacement_map.path;
+    let material = ForceFieldMaterial {
+        uniform: ForceFieldUniform {
+            color: LinearRgba::rgb(
+                force_field.material_color.0,
+                force_field.material_color.1,
+                force_field.material_color.2,
+            ),
+            width: force_field.material_width,
+            alpha_power: force_field.material_alpha_power,
+            alpha_falloff_power: force_field.material_alpha_falloff_power,
+            max_radius: force_field.material_max_radius,
+            ripple_distortion: force_field.material_ripple_distortion,
+            map_distortion: force_field.material_map_distortion,
+            ripple_count: 0,
+            ripples: [Vec4::ZERO; MAX_RIPPLES],
+        },
+        displacement_map: (!displacement_map.is_empty())
+            .then(|| assets.load_linear_image(find_image(displacement_map, content_path))),
+    };
+
+    Some(ForceFieldAssets {
+        mesh: assets.add(mesh),
+        material: assets.add(material),
+        collider,
+    })
+}
+
+pub(super) fn spawn_force_field(
+    Spawner::Parent(parent): Spawner,
+    force_field: &remagic::xnb_readers::magicka_content::ForceField,
+    force_field_assets: Option<&ForceFieldAssets>,
+) -> Entity {
+    let mut force_field_commands = parent.spawn((
+        Name::new("Force Field"),
+        ForceField {
+            ttl: force_field.ttl,
+            ripples: Vec::new(),
+        },
+        Transform::default(),
+        Visibility::default(),
+    ));
+    if let Some(ForceFieldAssets {
+        mesh,
+        material,
+        collider,
+    }) = force_field_assets
+    {
+        force_field_commands.insert((
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            NotShadowCaster,
+        ));
+        if let Some(collider) = collider {
+            force_field_commands.insert((
+                RigidBody::Static,
+                collider.clone(),
+                // Blocks like level geometry, but spells need to hit it too
+                CollisionLayers::new(
+                    Layers::Level,
+                    LayerMask::ALL & !LayerMask::from(Layers::Trigger),
+                ),
+                CollisionEventsEnabled,
+            ));
+        }
+    }
+    force_field_commands.id()
+}
+
+fn ripple_on_impact(
+    collision: On<CollisionStart>,
+    mut force_fields: Query<&mut ForceField>,
+    transform_helper: TransformHelper,
+) {
+    let (force_field, other) = match (collision.body1, collision.body2) {
+        (Some(body1), _) if force_fields.contains(body1) => (body1, collision.collider2),
+        (_, Some(body2)) if force_fields.contains(body2) => (body2, collision.collider1),
+        _ => return,
+    };
+    // The contact point isn't reliable on trimeshes, use where the other collider is instead
+    let Ok(impact) = transform_helper.compute_global_transform(other) else {
+        return;
+    };
+    let mut force_field = force_fields.get_mut(force_field).unwrap();
+    if force_field.ripples.len() >= MAX_RIPPLES {
+        // Replace the oldest
+        force_field.ripples.remove(0);
+    }
+    force_field.ripples.push((impact.translation(), 0.));
+}
+
+fn update_ripples(
+    force_fields: Query<(&mut ForceField, &MeshMaterial3d<ForceFieldMaterial>)>,
+    mut materials: ResMut<Assets<ForceFieldMaterial>>,
+    time: Res<Time>,
+) {
+    for (mut force_field, material) in force_fields {
+        if force_field.ripples.is_empty() {
+            continue;
+        }
+        let ttl = force_field.ttl.max(f32::EPSILON);
+        for (_, age) in &mut force_field.ripples {
+            *age += time.delta_secs();
+        }
+        force_field.ripples.retain(|&(_, age)| age < ttl);
+
+        let Some(mut material) = materials.get_mut(material) else {
+            continue;
+        };
+        let uniform = &mut material.uniform;
+        uniform.ripple_count = force_field.ripples.len() as u32;
+        for (slot, &(point, age)) in uniform.ripples.iter_mut().zip(&force_field.ripples) {
+            *slot = point.extend(age / ttl);
+        }
+    }
+}
+
+/// Magicka's force field effect: a glowing rim that distorts what's behind it, with ripples
+/// spreading out from impacts.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct ForceFieldMaterial {
+    #[uniform(0)]
+    uniform: ForceFieldUniform,
+    #[texture(1)]
+    #[sampler(2)]
+    displacement_map: Option<Handle<Image>>,
+}
+
+#[derive(ShaderType, Reflect, Debug, Clone)]
+struct ForceFieldUniform {
+    color: LinearRgba,
+    width: f32,
+    alpha_power: f32,
+    alpha_falloff_power: f32,
+    max_radius: f32,
+    ripple_distortion: f32,
+    map_distortion: f32,
+    ripple_count: u32,
+    /// The impact point in world space, and the ripple's age from 0 to 1.
+    ripples: [Vec4; MAX_RIPPLES],
+}
+
+impl Material for ForceFieldMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn reads_view_transmission_texture(&self) -> bool {
+        // For distorting what's behind
+        true
+    }
+}
@@ -1,6 +1,14 @@
 use super::map_vec3;
-use bevy::{ecs::relationship::RelatedSpawnerCommands, light::light_consts, prelude::*};
-use remagic::xnb_readers::magicka_content::LightData;
+use crate::{
+    camera::CameraGroupFollower,
+    settings::{Settings, ShadowQuality},
+};
+use bevy::{
+    ecs::relationship::RelatedSpawnerCommands,
+    light::{CascadeShadowConfig, DirectionalLightShadowMap, PointLightShadowMap, light_consts},
+    prelude::*,
+};
+use remagic::xnb_readers::magicka_content::{LightData, LightVariationType};
 
 /// EV_100
 pub const MAGICKA_EXPOSURE: f32 = bevy::camera::Exposure::EV100_BLENDER;
@@ -20,15 +28,28 @@ pub fn plugin(app: &mut App) {
         brightness: MAGICKA_TO_LUMINANCE,
         ..default()
     });
+    app.register_type::<LightVariation>();
+    app.register_type::<ShadowMapSize>();
     app.add_systems(
         PostUpdate,
-        apply_ambient_light_source
-            .after(bevy::camera::visibility::VisibilitySystems::VisibilityPropagate),
+        (apply_shadow_map_sizes, fit_shadow_cascades)
+            .before(bevy::light::SimulationLightSystems::UpdateDirectionalLightCascades),
+    );
+    app.add_systems(
+        PostUpdate,
+        (
+            vary_lights,
+            apply_ambient_light_source
+                .after(bevy::camera::visibility::VisibilitySystems::VisibilityPropagate),
+        )
+            .chain(),
     );
 }
 #[derive(Component, Debug)]
 pub struct AmbientLightSource {
     pub color: Vec3,
+    /// Scales [`color`](Self::color), for [`LightVariation`].
+    pub intensity: f32,
 }
 fn apply_ambient_light_source(
     ambient_lights: Query<
@@ -46,7 +67,9 @@ fn apply_ambient_light_source(
         warn_once!("There are multiple visible ambient light sources");
     }
 
-    let color = ambient_light.map(|c| c.color).unwrap_or(Vec3::ZERO);
+    let color = ambient_light
+        .map(|c| c.color * c.intensity)
+        .unwrap_or(Vec3::ZERO);
     ambient_color.color = LinearRgba::from_vec3(color).into();
 }
 
@@ -54,17 +77,6 @@ pub(crate) fn spawn_light(
     parent: &mut RelatedSpawnerCommands<ChildOf>,
     light: &remagic::xnb_readers::magicka_content::Light,
 ) -> Entity {
-    if !matches!(
-        light.variation_type,
-        remagic::xnb_readers::magicka_content::LightVariationType::None,
-    ) {
-        // When implementing light variation, remember to add support to AmbientLightSource/AmbientLight
-        info!(
-            "Unhandled light variation type on {:?}: {:?} (amount {}, speed {})",
-            light.name, light.variation_type, light.variation_amount, light.variation_speed
-        );
-    }
-
     let transform = match &light.data {
         LightData::Point { position, .. } => Transform::from_translation(map_vec3(*position)),
         LightData::Directional { direction } => {
@@ -91,6 +103,7 @@ pub(crate) fn spawn_light(
         transform,
         AmbientLightSource {
             color: map_vec3(light.ambient_color),
+            intensity: 1.,
         },
     ));
     let color_vec = map_vec3(light.diffuse_color);
@@ -103,31 +116,21 @@ pub(crate) fn spawn_light(
         } => {
             entity_commands.insert((PointLight {
                 color: diffuse_color,
-                intensity: magnitude * MAGICKA_TO_LUMINOUS_INTENSITY,
-                range: *radius, // TODO: range
-                radius: 0.,     // TODO radius
+                intensity: intensity_for_range(magnitude, *radius, true),
+                range: *radius,
+                // Magicka's radius is the range, its light data has no source size
+                radius: 0.,
                 shadow_maps_enabled: light.cast_shadows,
                 ..default()
             },))
         }
-        LightData::Directional { direction: _ } => entity_commands.insert((
-            DirectionalLight {
-                color: diffuse_color,
-                illuminance: magnitude * MAGICKA_TO_ILLUMINANCE,
-                shadow_maps_enabled: light.cast_shadows,
-                ..default()
-            },
-            {
-                let camera_offset_distance = 223.55536;
-                bevy::light::CascadeShadowConfigBuilder {
-                    minimum_distance: camera_offset_distance * 0.8,
-                    first_cascade_far_bound: camera_offset_distance * 1.1,
-                    maximum_distance: camera_offset_distance * 1.5,
-                    ..default()
-                }
-                .build()
-            },
-        )),
+        // Its cascades are fit to the camera by fit_shadow_cascades
+        LightData::Directional { direction: _ } => entity_commands.insert(DirectionalLight {
+            color: diffuse_color,
+            illuminance: magnitude * MAGICKA_TO_ILLUMINANCE,
+            shadow_maps_enabled: light.cast_shadows,
+            ..default()
+        }),
         LightData::Spot {
             position: _,
             range,
@@ -137,14 +140,230 @@ pub(crate) fn spawn_light(
             use_attenuation,
         } => entity_commands.insert(SpotLight {
             color: diffuse_color,
-            intensity: magnitude * MAGICKA_TO_LUMINOUS_INTENSITY,
-            range: 20., // TODO: range
-            radius: 0., // TODO radius
+            intensity: intensity_for_range(magnitude, *range, *use_attenuation),
+            range: *range,
+            // Its light data has no source size either
+            radius: 0.,
             shadow_maps_enabled: light.cast_shadows,
             outer_angle: *cutoff_angle,
             inner_angle: *cutoff_angle * *sharpness,
             ..default()
         }),
+    };
+
+    if light.cast_shadows
+        && let Ok(size) = u32::try_from(light.shadow_map_size)
+        && size > 0
+    {
+        entity_commands.insert(ShadowMapSize(size));
+    }
+    if let Some(kind) = LightVariationKind::from_magicka(light.variation_type) {
+        entity_commands.insert(LightVariation {
+            kind,
+            amount: light.variation_amount,
+            speed: light.variation_speed,
+            time: 0.,
+            from: 0.,
+            to: 0.,
+            base: None,
+        });
+    }
+    entity_commands.id()
+}
+
+/// The intensity of a point or spot light with a range.
+///
+/// Magicka's lights fall off as `(1 - distance / range)²`, or not at all without attenuation,
+/// while Bevy's fall off with the inverse square of the distance. The two are matched halfway to
+/// the range, so lights with a larger range light more of the level like they do in Magicka.
+/// [`MAGICKA_TO_LUMINOUS_INTENSITY`] is the intensity that gives a light of magnitude 1 at 1 m.
+fn intensity_for_range(magnitude: f32, range: f32, use_attenuation: bool) -> f32 {
+    let distance = 0.5 * range;
+    // (1 - 0.5)² halfway
+    let falloff = if use_attenuation { 0.25 } else { 1. };
+    magnitude * MAGICKA_TO_LUMINOUS_INTENSITY * falloff * distance * distance
+}
+
+/// The shadow map resolution a level light asks for. Bevy shares one size between all lights of
+/// a kind, so the largest is used, scaled by the [`ShadowQuality`].
+#[derive(Component, Debug, Reflect)]
+pub struct ShadowMapSize(pub u32);
+
+fn apply_shadow_map_sizes(
+    settings: Res<Settings>,
+    lights: Query<(&ShadowMapSize, Has<PointLight>)>,
+    changed: Query<(), Changed<ShadowMapSize>>,
+    mut point_shadow_map: ResMut<PointLightShadowMap>,
+    mut directional_shadow_map: ResMut<DirectionalLightShadowMap>,
+) {
+    if !settings.is_changed() && changed.is_empty() {
+        return;
+    }
+    let scale = settings.graphics.shadow_quality.map_size_scale();
+    let size = |size: u32| {
+        ((size as f32 * scale) as usize)
+            .next_power_of_two()
+            .clamp(MIN_SHADOW_MAP_SIZE, MAX_SHADOW_MAP_SIZE)
+    };
+    let max_size = |point: bool| {
+        lights
+            .iter()
+            .filter(|(_, is_point)| *is_point == point)
+            .map(|(ShadowMapSize(s), _)| *s)
+            .max()
+    };
+    // Spot lights use the directional shadow map size
+    if let Some(max) = max_size(true)
+        && point_shadow_map.size != size(max)
+    {
+        point_shadow_map.size = size(max);
+    }
+    if let Some(max) = max_size(false)
+        && directional_shadow_map.size != size(max)
+    {
+        directional_shadow_map.size = size(max);
+    }
+}
+
+const MIN_SHADOW_MAP_SIZE: usize = 256;
+
+const MAX_SHADOW_MAP_SIZE: usize = 8192;
+
+/// How far past what the camera sees on the ground shadows are still needed, for tall things.
+const SHADOW_MARGIN: f32 = 10.;
+
+/// Fits the cascades of every level directional light, with or without a [`ShadowMapSize`], to
+/// what the following camera sees, which depends on how magnified it is.
+fn fit_shadow_cascades(
+    settings: Res<Settings>,
+    camera: Option<Single<&CameraGroupFollower>>,
+    lights: Query<(&mut CascadeShadowConfig, Ref<DirectionalLight>), With<AmbientLightSource>>,
+    mut fitted: Local<Option<(f32, ShadowQuality)>>,
+) {
+    let magnify = camera.map_or(1., |camera| camera.magnify);
+    let quality = settings.graphics.shadow_quality;
+    let refit = *fitted != Some((magnify, quality));
+    *fitted = Some((magnify, quality));
+
+    let distance = crate::camera::follow_distance();
+    let depth = crate::camera::follow_view_depth(magnify) + SHADOW_MARGIN;
+    for (mut cascades, light) in lights {
+        if !refit && !light.is_added() {
+            continue;
+        }
+        *cascades = bevy::light::CascadeShadowConfigBuilder {
+            num_cascades: quality.cascades(),
+            minimum_distance: (distance - depth).max(0.1),
+            first_cascade_far_bound: distance,
+            maximum_distance: distance + depth,
+            ..default()
+        }
+        .build();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum LightVariationKind {
+    /// Pulses smoothly.
+    Sine,
+    /// Jumps to a random brightness.
+    Flicker,
+    /// Wavers smoothly between random brightnesses.
+    Candle,
+    /// Blinks on and off.
+    Strobe,
+}
+
+impl LightVariationKind {
+    fn from_magicka(variation: LightVariationType) -> Option<Self> {
+        match variation {
+            LightVariationType::None => None,
+            LightVariationType::Sine => Some(Self::Sine),
+            LightVariationType::Flicker => Some(Self::Flicker),
+            LightVariationType::Candle => Some(Self::Candle),
+            LightVariationType::Strobe => Some(Self::Strobe),
+        }
+    }
+}
+
+/// Animates the intensity of a level light and its ambient contribution, by up to `amount` of it.
+#[derive(Component, Debug, Reflect)]
+pub struct LightVariation {
+    pub kind: LightVariationKind,
+    pub amount: f32,
+    /// How many variations there are per second.
+    pub speed: f32,
+    time: f32,
+    /// The random variations being moved between, from -1 to 1.
+    from: f32,
+    to: f32,
+    /// The intensity of the light without variation, once known.
+    base: Option<f32>,
+}
+
+impl LightVariation {
+    /// How much to scale the intensity by, after advancing by `delta` seconds.
+    fn advance(&mut self, delta: f32, rng: &mut impl rand::Rng) -> f32 {
+        use rand::distr::uniform::SampleRange as _;
+        use std::f32::consts::TAU;
+
+        self.time += delta * self.speed.max(0.);
+        while self.time >= 1. {
+            self.time -= 1.;
+            self.from = self.to;
+            self.to = (-1.0..=1.0).sample_single(rng).unwrap();
+        }
+        let variation = match self.kind {
+            LightVariationKind::Sine => (self.time * TAU).sin(),
+            LightVariationKind::Flicker => self.to,
+            LightVariationKind::Candle => {
+                let t = self.time;
+                self.from.lerp(self.to, t * t * (3. - 2. * t))
+            }
+            LightVariationKind::Strobe => {
+                if self.time < 0.5 {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+        };
+        (1. + variation * self.amount).max(0.)
+    }
+}
+
+fn vary_lights(
+    lights: Query<(
+        &mut LightVariation,
+        Option<&mut PointLight>,
+        Option<&mut SpotLight>,
+        Option<&mut DirectionalLight>,
+        Option<&mut AmbientLightSource>,
+    )>,
+    time: Res<Time>,
+) {
+    use rand::distr::uniform::SampleRange as _;
+
+    let mut rng: rand::rngs::SmallRng = rand::make_rng();
+    for (mut variation, point, spot, directional, ambient) in lights {
+        if variation.base.is_none() {
+            // So lights with the same variation don't all pulse together
+            variation.time = (0.0..1.0).sample_single(&mut rng).unwrap();
+        }
+        let scale = variation.advance(time.delta_secs(), &mut rng);
+        let intensity = if let Some(point) = point {
+            Some(point.map_unchanged(|l| &mut l.intensity))
+        } else if let Some(spot) = spot {
+            Some(spot.map_unchanged(|l| &mut l.intensity))
+        } else {
+            directional.map(|directional| directional.map_unchanged(|l| &mut l.illuminance))
+        };
+        if let Some(mut intensity) = intensity {
+            let base = *variation.base.get_or_insert(*intensity);
+            *intensity = base * scale;
+        }
+        if let Some(mut ambient) = ambient {
+            ambient.intensity = scale;
+        }
     }
-    .id()
 }
@@ -1,30 +1,47 @@
 // Could consider using this for waves:
 // https://github.com/Neopallium/bevy_water
 
-use crate::magicka_level_model::effect::{VertexColorState, find_image};
+use crate::{
+    magicka_assets::AssetCreator,
+    magicka_level_model::effect::{VertexColorState, find_image},
+};
 
-use super::{Spawner, effect, xna_geom};
+use super::{Spawner, collision::Layers, effect, xna_geom};
+use avian3d::prelude::{Collider, CollisionLayers, LayerMask, Sensor};
 use bevy::{prelude::*, render::render_resource::Face};
 use remagic::xnb_readers::magicka_effect::{DeferredLiquidEffect, LavaEffect};
 use typed_path::PlatformPath;
 
 #[derive(Component, Reflect)]
 pub struct Liquid {
+    pub kind: LiquidKind,
     pub freezable: bool,
+    /// Whether the whole surface freezes at once, rather than only where it's hit.
+    pub auto_freeze: bool,
+    pub ice_material: Option<Handle<StandardMaterial>>,
 }
 
-pub(crate) fn spawn_liquid(
-    Spawner::Parent(parent): Spawner,
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum LiquidKind {
+    Water,
+    Lava,
+}
+
+/// The assets of a [`Liquid`](remagic::xnb_readers::magicka_content::Liquid), if it has valid mesh data.
+#[derive(Debug)]
+pub(crate) struct LiquidAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    ice_material: Option<Handle<StandardMaterial>>,
+    /// The surface, for finding how deep things are in the liquid.
+    collider: Option<Collider>,
+}
+
+pub(crate) fn load_liquid(
     liquid: &remagic::xnb_readers::magicka_content::Liquid,
     content_path: &PlatformPath,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<StandardMaterial>,
-    assets: &AssetServer,
-) -> Entity {
-    let liquid_component = Liquid {
-        freezable: liquid.freezable,
-    };
-
+    assets: &mut impl AssetCreator,
+) -> Option<LiquidAssets> {
     let (
         Some(vertices),
         Some(indices),
@@ -42,7 +59,7 @@ pub(crate) fn spawn_liquid(
     )
     else {
         error!("liquid has missing or invalid mesh data");
-        return parent.spawn((Name::new("Liquid"), liquid_component)).id();
+        return None;
     };
 
     let stream = 0;
@@ -61,12 +78,17 @@ pub(crate) fn spawn_liquid(
         Face::Front
     };
 
-    let (material, vertex_color_state) = match &liquid.effect {
+    let (material, vertex_color_state, ice_material) = match &liquid.effect {
         remagic::xnb_readers::magicka_content::LiquidEffect::DeferredLiquid(effect) => {
-            translate_effect_liquid(effect, content_path, back_face, assets)
+            let (material, vertex_color_state) =
+                translate_effect_liquid(effect, content_path, back_face, assets);
+            let ice_material = translate_effect_ice(effect, content_path, assets);
+            (material, vertex_color_state, Some(ice_material))
         }
         remagic::xnb_readers::magicka_content::LiquidEffect::Lava(effect) => {
-            translate_effect_lava(effect, content_path, back_face, assets)
+            let (material, vertex_color_state) =
+                translate_effect_lava(effect, content_path, back_face, assets);
+            (material, vertex_color_state, None)
         }
     };
     if matches!(vertex_color_state, effect::VertexColorState::Disabled) {
@@ -74,25 +96,62 @@ pub(crate) fn spawn_liquid(
     }
 
     info!("liquid has unhandled collision={:?}", liquid.collision);
-    if liquid.auto_freeze {
-        info!("liquid has unhandled auto_freeze=true");
+    let collider = Collider::trimesh_from_mesh(&mesh);
+    if collider.is_none() {
+        warn!("liquid mesh can't be used to find depths in it");
     }
 
-    parent
-        .spawn((
-            Name::new("Liquid"),
-            liquid_component,
-            Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(materials.add(material)),
-        ))
-        .id()
+    Some(LiquidAssets {
+        mesh: assets.add(mesh),
+        material: assets.add(material),
+        ice_material: ice_material.map(|material| assets.add(material)),
+        collider,
+    })
+}
+
+pub(super) fn spawn_liquid(
+    Spawner::Parent(parent): Spawner,
+    liquid: &remagic::xnb_readers::magicka_content::Liquid,
+    liquid_assets: Option<&LiquidAssets>,
+) -> Entity {
+    let liquid_component = Liquid {
+        kind: match liquid.effect {
+            remagic::xnb_readers::magicka_content::LiquidEffect::DeferredLiquid(_) => {
+                LiquidKind::Water
+            }
+            remagic::xnb_readers::magicka_content::LiquidEffect::Lava(_) => LiquidKind::Lava,
+        },
+        freezable: liquid.freezable,
+        auto_freeze: liquid.auto_freeze,
+        ice_material: liquid_assets.and_then(|assets| assets.ice_material.clone()),
+    };
+
+    let mut liquid_commands = parent.spawn((Name::new("Liquid"), liquid_component));
+    if let Some(LiquidAssets {
+        mesh,
+        material,
+        ice_material: _,
+        collider,
+    }) = liquid_assets
+    {
+        liquid_commands.insert((Mesh3d(mesh.clone()), MeshMaterial3d(material.clone())));
+        if let Some(collider) = collider {
+            // Characters sink into liquids, their surface is only used to find how deep they are
+            liquid_commands.insert((
+                collider.clone(),
+                Sensor,
+                CollisionLayers::new(Layers::Liquid, LayerMask::NONE),
+            ));
+        }
+    }
+    liquid_commands.id()
 }
 
 fn translate_effect_liquid(
     effect: &DeferredLiquidEffect,
     content_path: &PlatformPath,
     back_face: Face,
-    assets: &AssetServer,
+    assets: &mut impl AssetCreator,
 ) -> (StandardMaterial, VertexColorState) {
     let DeferredLiquidEffect {
         ref reflection_map,
@@ -107,7 +166,7 @@ fn translate_effect_liquid(
         water_spec_power,
         ref bottom_texture,
         ref water_normal_map,
-        // TODO: The material to use when frozen
+        // The material to use when frozen, see translate_effect_ice
         ice_reflectiveness: _,
         ice_color: _,
         ice_emissive_amount: _,
@@ -164,17 +223,7 @@ fn translate_effect_liquid(
         // normal_map_channel: todo!(),
         // normal_map_texture: todo!(),
         normal_map_texture: if !water_normal_map.path.is_empty() {
-            Some(
-                assets
-                    .load_builder()
-                    .with_settings(
-                        |s: &mut crate::magicka_assets::image::MagickaTexture2dLoaderSettings| {
-                            s.is_srgb = false;
-                        },
-                    )
-                    .override_unapproved()
-                    .load(find_image(water_normal_map.path.as_str(), content_path)),
-            )
+            Some(assets.load_linear_image(find_image(water_normal_map.path.as_str(), content_path)))
         } else {
             None
         },
@@ -204,11 +253,36 @@ fn translate_effect_liquid(
     (material, effect::VertexColorState::Disabled)
 }
 
+/// The material of ice where the liquid has been frozen.
+fn translate_effect_ice(
+    effect: &DeferredLiquidEffect,
+    content_path: &PlatformPath,
+    assets: &mut impl AssetCreator,
+) -> StandardMaterial {
+    let (base_color, emissive) =
+        effect::blend_base_emissive(effect.ice_color, effect.ice_emissive_amount);
+    let (reflectance, perceptual_roughness) =
+        effect::specular_to_pbr(effect.ice_spec_amount, effect.ice_spec_power);
+    StandardMaterial {
+        base_color,
+        base_color_texture: (!effect.ice_diffuse_map.path.is_empty())
+            .then(|| assets.load(find_image(&effect.ice_diffuse_map.path, content_path))),
+        emissive,
+        perceptual_roughness,
+        reflectance,
+        normal_map_texture: (!effect.ice_normal_map.path.is_empty()).then(|| {
+            assets.load_linear_image(find_image(&effect.ice_normal_map.path, content_path))
+        }),
+        flip_normal_map_y: true,
+        ..default()
+    }
+}
+
 fn translate_effect_lava(
     effect: &LavaEffect,
     _content_path: &PlatformPath,
     back_face: Face,
-    _assets: &AssetServer,
+    _assets: &mut impl AssetCreator,
 ) -> (StandardMaterial, VertexColorState) {
     warn!("unhandled liquid effect {effect:#?}");
     let material = StandardMaterial {
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use crate::magicka_level_model::map_vec3;
+use avian3d::prelude::Collider;
 use bevy::{ecs::relationship::RelatedSpawnerCommands, prelude::*};
 use bevy_landmass::{
-    Archipelago3d, ArchipelagoOptions, ArchipelagoRef3d, FromAgentRadius as _, Island,
-    Island3dBundle, Landmass3dPlugin, NavMesh3d, NavMeshHandle, NavigationMesh3d, ValidationError,
-    Velocity3d, coords::ThreeD,
+    AgentTypeIndexCostOverrides, Archipelago3d, ArchipelagoOptions, ArchipelagoRef3d,
+    FromAgentRadius as _, Island, Island3dBundle, Landmass3dPlugin, NavMesh3d, NavMeshHandle,
+    NavigationMesh3d, ValidationError, Velocity3d, coords::ThreeD,
 };
 use remagic::xnb_readers::magicka_content::{NavMesh as MagickaNavMesh, PathFindingTriangle};
 
@@ -42,57 +43,125 @@ pub fn plugin(app: &mut App) {
     }
 }
 
-#[derive(bevy::ecs::system::SystemParam)]
-pub struct SpawnParams<'w> {
-    assets: ResMut<'w, Assets<NavMesh3d>>,
+/// How a nav mesh triangle can be moved through, and how a character can move.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Reflect)]
+pub struct MovementProperties(pub u8);
+
+impl MovementProperties {
+    pub const DEFAULT: Self = Self(0);
+    pub const WATER: Self = Self(1);
+    pub const JUMP: Self = Self(2);
+    pub const FLY: Self = Self(4);
+    /// Can be blocked by things moving in the level. Doesn't restrict who can move through.
+    pub const DYNAMIC: Self = Self(128);
+
+    const KNOWN: u8 = Self::WATER.0 | Self::JUMP.0 | Self::FLY.0 | Self::DYNAMIC.0;
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether a character that can move like `self` can move through a triangle with `triangle`.
+    pub fn allows(self, triangle: Self) -> bool {
+        self.contains(Self(triangle.0 & !Self::DYNAMIC.0))
+    }
+}
+
+/// How much more it costs to cross triangles that a character can't normally move through, so it
+/// only paths through them when there's no other way.
+const AVOIDED_COST: f32 = 100.;
+
+/// Magicka's edge costs are turned into per-triangle cost multipliers, in steps of this size from 1.
+const COST_STEP: f32 = 0.25;
+
+const MAX_COST_STEPS: usize = 15;
+
+/// The landmass type index of triangles with `properties`, and `cost_steps` steps of extra cost.
+fn type_index(properties: MovementProperties, cost_steps: usize) -> usize {
+    (cost_steps.min(MAX_COST_STEPS) << 8) | usize::from(properties.0 & MovementProperties::KNOWN)
+}
+
+/// All type indices used by nav meshes, with their properties and base costs.
+fn type_indices() -> impl Iterator<Item = (usize, MovementProperties, f32)> {
+    (0..=MAX_COST_STEPS).flat_map(|cost_steps| {
+        (0..=u8::MAX)
+            .filter(|properties| properties & !MovementProperties::KNOWN == 0)
+            .map(move |properties| {
+                let properties = MovementProperties(properties);
+                let cost = 1. + cost_steps as f32 * COST_STEP;
+                (type_index(properties, cost_steps), properties, cost)
+            })
+    })
+}
+
+/// The costs for an agent that moves like `movement`, making it avoid the triangles it can't
+/// move through.
+pub fn agent_type_costs(movement: MovementProperties) -> AgentTypeIndexCostOverrides {
+    let mut overrides = AgentTypeIndexCostOverrides::default();
+    for (type_index, properties, cost) in type_indices() {
+        if !movement.allows(properties) {
+            overrides.set_type_index_cost(type_index, cost * AVOIDED_COST);
+        }
+    }
+    overrides
 }
 
 pub struct NavMeshSetup {
-    archipelago: Entity,
+    pub archipelago: Entity,
 }
 
 pub fn setup_for_level(level: &mut RelatedSpawnerCommands<ChildOf>) -> NavMeshSetup {
     let archipelago = level
         .spawn((
             Name::new("Navigation Mesh Archipelago"),
-            Archipelago3d::new({
-                let (length, radius, _mass, _speed, _turn_speed) = (0.5, 0.75, 70.0, 5.0, 7.0);
-                let total_height = length + radius * 2.;
-                let agent_radius = total_height * 0.5;
-                let mut opts = ArchipelagoOptions::<ThreeD>::from_agent_radius(agent_radius);
-                opts.point_sample_distance.horizontal_distance *= 50.;
-                opts.point_sample_distance.distance_below *= 10.;
-                opts.point_sample_distance.distance_above *= 20.;
-                opts.point_sample_distance.vertical_preference_ratio = 8.0;
-                opts
-            }),
+            {
+                let mut archipelago = Archipelago3d::new({
+                    let (length, radius, _mass, _speed, _turn_speed) =
+                        (0.5, 0.75, 70.0, 5.0, 7.0);
+                    let total_height = length + radius * 2.;
+                    let agent_radius = total_height * 0.5;
+                    let mut opts = ArchipelagoOptions::<ThreeD>::from_agent_radius(agent_radius);
+                    opts.point_sample_distance.horizontal_distance *= 50.;
+                    opts.point_sample_distance.distance_below *= 10.;
+                    opts.point_sample_distance.distance_above *= 20.;
+                    opts.point_sample_distance.vertical_preference_ratio = 8.0;
+                    opts
+                });
+                for (type_index, _, cost) in type_indices() {
+                    archipelago
+                        .set_type_index_cost(type_index, cost)
+                        .expect("type index costs are positive");
+                }
+                archipelago
+            },
         ))
         .id();
     NavMeshSetup { archipelago }
 }
 
-pub fn spawn(
-    parent: &mut RelatedSpawnerCommands<ChildOf>,
-    source_nav_mesh: &MagickaNavMesh,
-    params: &mut SpawnParams,
-    setup: &NavMeshSetup,
-) {
-    let bevy_nav_mesh = match convert_nav_mesh(source_nav_mesh) {
-        Ok(x) => x,
+/// Converts the nav mesh for adding as an asset, or logs why it can't be used.
+///
+/// Magicka's nav meshes float a little above the ground, so they're projected down onto `ground`.
+pub fn load(source_nav_mesh: &MagickaNavMesh, ground: &[&Collider]) -> Option<NavMesh3d> {
+    match convert_nav_mesh(source_nav_mesh, ground) {
+        Ok(x) => Some(x),
         Err(e) => {
             error!("Failed to create level navigation mesh: {}", e);
-            return;
+            None
         }
-    };
+    }
+}
 
+pub fn spawn(
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    nav_mesh_handle: &Handle<NavMesh3d>,
+    setup: &NavMeshSetup,
+) {
     let archipelago_id = setup.archipelago;
 
-    let nav_mesh_handle = params.assets.add(bevy_nav_mesh);
-
     parent.spawn((
         Name::new("Navigation Mesh Island"),
-        // TODO: Remove this hack, configure agent sampling better?
-        Transform::from_translation(Vec3::Y * -0.6), // Magicka nav mesh is floating above ground
+        Transform::default(),
         Island3dBundle {
             island: Island,
             archipelago_ref: ArchipelagoRef3d::new(archipelago_id),
@@ -109,38 +178,98 @@ fn propagate_physics_velocities_to_nav(
     }
 }
 
-fn convert_nav_mesh(magicka: &MagickaNavMesh) -> Result<NavMesh3d, ValidationError> {
+/// How far above the ground Magicka's nav meshes can be.
+const MAX_GROUND_DISTANCE: f32 = 2.;
+
+fn project_to_ground(point: Vec3, ground: &[&Collider]) -> Vec3 {
+    ground
+        .iter()
+        .filter_map(|collider| {
+            collider.cast_ray(
+                Vec3::ZERO,
+                Quat::IDENTITY,
+                point,
+                Vec3::NEG_Y,
+                MAX_GROUND_DISTANCE,
+                true,
+            )
+        })
+        .map(|(distance, _)| distance)
+        .min_by(f32::total_cmp)
+        .map_or(point, |distance| point + Vec3::NEG_Y * distance)
+}
+
+/// How many [`COST_STEP`]s more a triangle costs to cross than its size, from Magicka's costs of
+/// moving to each neighbor.
+fn cost_steps(index: usize, triangle: &PathFindingTriangle, centers: &[Vec3]) -> usize {
+    let center = |index: u16| centers.get(usize::from(index)).copied();
+    let own_center = centers[index];
+    let (sum, count) = [
+        (triangle.neighbors.0, triangle.costs.0),
+        (triangle.neighbors.1, triangle.costs.1),
+        (triangle.neighbors.2, triangle.costs.2),
+    ]
+    .into_iter()
+    .filter_map(|(neighbor, cost)| {
+        let distance = own_center.distance(center(neighbor)?);
+        (distance > f32::EPSILON && cost.is_finite()).then(|| cost / distance)
+    })
+    .fold((0., 0), |(sum, count), ratio| (sum + ratio, count + 1));
+    if count == 0 {
+        return 0;
+    }
+    let multiplier = sum / count as f32;
+    ((multiplier - 1.) / COST_STEP).round().clamp(0., MAX_COST_STEPS as f32) as usize
+}
+
+fn convert_nav_mesh(
+    magicka: &MagickaNavMesh,
+    ground: &[&Collider],
+) -> Result<NavMesh3d, ValidationError> {
+    let vertices: Vec<_> = magicka
+        .vertices
+        .iter()
+        .copied()
+        .map(|vertex| project_to_ground(map_vec3(vertex), ground))
+        .collect();
+    // Magicka's costs were measured on the original positions
+    let original_vertices: Vec<_> = magicka.vertices.iter().copied().map(map_vec3).collect();
+    let centers: Vec<_> = magicka
+        .triangles
+        .iter()
+        .map(|triangle| {
+            let (a, b, c) = triangle.vertices;
+            [a, b, c]
+                .into_iter()
+                .filter_map(|i| original_vertices.get(usize::from(i)))
+                .sum::<Vec3>()
+                / 3.
+        })
+        .collect();
+
     let nav_mesh = NavigationMesh3d {
-        vertices: magicka.vertices.iter().copied().map(map_vec3).collect(),
+        vertices,
         polygons: magicka
             .triangles
             .iter()
-            .map(
-                |PathFindingTriangle {
-                     vertices,
-                     neighbors,
-                     costs: _,
-                     properties: _,
-                 }| {
-                    vec![
-                        usize::from(vertices.0),
-                        usize::from(vertices.1),
-                        usize::from(vertices.2),
-                    ]
-                },
-            )
+            .map(|PathFindingTriangle { vertices, .. }| {
+                vec![
+                    usize::from(vertices.0),
+                    usize::from(vertices.1),
+                    usize::from(vertices.2),
+                ]
+            })
             .collect(),
         polygon_type_indices: magicka
             .triangles
             .iter()
-            .map(
-                |PathFindingTriangle {
-                     vertices: _,
-                     neighbors: _,
-                     costs,
-                     properties,
-                 }| { 0 },
-            )
+            .enumerate()
+            .map(|(index, triangle)| {
+                type_index(
+                    MovementProperties(triangle.properties),
+                    cost_steps(index, triangle, &centers),
+                )
+            })
             .collect(),
         height_mesh: None,
     };
@@ -2,6 +2,7 @@
 
 use crate::magicka_level_model::{Spawner, map_vec3};
 use bevy::{asset::AsAssetId, prelude::*};
+use std::collections::HashMap;
 
 pub fn debug_visual_effects(
     effects: Query<&GlobalTransform, With<VisualEffect>>,
@@ -16,7 +17,7 @@ pub fn debug_visual_effects(
 pub fn spawn_visual_effect(
     Spawner::Parent(parent): Spawner,
     visual_effect: &remagic::xnb_readers::magicka_content::VisualEffect,
-    assets: &AssetServer,
+    effects: &HashMap<String, Handle<crate::magicka_assets::visual_effect::VisualEffect>>,
 ) {
     let position = map_vec3(visual_effect.position);
     let direction = map_vec3(visual_effect.direction);
@@ -25,8 +26,10 @@ pub fn spawn_visual_effect(
         Transform::from_translation(position).looking_to(direction, Vec3::Y),
         Visibility::default(),
         VisualEffect {
-            // TODO: Find the visual effect by name in the Effects/ folders
-            asset: Handle::Uuid(AssetId::<WorldAsset>::INVALID_UUID, std::marker::PhantomData),
+            asset: effects
+                .get(&visual_effect.effect.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default(),
             effect: visual_effect.effect.clone(),
             range: visual_effect.range,
         },
@@ -10,17 +10,46 @@ pub enum SceneError {
     Xml(#[from] xml::reader::Error),
     #[error("There is no root <Scene> element in the XML data.")]
     NoSceneElement,
+    #[error("Trigger condition <{0}> is missing an attribute or has an invalid one.")]
+    InvalidCondition(String),
+    #[error("Trigger action <{0}> is missing an attribute or has an invalid one.")]
+    InvalidAction(String),
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct SceneConfig {
     pub force_camera: Option<bool>,
     pub force_nav_mesh: Option<bool>,
     pub model: Option<String>,
+    pub sky: Option<SceneSky>,
+    pub fog: Option<SceneFog>,
     pub triggers: Vec<Trigger>,
 }
 
-#[derive(Debug)]
+/// What is drawn behind the level, from a `<Sky>` element.
+///
+/// The layout of `<Sky>` and `<Fog>` hasn't been checked against the scenes in Content yet, which
+/// the ignored `content_scene_sky_and_fog` test does.
+#[derive(Clone, Debug, Default)]
+pub struct SceneSky {
+    /// A cube map, relative to the scene's folder like the model.
+    pub texture: Option<String>,
+    /// sRGB. Fills the background where there is no texture.
+    pub color: Option<[f32; 3]>,
+}
+
+/// Linear distance fog, from a `<Fog>` element.
+#[derive(Clone, Debug)]
+pub struct SceneFog {
+    /// sRGB.
+    pub color: [f32; 3],
+    /// Distance from the camera where the fog begins.
+    pub start: f32,
+    /// Distance from the camera where the fog is opaque.
+    pub end: f32,
+}
+
+#[derive(Clone, Debug)]
 pub struct Trigger {
     pub id: Option<String>,
     pub autorun: bool,
@@ -28,20 +57,36 @@ pub struct Trigger {
     /// Nested list represents [ [X and Y] or [Z and W] ]
     pub conditions: Vec<Vec<TriggerCondition>>,
     pub actions: Vec<TriggerAction>,
+    /// For each `<Random>` group of actions, the weight of each of its choices.
+    pub random_groups: Vec<Vec<f32>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TriggerCondition {
     pub invert: bool,
     pub logic: TriggerConditionLogic,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TriggerAction {
     pub delay: f32,
+    pub group: ActionGroup,
     pub behavior: TriggerActionBehavior,
 }
 
+/// When an action runs as part of its trigger.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ActionGroup {
+    /// Every time the trigger executes.
+    #[default]
+    Always,
+    /// When this choice is picked from its `<Random>` group, one is picked every execution.
+    Random { group: usize, choice: usize },
+    /// Over and over from when the trigger executes until it is stopped, all `<Looped>`
+    /// actions together. The loop restarts once they have all run.
+    Looped,
+}
+
 pub fn read_scene(reader: impl BufRead) -> Result<SceneConfig, SceneError> {
     let mut parser = EventReader::new(reader);
 
@@ -123,6 +168,12 @@ fn read_element(
 
     if name.eq_ignore_ascii_case("model") {
         scene.model = Some(read_content_required(parser)?);
+    } else if name.eq_ignore_ascii_case("sky") {
+        scene.sky = Some(read_sky(attributes));
+        parser.skip()?;
+    } else if name.eq_ignore_ascii_case("fog") {
+        scene.fog = read_fog(attributes);
+        parser.skip()?;
     } else if name.eq_ignore_ascii_case("trigger") {
         scene.triggers.push(read_trigger(parser, attributes)?);
     } else {
@@ -135,6 +186,60 @@ fn read_element(
     Ok(())
 }
 
+fn read_sky(attributes: Vec<OwnedAttribute>) -> SceneSky {
+    let mut sky = SceneSky::default();
+    for OwnedAttribute { name, value } in attributes {
+        if name.namespace.is_some() {
+            continue;
+        }
+        let name = name.local_name.as_str();
+        if name.eq_ignore_ascii_case("texture") {
+            sky.texture = Some(value);
+        } else if name.eq_ignore_ascii_case("color") {
+            sky.color = parse_color(&value);
+        }
+    }
+    sky
+}
+
+fn read_fog(attributes: Vec<OwnedAttribute>) -> Option<SceneFog> {
+    let mut color = None;
+    let mut start = None;
+    let mut end = None;
+    for OwnedAttribute { name, value } in attributes {
+        if name.namespace.is_some() {
+            continue;
+        }
+        let name = name.local_name.as_str();
+        if name.eq_ignore_ascii_case("color") {
+            color = parse_color(&value);
+        } else if name.eq_ignore_ascii_case("start") {
+            start = value.parse().ok();
+        } else if name.eq_ignore_ascii_case("end") {
+            end = value.parse().ok();
+        }
+    }
+    let (Some(color), Some(start), Some(end)) = (color, start, end) else {
+        warn!("scene fog needs a color, start and end, ignoring");
+        return None;
+    };
+    Some(SceneFog { color, start, end })
+}
+
+/// Parses three comma or space separated components from 0 to 1.
+fn parse_color(value: &str) -> Option<[f32; 3]> {
+    let components: Option<Vec<f32>> = value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect();
+    let Some(Ok(color)) = components.map(<[f32; 3]>::try_from) else {
+        warn!("invalid scene color {value:?}, ignoring");
+        return None;
+    };
+    Some(color)
+}
+
 fn read_trigger(
     parser: &mut EventReader<impl BufRead>,
     attributes: Vec<OwnedAttribute>,
@@ -144,6 +249,7 @@ fn read_trigger(
     let mut repeat = None;
     let mut conditions_or = Vec::new();
     let mut actions = Vec::new();
+    let mut random_groups = Vec::new();
 
     for OwnedAttribute { name, value } in attributes {
         if name.local_name.eq_ignore_ascii_case("id") && name.namespace.is_none() {
@@ -167,7 +273,7 @@ fn read_trigger(
                 if name.local_name.eq_ignore_ascii_case("if") && name.namespace.is_none() {
                     conditions_or.push(read_trigger_conditions(parser)?);
                 } else if name.local_name.eq_ignore_ascii_case("then") && name.namespace.is_none() {
-                    actions = read_trigger_actions(parser)?;
+                    read_trigger_actions(parser, &mut actions, &mut random_groups)?;
                 } else {
                     parser.skip()?;
                 }
@@ -179,6 +285,7 @@ fn read_trigger(
                     repeat,
                     conditions: conditions_or,
                     actions,
+                    random_groups,
                 });
             }
             XmlEvent::EndDocument => unreachable!(),
@@ -223,67 +330,145 @@ fn read_trigger_condition(
         }
     });
 
+    let element_name = name.local_name.clone();
     let logic = crate::script_triggers::condition::read_logic(name, attributes)
-        .map_err(|_e| -> SceneError { todo!() })?;
+        .map_err(|()| SceneError::InvalidCondition(element_name))?;
     Ok(TriggerCondition { invert, logic })
 }
 
 fn read_trigger_actions(
     parser: &mut EventReader<impl BufRead>,
-) -> Result<Vec<TriggerAction>, SceneError> {
-    let mut actions = Vec::new();
-    let random_action_groups: Vec<Vec<TriggerAction>> = Vec::new();
-    let only_looped_actions: Vec<TriggerAction> = Vec::new();
+    actions: &mut Vec<TriggerAction>,
+    random_groups: &mut Vec<Vec<f32>>,
+) -> Result<(), SceneError> {
     loop {
         match parser.next()? {
             XmlEvent::StartElement {
                 name, attributes, ..
             } => {
                 if name.local_name.eq_ignore_ascii_case("random") && name.namespace.is_none() {
-                    warn!("unhandled random action group");
-                    parser.skip()?;
+                    let group = random_groups.len();
+                    let weights = read_random_action_group(parser, group, actions)?;
+                    random_groups.push(weights);
                 } else if name.local_name.eq_ignore_ascii_case("looped") && name.namespace.is_none()
                 {
-                    warn!("unhandled looped action group");
-                    parser.skip()?;
+                    let start = actions.len();
+                    read_action_list(parser, actions)?;
+                    for action in &mut actions[start..] {
+                        action.group = ActionGroup::Looped;
+                    }
                 } else {
-                    actions.push(read_trigger_action(parser, name, attributes)?);
+                    actions.push(read_trigger_action(parser, name, attributes)?.0);
                 }
             }
-            XmlEvent::EndElement { .. } => break,
+            XmlEvent::EndElement { .. } => return Ok(()),
             XmlEvent::EndDocument => unreachable!(),
             _ => {}
         }
     }
-    if !random_action_groups.is_empty() {
-        // actions.push(TriggerAction::Random { groups: random_action_groups });
+}
+
+/// Reads the choices of a `<Random>` group, returning their weights. Each child is a choice,
+/// either a single action or a `<Group>` of actions, with an optional `weight`.
+fn read_random_action_group(
+    parser: &mut EventReader<impl BufRead>,
+    group: usize,
+    actions: &mut Vec<TriggerAction>,
+) -> Result<Vec<f32>, SceneError> {
+    let mut weights = Vec::new();
+    loop {
+        match parser.next()? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let choice = weights.len();
+                let start = actions.len();
+                if name.local_name.eq_ignore_ascii_case("group") && name.namespace.is_none() {
+                    let weight = attributes
+                        .iter()
+                        .find(|attr| {
+                            attr.name.local_name.eq_ignore_ascii_case("weight")
+                                && attr.name.namespace.is_none()
+                        })
+                        .map_or(Ok(1.), |attr| attr.value.parse())
+                        .map_err(|_| SceneError::InvalidAction(name.local_name.clone()))?;
+                    weights.push(weight);
+                    read_action_list(parser, actions)?;
+                } else {
+                    let (action, weight) = read_trigger_action(parser, name, attributes)?;
+                    weights.push(weight);
+                    actions.push(action);
+                }
+                for action in &mut actions[start..] {
+                    action.group = ActionGroup::Random { group, choice };
+                }
+            }
+            XmlEvent::EndElement { .. } => return Ok(weights),
+            XmlEvent::EndDocument => unreachable!(),
+            _ => {}
+        }
     }
-    // ... looped
+}
 
-    Ok(actions)
+/// Reads plain actions until the end of the current element.
+fn read_action_list(
+    parser: &mut EventReader<impl BufRead>,
+    actions: &mut Vec<TriggerAction>,
+) -> Result<(), SceneError> {
+    loop {
+        match parser.next()? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let nested_group = name.namespace.is_none()
+                    && ["random", "looped", "group"]
+                        .iter()
+                        .any(|group| name.local_name.eq_ignore_ascii_case(group));
+                if nested_group {
+                    warn!("unhandled nested {:?} action group", name.local_name);
+                    parser.skip()?;
+                } else {
+                    actions.push(read_trigger_action(parser, name, attributes)?.0);
+                }
+            }
+            XmlEvent::EndElement { .. } => return Ok(()),
+            XmlEvent::EndDocument => unreachable!(),
+            _ => {}
+        }
+    }
 }
 
+/// Reads an action, and its weight for when it's a choice of a `<Random>` group.
 fn read_trigger_action(
     parser: &mut EventReader<impl BufRead>,
     name: OwnedName,
     mut attributes: Vec<OwnedAttribute>,
-) -> Result<TriggerAction, SceneError> {
+) -> Result<(TriggerAction, f32), SceneError> {
     let mut delay: f32 = 0.0;
+    let mut weight: Result<f32, _> = Ok(1.0);
     attributes.retain_mut(|attr| {
         if attr.name.namespace.is_some() {
             return false;
         }
         if attr.name.local_name.eq_ignore_ascii_case("delay") {
             delay = attr.value.parse().unwrap();
+        } else if attr.name.local_name.eq_ignore_ascii_case("weight") {
+            weight = attr.value.parse();
         } else {
             return true;
         }
         false
     });
+    let weight = weight.map_err(|_| SceneError::InvalidAction(name.local_name.clone()))?;
 
     let behavior = crate::script_triggers::action::read_behavior(parser, name, attributes)?;
 
-    Ok(TriggerAction { delay, behavior })
+    let action = TriggerAction {
+        delay,
+        group: ActionGroup::Always,
+        behavior,
+    };
+    Ok((action, weight))
 }
 
 fn read_content_required(parser: &mut EventReader<impl BufRead>) -> Result<String, SceneError> {
@@ -312,9 +497,11 @@ fn read_content_required(parser: &mut EventReader<impl BufRead>) -> Result<Strin
 
 #[cfg(test)]
 mod test {
-    use super::read_scene;
+    use super::{SceneConfig, SceneError, read_scene};
+    use crate::script_triggers::condition::TriggerConditionLogic;
     use std::fs::File;
     use std::io::BufReader;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn test() {
@@ -324,4 +511,105 @@ mod test {
         let scene = read_scene(file).unwrap();
         dbg!(&scene);
     }
+
+    /// Every scene in the Levels folder of Content, with its path.
+    fn content_scenes() -> Vec<(PathBuf, SceneConfig)> {
+        assert!(
+            crate::magicka_assets::init_content_root(None),
+            "Magicka Content not found"
+        );
+        let content = crate::magicka_assets::content_root();
+        let levels = crate::magicka_assets::find_path_ignore_ascii_case(
+            &Path::new(content.as_ref() as &std::ffi::OsStr).join("Levels"),
+        )
+        .unwrap();
+
+        let mut dirs = vec![levels];
+        let mut scene_paths: Vec<PathBuf> = Vec::new();
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"))
+                {
+                    scene_paths.push(path);
+                }
+            }
+        }
+
+        let mut scenes = Vec::new();
+        for path in scene_paths {
+            let file = BufReader::new(File::open(&path).unwrap());
+            match read_scene(file) {
+                Ok(scene) => scenes.push((path, scene)),
+                // Not every XML file in Levels is a scene
+                Err(SceneError::NoSceneElement) => continue,
+                Err(e) => panic!("{}: {e}", path.display()),
+            }
+        }
+        scenes
+    }
+
+    /// Every trigger condition used by the scenes in Content can be read.
+    #[test]
+    #[ignore = "needs Magicka Content"]
+    fn all_scene_triggers_known() {
+        let mut unknown = Vec::new();
+        for (path, scene) in content_scenes() {
+            let conditions = scene
+                .triggers
+                .iter()
+                .flat_map(|trigger| trigger.conditions.iter().flatten());
+            for condition in conditions {
+                if let TriggerConditionLogic::Unknown(name) = &condition.logic {
+                    unknown.push(format!("condition {name} in {}", path.display()));
+                }
+            }
+        }
+        assert!(
+            unknown.is_empty(),
+            "unknown trigger conditions: {unknown:#?}"
+        );
+    }
+
+    /// The `<Sky>` and `<Fog>` elements are found in the scenes in Content, with valid colors.
+    #[test]
+    #[ignore = "needs Magicka Content"]
+    fn content_scene_sky_and_fog() {
+        let scenes = content_scenes();
+        let skies: Vec<_> = scenes
+            .iter()
+            .filter_map(|(path, scene)| Some((path, scene.sky.as_ref()?)))
+            .collect();
+        let fogs: Vec<_> = scenes
+            .iter()
+            .filter_map(|(path, scene)| Some((path, scene.fog.as_ref()?)))
+            .collect();
+        assert!(!skies.is_empty(), "no scene in Content has a sky");
+        assert!(!fogs.is_empty(), "no scene in Content has fog");
+        let in_range = |c: &f32| (0. ..=1.).contains(c);
+        for (path, sky) in skies {
+            assert!(
+                sky.texture.is_some() || sky.color.is_some(),
+                "{}: sky without a texture or color",
+                path.display()
+            );
+            assert!(
+                sky.color.iter().flatten().all(in_range),
+                "{}: sky color {:?} isn't from 0 to 1",
+                path.display(),
+                sky.color
+            );
+        }
+        for (path, fog) in fogs {
+            assert!(
+                fog.color.iter().all(in_range) && fog.start < fog.end,
+                "{}: {fog:?}",
+                path.display()
+            );
+        }
+    }
 }
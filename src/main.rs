@@ -9,6 +9,7 @@
 mod camera;
 mod character;
 mod components_basic;
+mod content_picker;
 mod dev;
 mod dialog;
 mod gameplay;
@@ -18,6 +19,7 @@ mod magicka_level_model;
 mod magicka_scene;
 mod scene;
 mod script_triggers;
+mod settings;
 mod spelling;
 
 use avian3d::prelude::*;
@@ -26,34 +28,46 @@ use bevy::{
     prelude::*,
     window::{CursorGrabMode, CursorOptions},
 };
-use bevy_asset_loader::prelude::*;
 #[cfg(feature = "audio")]
 use bevy_seedling::prelude::*;
 
 fn main() -> AppExit {
+    let settings = settings::Settings::load();
+    // Must be known before the asset source is built, otherwise it's picked in game
+    let content_dir_found = magicka_assets::init_content_root(settings.content_dir.as_deref());
+
     let mut app = App::new();
-    app.insert_resource(ClearColor(Color::BLACK));
+    app.insert_resource(ClearColor(scene::DEFAULT_SKY_COLOR));
+    app.add_plugins(magicka_assets::source::plugin);
     app.add_plugins((
-        DefaultPlugins.set(AssetPlugin {
-            unapproved_path_mode: bevy::asset::UnapprovedPathMode::Deny,
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(settings.window()),
             ..default()
         }),
-        PhysicsPlugins::default(),
+        PhysicsPlugins::default()
+            .with_collision_hooks::<magicka_level_model::collision::LevelCollisionHooks>(),
         bevy_enhanced_input::EnhancedInputPlugin,
         #[cfg(feature = "audio")]
         SeedlingPlugin::default(),
         bevy_hanabi::HanabiPlugin,
     ))
     .register_type_data::<TrimeshFlags, ReflectDeserialize>();
-    app.init_state::<LoadState>().add_loading_state(
-        LoadingState::new(LoadState::Loading).continue_to_state(LoadState::Loaded),
-    );
+    app.insert_resource(settings);
+    app.insert_state(if content_dir_found {
+        content_picker::ContentDirState::Ready
+    } else {
+        content_picker::ContentDirState::Picking
+    });
+    app.init_state::<LoadState>();
     app.add_plugins((
+        settings::plugin,
+        content_picker::plugin,
         magicka_assets::plugin,
         components_basic::plugin,
         magicka_level_model::plugin,
         camera::plugin,
         character::plugin,
+        dialog::plugin,
         item::plugin,
         script_triggers::plugin,
         gameplay::plugin,
@@ -70,10 +84,21 @@ fn main() -> AppExit {
 }
 
 fn setup_scenes(app: &mut App) {
+    app.init_resource::<scene::VisitedScenes>();
     // Check for transition
-    app.add_systems(PreUpdate, scene::change_to_next_scene);
-    // Load into a scene on startup
-    app.add_systems(Startup, |mut commands: Commands| {
+    app.add_systems(
+        PreUpdate,
+        (scene::change_to_next_scene, scene::spawn_pending_scenes).chain(),
+    );
+    app.add_systems(OnEnter(LoadState::Loading), scene::spawn_loading_screen);
+    app.add_systems(
+        PostUpdate,
+        scene::update_loading_screen.run_if(in_state(LoadState::Loading)),
+    );
+    app.add_systems(PostUpdate, scene::apply_scene_atmosphere);
+    app.add_systems(Update, scene::restart_from_checkpoint);
+    // Load into a scene on startup, or once Magicka has been located
+    app.add_systems(OnEnter(content_picker::ContentDirState::Ready), |mut commands: Commands| {
         commands.queue(crate::scene::StartScene {
             level: "WizardCastle".to_owned(),
             scene: "wc_s1".to_owned(),
@@ -100,7 +125,11 @@ fn setup_scenes(app: &mut App) {
                     spawn_point: Some("start".to_owned()),
                 });
             })
-            .run_if(input_just_pressed(input).and_then(input_pressed(KeyCode::AltLeft))),
+            .run_if(
+                in_state(content_picker::ContentDirState::Ready)
+                    .and_then(input_just_pressed(input))
+                    .and_then(input_pressed(KeyCode::AltLeft)),
+            ),
         );
     }
 
@@ -123,10 +152,11 @@ fn setup_scenes(app: &mut App) {
     // "Levels/EndofWorld/ew_s4.xnb", // Contains LavaEffect
 }
 
+/// Whether a scene is being loaded, while a loading screen is shown.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, States, Debug)]
 pub(crate) enum LoadState {
-    #[default]
     Loading,
+    #[default]
     Loaded,
 }
 
@@ -1,11 +1,28 @@
-use std::{fs::File, io::BufReader, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
-use bevy::prelude::*;
-use typed_path::{PlatformPath, PlatformPathBuf};
+use bevy::{
+    asset::{RecursiveDependencyLoadState, UntypedAssetId},
+    core_pipeline::Skybox,
+    platform::collections::HashSet,
+    prelude::*,
+    render::render_resource::TextureViewDimension,
+};
+use typed_path::PlatformPathBuf;
 
 use crate::{
-    magicka_scene::{SceneConfig, read_scene},
-    script_triggers::{ActionState, AutoEvaluateCriterion, TriggerAutoEvaluate},
+    LoadState, PlayerControlled,
+    camera::{CameraScript, PrimaryView},
+    character::{FOLLOWER_TEMPLATE, PLAYER_TEMPLATE, template_asset_path},
+    gameplay::damage::Health,
+    magicka_assets::{
+        character_template::CharacterTemplate, level::MagickaLevel, scene::MagickaScene,
+    },
+    magicka_level_model::light::MAGICKA_TO_LUMINANCE,
+    magicka_scene::SceneConfig,
+    script_triggers::{
+        ActionState, AutoEvaluateCriterion, TriggerAutoEvaluate,
+        condition::magick_cast::CastMagicks, variables::SceneVariables,
+    },
 };
 
 #[derive(Component, Debug, Reflect)]
@@ -14,6 +31,71 @@ pub struct Scene {
     pub scene: String,
 }
 
+/// When a scene was spawned, in [`Time::elapsed`].
+#[derive(Component, Debug, Reflect)]
+pub struct SceneStarted(pub Duration);
+
+/// The scenes that have been left, by lowercase level and scene name.
+#[derive(Resource, Default, Debug)]
+pub struct VisitedScenes(HashSet<(String, String)>);
+
+impl VisitedScenes {
+    pub fn contains(&self, level: &str, scene: &str) -> bool {
+        self.0
+            .contains(&(level.to_ascii_lowercase(), scene.to_ascii_lowercase()))
+    }
+
+    fn record(&mut self, level: &str, scene: &str) {
+        self.0
+            .insert((level.to_ascii_lowercase(), scene.to_ascii_lowercase()));
+    }
+}
+
+/// Where players start over once they have all died, saved by a checkpoint trigger action.
+#[derive(Resource, Clone, Debug)]
+pub struct Checkpoint {
+    pub level: String,
+    pub scene: String,
+    /// Defaults to `start`.
+    pub spawn_point: Option<String>,
+}
+
+/// How long after the last player dies the checkpoint is loaded, in seconds.
+const CHECKPOINT_RESTART_DELAY: f32 = 3.;
+
+/// Loads the [`Checkpoint`] once every player has been dead for a while.
+pub fn restart_from_checkpoint(
+    players: Query<&Health, With<PlayerControlled>>,
+    checkpoint: Option<Res<Checkpoint>>,
+    time: Res<Time>,
+    mut dead_time: Local<f32>,
+    mut commands: Commands,
+) {
+    if players.is_empty() || players.iter().any(Health::alive) {
+        *dead_time = 0.;
+        return;
+    }
+    let Some(checkpoint) = checkpoint else {
+        return;
+    };
+    *dead_time += time.delta_secs();
+    if *dead_time < CHECKPOINT_RESTART_DELAY {
+        return;
+    }
+    *dead_time = 0.;
+    commands.queue(ChangeScene {
+        level: checkpoint.level.clone(),
+        scene: checkpoint.scene.clone(),
+        spawn_players: true,
+        spawn_point: Some(
+            checkpoint
+                .spawn_point
+                .clone()
+                .unwrap_or_else(|| "start".to_owned()),
+        ),
+    });
+}
+
 pub fn queue_delayed_scene_change(In(change_command): In<ChangeScene>, world: &mut World) {
     world.insert_resource(NextScene { change_command });
 }
@@ -45,11 +127,17 @@ impl Command for ChangeScene {
 
     fn apply(self, world: &mut World) -> Result {
         let existing_scenes: Vec<_> = world
-            .query_filtered::<Entity, With<Scene>>()
+            .query::<(Entity, &Scene)>()
             .iter(world)
+            .map(|(entity, scene)| (entity, scene.level.clone(), scene.scene.clone()))
             .collect();
-        for scene in existing_scenes {
-            world.despawn(scene);
+        for (scene_entity, level, scene) in existing_scenes {
+            world.resource_mut::<VisitedScenes>().record(&level, &scene);
+            world.despawn(scene_entity);
+        }
+        world.resource_mut::<CastMagicks>().0.clear();
+        for mut camera_script in world.query::<&mut CameraScript>().iter_mut(world) {
+            *camera_script = default();
         }
         let start_command = StartScene {
             level: self.level,
@@ -74,22 +162,30 @@ impl Command for StartScene {
     type Out = Result;
 
     fn apply(self, world: &mut World) -> Result {
-        use std::ffi::OsStr;
-
-        // Locate the file containing scene config
         let mut scene_content_path = ["Levels", &self.level, &self.scene]
             .iter()
             .collect::<PlatformPathBuf>();
         scene_content_path.set_extension("xml");
 
-        // Read in the scene config
-        let scene_full_path = crate::magicka_assets::content_root()
-            .join_checked(&scene_content_path)
-            .unwrap();
-        let file = BufReader::new(File::open(scene_full_path.as_ref() as &OsStr).unwrap());
-        let scene = read_scene(file).unwrap();
+        world
+            .resource_mut::<SceneVariables>()
+            .enter_level(&self.level);
+
+        // Load everything in the background, the scene is spawned once it's all ready
+        let assets = world.resource::<AssetServer>();
+        let scene = assets.load(crate::magicka_assets::source::asset_path(&scene_content_path));
+        let templates: Vec<Handle<CharacterTemplate>> = if self.spawn_players {
+            [PLAYER_TEMPLATE, FOLLOWER_TEMPLATE]
+                .map(|name| assets.load(template_asset_path(name)))
+                .into()
+        } else {
+            Vec::new()
+        };
+        world
+            .resource_mut::<NextState<LoadState>>()
+            .set(LoadState::Loading);
 
-        // Spawn an entity to hold the scene
+        // Spawn an entity to hold the scene, which is filled in once its assets are loaded
         let scene_entity = world
             .spawn((
                 Name::new(format!("Scene - {} of level {}", &self.scene, &self.level)),
@@ -99,42 +195,322 @@ impl Command for StartScene {
                 },
                 Transform::default(),
                 Visibility::default(),
+                PendingScene {
+                    start: self,
+                    scene,
+                    templates,
+                },
             ))
             .id();
 
-        let spawn_result =
-            self.spawn_scene_into(world, scene_entity, scene, scene_content_path.as_path());
-        if spawn_result.is_err() {
-            world.despawn(scene_entity);
-        }
-        // TODO: Turn into actual result
-        spawn_result.unwrap();
+        // Skip waiting a frame when everything is already loaded
+        spawn_pending_scene(world, scene_entity);
         Ok(())
     }
 }
 
+/// Keeps the current scene's assets loaded, so changing to another scene in the same level doesn't
+/// load the level again.
+#[derive(Resource, Debug)]
+struct CurrentScene(Handle<MagickaScene>);
+
+/// The background when no scene gives a sky.
+pub const DEFAULT_SKY_COLOR: Color = Color::BLACK;
+
+/// The sky and fog of a spawned scene, shown by every [`PrimaryView`] camera.
+#[derive(Component, Debug)]
+pub struct SceneAtmosphere {
+    /// A cube map drawn as a [`Skybox`], over the sky color.
+    pub sky_texture: Option<Handle<Image>>,
+    pub sky_color: Color,
+    pub sky_brightness: f32,
+    pub fog: Option<DistanceFog>,
+}
+
+/// Applies the current [`SceneAtmosphere`] to the cameras, or clears it once its scene is gone.
+pub fn apply_scene_atmosphere(
+    mut commands: Commands,
+    atmospheres: Query<Ref<SceneAtmosphere>>,
+    mut removed_atmospheres: RemovedComponents<SceneAtmosphere>,
+    cameras: Query<Entity, With<PrimaryView>>,
+    added_cameras: Query<(), Added<PrimaryView>>,
+    images: Res<Assets<Image>>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let removed = removed_atmospheres.read().count() > 0;
+    if !removed && added_cameras.is_empty() && !atmospheres.iter().any(|a| a.is_changed()) {
+        return;
+    }
+
+    let atmosphere = atmospheres.iter().next();
+    clear_color.0 = atmosphere.map_or(DEFAULT_SKY_COLOR, |a| a.sky_color);
+    let skybox = atmosphere.and_then(|atmosphere| {
+        let image = atmosphere.sky_texture.clone()?;
+        let is_cube_map = images.get(&image).is_some_and(|image| {
+            image
+                .texture_view_descriptor
+                .as_ref()
+                .is_some_and(|view| view.dimension == Some(TextureViewDimension::Cube))
+        });
+        if !is_cube_map {
+            warn!("Scene sky texture is not a cube map, showing only the sky color");
+            return None;
+        }
+        Some(Skybox {
+            image: Some(image),
+            brightness: atmosphere.sky_brightness,
+            ..default()
+        })
+    });
+    let fog = atmosphere.and_then(|a| a.fog.clone());
+
+    for camera in &cameras {
+        let mut camera_commands = commands.entity(camera);
+        match &skybox {
+            Some(skybox) => camera_commands.insert(skybox.clone()),
+            None => camera_commands.remove::<Skybox>(),
+        };
+        match &fog {
+            Some(fog) => camera_commands.insert(fog.clone()),
+            None => camera_commands.remove::<DistanceFog>(),
+        };
+    }
+}
+
+/// A scene waiting for its assets to load before it is spawned.
+#[derive(Component, Debug)]
+pub struct PendingScene {
+    start: StartScene,
+    scene: Handle<MagickaScene>,
+    /// The player character templates, if players are spawned.
+    templates: Vec<Handle<CharacterTemplate>>,
+}
+
+impl PendingScene {
+    /// How many of the assets known to be needed so far have finished loading, and how many
+    /// there are. More are discovered as the scene and level load.
+    fn progress(
+        &self,
+        assets: &AssetServer,
+        scenes: &Assets<MagickaScene>,
+        levels: &Assets<MagickaLevel>,
+    ) -> (usize, usize) {
+        let mut ids: Vec<UntypedAssetId> = vec![self.scene.id().untyped()];
+        ids.extend(self.templates.iter().map(|t| t.id().untyped()));
+        if let Some(scene) = scenes.get(&self.scene) {
+            ids.push(scene.level.id().untyped());
+            ids.extend(scene.sky_texture.iter().map(|t| t.id().untyped()));
+            ids.extend(scene.templates.values().map(|t| t.id().untyped()));
+            ids.extend(scene.items.values().map(|i| i.id().untyped()));
+            if let Some(level) = levels.get(&scene.level) {
+                ids.extend(level.effects.values().map(|e| e.id().untyped()));
+            }
+        }
+        let done = ids
+            .iter()
+            .filter(|&&id| {
+                matches!(
+                    assets.recursive_dependency_load_state(id),
+                    RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed(_)
+                )
+            })
+            .count();
+        (done, ids.len())
+    }
+}
+
+pub fn spawn_pending_scenes(world: &mut World) {
+    let pending: Vec<_> = world
+        .query_filtered::<Entity, With<PendingScene>>()
+        .iter(world)
+        .collect();
+    for scene_entity in pending {
+        spawn_pending_scene(world, scene_entity);
+    }
+}
+
+fn spawn_pending_scene(world: &mut World, scene_entity: Entity) {
+    let Some(pending) = world.get::<PendingScene>(scene_entity) else {
+        return;
+    };
+    let assets = world.resource::<AssetServer>();
+    let is_loading = |state| {
+        matches!(
+            state,
+            RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading
+        )
+    };
+    if pending
+        .templates
+        .iter()
+        .any(|template| is_loading(assets.recursive_dependency_load_state(template)))
+    {
+        return;
+    }
+    match assets.recursive_dependency_load_state(&pending.scene) {
+        RecursiveDependencyLoadState::NotLoaded | RecursiveDependencyLoadState::Loading => {
+            return;
+        }
+        RecursiveDependencyLoadState::Loaded => {}
+        RecursiveDependencyLoadState::Failed(error) => {
+            let level = world
+                .resource::<Assets<MagickaScene>>()
+                .get(&pending.scene)
+                .map(|scene| &scene.level);
+            if let Some(level) = level
+                && world.resource::<Assets<MagickaLevel>>().contains(level)
+            {
+                // Failures are reported before everything else has finished loading
+                if is_loading(assets.recursive_dependency_load_state(level)) {
+                    return;
+                }
+                // Only some visual effects, character templates or items are missing, spawn without
+                // them
+                warn!(
+                    "Scene {:?} loaded with missing dependencies: {error}",
+                    pending.scene.path()
+                );
+            } else {
+                error!(
+                    "Loading scene {:?} failed: {error}",
+                    pending.scene.path()
+                );
+                world.despawn(scene_entity);
+                world
+                    .resource_mut::<NextState<LoadState>>()
+                    .set(LoadState::Loaded);
+                return;
+            }
+        }
+    }
+
+    let PendingScene {
+        start,
+        scene,
+        templates: _,
+    } = world
+        .entity_mut(scene_entity)
+        .take::<PendingScene>()
+        .unwrap();
+    let magicka_scene = world.resource::<Assets<MagickaScene>>().get(&scene).unwrap();
+    let config = magicka_scene.config.clone();
+    let level = magicka_scene.level.clone();
+    let sky_texture = magicka_scene.sky_texture.clone();
+    world.insert_resource(CurrentScene(scene));
+    world
+        .resource_mut::<NextState<LoadState>>()
+        .set(LoadState::Loaded);
+
+    let spawn_result = start.spawn_scene_into(world, scene_entity, config, level, sky_texture);
+    if spawn_result.is_err() {
+        world.despawn(scene_entity);
+    }
+    // TODO: Turn into actual result
+    spawn_result.unwrap();
+}
+
+#[derive(Component)]
+pub struct LoadingProgressText;
+
+#[derive(Component)]
+pub struct LoadingProgressBar;
+
+pub fn spawn_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Loading Screen"),
+        DespawnOnExit(LoadState::Loading),
+        Node {
+            width: percent(100),
+            height: percent(100),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: px(12),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        children![
+            (LoadingProgressText, Text::new("Loading…")),
+            (
+                Node {
+                    width: px(320),
+                    height: px(8),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                children![(
+                    LoadingProgressBar,
+                    Node {
+                        width: percent(0),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.8, 0.8, 0.8)),
+                )],
+            ),
+        ],
+    ));
+}
+
+pub fn update_loading_screen(
+    pending_scenes: Query<(&PendingScene, &Scene)>,
+    assets: Res<AssetServer>,
+    scenes: Res<Assets<MagickaScene>>,
+    levels: Res<Assets<MagickaLevel>>,
+    mut text: Single<&mut Text, With<LoadingProgressText>>,
+    mut bar: Single<&mut Node, With<LoadingProgressBar>>,
+) {
+    let Some((pending, scene)) = pending_scenes.iter().next() else {
+        return;
+    };
+    let (done, total) = pending.progress(&assets, &scenes, &levels);
+    text.0 = format!(
+        "Loading {} of level {}… ({done}/{total})",
+        scene.scene, scene.level
+    );
+    bar.width = percent(100. * done as f32 / total.max(1) as f32);
+}
+
 impl StartScene {
     fn spawn_scene_into(
         self,
         world: &mut World,
         parent_entity: Entity,
         scene: SceneConfig,
-        scene_path: &PlatformPath,
+        level: Handle<MagickaLevel>,
+        sky_texture: Option<Handle<Image>>,
     ) -> Result<(), ()> {
         // Spawn the scene's level model
-        let level_model = scene.model.as_ref().unwrap();
-        let mut level_model_path = scene_path.to_owned();
-        level_model_path.pop();
-        level_model_path.push(level_model);
-        level_model_path.set_extension("xnb");
         let level_entity = world
             .run_system_cached_with::<_, Result<Entity>, _, _>(
                 crate::magicka_level_model::spawn_level,
-                level_model_path.as_path(),
+                level,
             )
             .unwrap()
             .unwrap();
         world.entity_mut(parent_entity).add_child(level_entity);
+        let started = world.resource::<Time>().elapsed();
+        world.entity_mut(parent_entity).insert(SceneStarted(started));
+
+        let sky = scene.sky.unwrap_or_default();
+        world.entity_mut(parent_entity).insert(SceneAtmosphere {
+            sky_texture,
+            sky_color: sky
+                .color
+                .map_or(DEFAULT_SKY_COLOR, |[r, g, b]| Color::srgb(r, g, b)),
+            sky_brightness: MAGICKA_TO_LUMINANCE,
+            fog: scene.fog.map(|fog| {
+                let [r, g, b] = fog.color;
+                DistanceFog {
+                    color: Color::srgb(r, g, b),
+                    falloff: FogFalloff::Linear {
+                        start: fog.start,
+                        end: fog.end,
+                    },
+                    ..default()
+                }
+            }),
+        });
 
         if self.spawn_players
             && let Some(spawn_point) = self.spawn_point
@@ -166,6 +542,8 @@ impl StartScene {
                         .into_iter()
                         .map(|d| (d, ActionState::default()))
                         .collect(),
+                    random_groups: Arc::new(trigger.random_groups),
+                    looping: false,
                 });
                 if let Some(name) = trigger.id {
                     trigger_commands.insert(Name::new(name.clone()));
@@ -2,9 +2,11 @@ pub mod condition;
 
 pub mod action;
 
+pub mod variables;
+
 use std::sync::Arc;
 
-use crate::magicka_scene;
+use crate::magicka_scene::{self, ActionGroup};
 use bevy::{ecs::query::QueryEntityError, prelude::*, time::Stopwatch};
 
 #[derive(Component, Debug)]
@@ -13,6 +15,41 @@ pub struct Trigger {
     // /// Nested list represents [ [X and Y] or [Z and W] ]
     pub conditions: Arc<Vec<Vec<magicka_scene::TriggerCondition>>>,
     pub actions: Vec<(magicka_scene::TriggerAction, ActionState)>,
+    /// The weights of the choices of each [`ActionGroup::Random`] group.
+    pub random_groups: Arc<Vec<Vec<f32>>>,
+    /// Whether the [`ActionGroup::Looped`] actions repeat once they have all run.
+    pub looping: bool,
+}
+
+impl Trigger {
+    /// Stops repeating the looped actions, including any waiting for their delay.
+    pub fn stop_loop(&mut self) {
+        self.looping = false;
+        for (action, state) in &mut self.actions {
+            if action.group == ActionGroup::Looped {
+                state.pending = None;
+            }
+        }
+    }
+}
+
+/// Picks an index with a chance proportional to its weight.
+fn pick_weighted(weights: &[f32], rng: &mut impl rand::Rng) -> Option<usize> {
+    use rand::distr::uniform::SampleRange as _;
+
+    let total: f32 = weights.iter().map(|weight| weight.max(0.)).sum();
+    if total <= 0. {
+        return None;
+    }
+    let mut pick = (0.0..total).sample_single(rng).unwrap();
+    for (index, weight) in weights.iter().enumerate() {
+        pick -= weight.max(0.);
+        if pick < 0. {
+            return Some(index);
+        }
+    }
+    // Only reachable through rounding
+    weights.iter().rposition(|weight| *weight > 0.)
 }
 
 #[derive(Debug, Default)]
@@ -20,6 +57,10 @@ pub struct ActionState {
     pub pending: Option<(usize, Stopwatch)>,
 }
 
+/// The trigger isn't evaluated until a trigger action enables it again.
+#[derive(Component, Default, Debug)]
+pub struct TriggerDisabled;
+
 #[derive(Component, Debug)]
 pub struct TriggerAutoEvaluate {
     pub criterion: AutoEvaluateCriterion,
@@ -32,6 +73,9 @@ pub enum AutoEvaluateCriterion {
 }
 
 pub fn plugin(app: &mut App) {
+    app.add_plugins(condition::plugin);
+    app.init_resource::<variables::SceneVariables>();
+    app.register_type::<variables::SceneVariables>();
     app.add_systems(Update, evaluate_auto_triggers);
 }
 
@@ -75,9 +119,17 @@ fn evaluate_auto_triggers(
 }
 
 fn evaluate_trigger(In(trigger): In<Entity>, world: &mut World) -> Result {
+    if world.get::<TriggerDisabled>(trigger).is_some() {
+        return Ok(());
+    }
     let should_execute = world.run_system_cached_with(condition::conditions_met, trigger)?;
     if should_execute {
         world.run_system_cached_with::<_, Result, _, _>(execute_trigger, trigger)??;
+    } else if let Some(mut trigger_data) = world.get_mut::<Trigger>(trigger) {
+        // Looped actions only repeat while their trigger's conditions hold
+        if trigger_data.looping {
+            trigger_data.stop_loop();
+        }
     }
     Ok(())
 }
@@ -102,11 +154,35 @@ pub(crate) fn execute_trigger(
         }
     }
 
-    for action in &mut trigger_data.actions {
-        if let Some((count, _)) = action.1.pending.as_mut() {
+    let mut rng: rand::rngs::SmallRng = rand::make_rng();
+    let choices: Vec<_> = trigger_data
+        .random_groups
+        .iter()
+        .map(|weights| pick_weighted(weights, &mut rng))
+        .collect();
+
+    let trigger_data = &mut *trigger_data;
+    for (action, state) in &mut trigger_data.actions {
+        match action.group {
+            ActionGroup::Always => {}
+            ActionGroup::Random { group, choice } => {
+                if choices.get(group) != Some(&Some(choice)) {
+                    continue;
+                }
+            }
+            ActionGroup::Looped => {
+                // Executing again doesn't restart a running loop
+                trigger_data.looping = true;
+                if state.pending.is_none() {
+                    state.pending = Some((0, Stopwatch::new()));
+                }
+                continue;
+            }
+        }
+        if let Some((count, _)) = state.pending.as_mut() {
             *count = count.saturating_add(1);
         } else {
-            action.1.pending = Some((0, Stopwatch::new()));
+            state.pending = Some((0, Stopwatch::new()));
         }
     }
 
@@ -145,5 +221,59 @@ fn run_trigger_actions(
             )??;
         }
     }
+
+    // Restart the loop once all of its actions have run
+    let Ok((mut trigger_data, _name)) = q_triggers.get_mut(world, trigger) else {
+        // An action removed the trigger
+        return Ok(());
+    };
+    let mut looped = trigger_data
+        .actions
+        .iter()
+        .filter(|(action, _)| action.group == ActionGroup::Looped)
+        .peekable();
+    let loop_finished = looped.peek().is_some() && looped.all(|(_, state)| state.pending.is_none());
+    if trigger_data.looping && loop_finished {
+        for (action, state) in &mut trigger_data.actions {
+            if action.group == ActionGroup::Looped {
+                state.pending = Some((0, Stopwatch::new()));
+            }
+        }
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::pick_weighted;
+    use rand::{SeedableRng as _, rngs::SmallRng};
+
+    #[test]
+    fn pick_weighted_without_positive_weights() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        assert_eq!(pick_weighted(&[], &mut rng), None);
+        assert_eq!(pick_weighted(&[0., 0.], &mut rng), None);
+        assert_eq!(pick_weighted(&[-1., 0.], &mut rng), None);
+    }
+
+    #[test]
+    fn pick_weighted_skips_zero_and_negative_weights() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            assert_eq!(pick_weighted(&[0., -2., 1., 0.], &mut rng), Some(2));
+        }
+    }
+
+    #[test]
+    fn pick_weighted_uneven_weights() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[pick_weighted(&[1., 3., 6.], &mut rng).unwrap()] += 1;
+        }
+        // Within a few standard deviations of 1000, 3000 and 6000
+        assert!((850..1150).contains(&counts[0]), "{counts:?}");
+        assert!((2750..3250).contains(&counts[1]), "{counts:?}");
+        assert!((5750..6250).contains(&counts[2]), "{counts:?}");
+    }
+}
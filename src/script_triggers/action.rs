@@ -1,21 +1,58 @@
+pub mod ai;
 pub mod avatar_move;
+pub mod camera;
 pub mod change_scene;
+pub mod checkpoint;
+pub mod damage;
+pub mod end_level;
 pub mod execute_trigger_action;
+pub mod kill;
+pub mod light;
 pub mod play_animation;
+pub mod set_variable;
+pub mod show_text;
+pub mod sound;
 pub mod spawn_character;
+pub mod spawn_item;
+pub mod switch_trigger;
+pub mod teleport;
 
-use crate::{magicka_scene::TriggerAction, script_triggers::Trigger};
-use bevy::prelude::*;
+use crate::{
+    character::Character,
+    magicka_level_model::{Locator, TriggerArea},
+    magicka_scene::{SceneError, TriggerAction},
+    script_triggers::Trigger,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
 use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TriggerActionBehavior {
     ChangeScene(Arc<change_scene::ChangeScene>),
     SpawnCharacter(Arc<spawn_character::SpawnCharacter>),
     ExecuteTrigger(Arc<execute_trigger_action::ExecuteTrigger>),
     PlayAnimation(Arc<play_animation::PlayAnimation>),
     AvatarMove(Arc<avatar_move::AvatarMove>),
-    Unknown,
+    Kill(Arc<kill::Kill>),
+    Damage(Arc<damage::Damage>),
+    SwitchTrigger(Arc<switch_trigger::SwitchTrigger>),
+    Teleport(Arc<teleport::Teleport>),
+    SpawnItem(Arc<spawn_item::SpawnItem>),
+    SpawnMagick(Arc<spawn_item::SpawnMagick>),
+    CameraMove(Arc<camera::CameraMove>),
+    CameraShake(Arc<camera::CameraShake>),
+    CameraLock(Arc<camera::CameraLock>),
+    ShowText(Arc<show_text::ShowText>),
+    PlaySound(Arc<sound::PlaySound>),
+    PlayMusic(Arc<sound::PlayMusic>),
+    SetAiTarget(Arc<ai::SetAiTarget>),
+    SetAiBehaviour(Arc<ai::SetAiBehaviour>),
+    SwitchLight(Arc<light::SwitchLight>),
+    Checkpoint(Arc<checkpoint::Checkpoint>),
+    EndLevel(Arc<end_level::EndLevel>),
+    SetVariable(Arc<set_variable::SetVariable>),
+    /// An action type that isn't supported, by its element name.
+    Unknown(String),
 }
 
 pub(super) fn execute_trigger_action(
@@ -57,7 +94,79 @@ pub(super) fn execute_trigger_action(
         AvatarMove(handle) => {
             world.run_system_cached_with(avatar_move::execute_avatar_move, handle.clone())?;
         }
-        Unknown => {
+        Kill(handle) => {
+            world.run_system_cached_with(kill::execute_kill, handle.clone())?;
+        }
+        Damage(handle) => {
+            world.run_system_cached_with(damage::execute_damage, handle.clone())?;
+        }
+        SwitchTrigger(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                switch_trigger::execute_switch_trigger,
+                handle.clone(),
+            )??;
+        }
+        Teleport(handle) => {
+            world.run_system_cached_with(teleport::execute_teleport, handle.clone())?;
+        }
+        SpawnItem(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                spawn_item::execute_spawn_item,
+                handle.clone(),
+            )??;
+        }
+        SpawnMagick(handle) => {
+            world.run_system_cached_with(spawn_item::execute_spawn_magick, handle.clone())?;
+        }
+        CameraMove(handle) => {
+            world.run_system_cached_with(camera::execute_camera_move, handle.clone())?;
+        }
+        CameraShake(handle) => {
+            world.run_system_cached_with(camera::execute_camera_shake, handle.clone())?;
+        }
+        CameraLock(handle) => {
+            world.run_system_cached_with(camera::execute_camera_lock, handle.clone())?;
+        }
+        ShowText(handle) => {
+            world.run_system_cached_with(show_text::execute_show_text, handle.clone())?;
+        }
+        PlaySound(handle) => {
+            world.run_system_cached_with(sound::execute_play_sound, handle.clone())?;
+        }
+        PlayMusic(handle) => {
+            world.run_system_cached_with(sound::execute_play_music, handle.clone())?;
+        }
+        SetAiTarget(handle) => {
+            world.run_system_cached_with(ai::execute_set_ai_target, handle.clone())?;
+        }
+        SetAiBehaviour(handle) => {
+            world.run_system_cached_with(ai::execute_set_ai_behaviour, handle.clone())?;
+        }
+        SwitchLight(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                light::execute_switch_light,
+                handle.clone(),
+            )??;
+        }
+        Checkpoint(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                checkpoint::execute_checkpoint,
+                handle.clone(),
+            )??;
+        }
+        EndLevel(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                end_level::execute_end_level,
+                handle.clone(),
+            )??;
+        }
+        SetVariable(handle) => {
+            world.run_system_cached_with::<_, Result, _, _>(
+                set_variable::execute_set_variable,
+                handle.clone(),
+            )??;
+        }
+        Unknown(_) => {
             debug!("Unhandled executed action of trigger {name:?} ({trigger}): {behavior:?}");
         }
     }
@@ -68,30 +177,493 @@ pub fn read_behavior(
     parser: &mut xml::EventReader<impl std::io::Read>,
     name: xml::name::OwnedName,
     attributes: Vec<xml::attribute::OwnedAttribute>,
-) -> Result<TriggerActionBehavior, crate::magicka_scene::SceneError> {
+) -> Result<TriggerActionBehavior, SceneError> {
     use TriggerActionBehavior::*;
-    let behavior = if name.local_name.eq_ignore_ascii_case("changescene") {
-        parser.skip()?;
-        ChangeScene(Arc::new(change_scene::from_xml(attributes).unwrap()))
-    } else if name.local_name.eq_ignore_ascii_case("spawn") {
-        parser.skip()?;
-        SpawnCharacter(Arc::new(spawn_character::from_xml(attributes).unwrap()))
-    } else if name.local_name.eq_ignore_ascii_case("executetrigger") {
-        parser.skip()?;
+    let is = |n: &str| name.local_name.eq_ignore_ascii_case(n);
+    let invalid = |()| SceneError::InvalidAction(name.local_name.clone());
+    if is("avatarmove") {
+        let action = avatar_move::from_xml(attributes, parser).map_err(invalid)?;
+        return Ok(AvatarMove(Arc::new(action)));
+    }
+    // The rest have no content
+    parser.skip()?;
+    let behavior = if is("changescene") {
+        ChangeScene(Arc::new(
+            change_scene::from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("spawn") {
+        SpawnCharacter(Arc::new(
+            spawn_character::from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("executetrigger") {
         ExecuteTrigger(Arc::new(
-            execute_trigger_action::from_xml(attributes).unwrap(),
+            execute_trigger_action::from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("playanimation") {
+        PlayAnimation(Arc::new(
+            play_animation::from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("kill") {
+        Kill(Arc::new(kill::from_xml(attributes).map_err(invalid)?))
+    } else if is("damage") {
+        Damage(Arc::new(
+            damage::from_xml(attributes, false).map_err(invalid)?,
+        ))
+    } else if is("heal") {
+        Damage(Arc::new(
+            damage::from_xml(attributes, true).map_err(invalid)?,
+        ))
+    } else if is("enabletrigger") {
+        SwitchTrigger(Arc::new(
+            switch_trigger::from_xml(attributes, true).map_err(invalid)?,
+        ))
+    } else if is("disabletrigger") {
+        SwitchTrigger(Arc::new(
+            switch_trigger::from_xml(attributes, false).map_err(invalid)?,
+        ))
+    } else if is("teleport") {
+        Teleport(Arc::new(teleport::from_xml(attributes).map_err(invalid)?))
+    } else if is("spawnitem") {
+        SpawnItem(Arc::new(
+            spawn_item::item_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("spawnmagick") {
+        SpawnMagick(Arc::new(
+            spawn_item::magick_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("cameramove") {
+        CameraMove(Arc::new(
+            camera::move_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("camerashake") {
+        CameraShake(Arc::new(
+            camera::shake_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("cameralock") {
+        CameraLock(Arc::new(
+            camera::lock_from_xml(attributes, true).map_err(invalid)?,
+        ))
+    } else if is("camerarelease") {
+        CameraLock(Arc::new(
+            camera::lock_from_xml(attributes, false).map_err(invalid)?,
+        ))
+    } else if is("showtext") {
+        ShowText(Arc::new(show_text::from_xml(attributes).map_err(invalid)?))
+    } else if is("playsound") {
+        PlaySound(Arc::new(
+            sound::sound_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("playmusic") {
+        PlayMusic(Arc::new(
+            sound::music_from_xml(attributes, true).map_err(invalid)?,
+        ))
+    } else if is("stopmusic") {
+        PlayMusic(Arc::new(
+            sound::music_from_xml(attributes, false).map_err(invalid)?,
+        ))
+    } else if is("setaitarget") {
+        SetAiTarget(Arc::new(ai::target_from_xml(attributes).map_err(invalid)?))
+    } else if is("setaibehaviour") {
+        SetAiBehaviour(Arc::new(
+            ai::behaviour_from_xml(attributes).map_err(invalid)?,
+        ))
+    } else if is("switchlight") {
+        SwitchLight(Arc::new(
+            light::from_xml(attributes, None).map_err(invalid)?,
+        ))
+    } else if is("lighton") {
+        SwitchLight(Arc::new(
+            light::from_xml(attributes, Some(true)).map_err(invalid)?,
+        ))
+    } else if is("lightoff") {
+        SwitchLight(Arc::new(
+            light::from_xml(attributes, Some(false)).map_err(invalid)?,
+        ))
+    } else if is("checkpoint") {
+        Checkpoint(Arc::new(checkpoint::from_xml(attributes).map_err(invalid)?))
+    } else if is("endlevel") {
+        EndLevel(Arc::new(end_level::from_xml(attributes).map_err(invalid)?))
+    } else if is("setvariable") {
+        SetVariable(Arc::new(
+            set_variable::from_xml(attributes, false).map_err(invalid)?,
+        ))
+    } else if is("addvariable") {
+        SetVariable(Arc::new(
+            set_variable::from_xml(attributes, true).map_err(invalid)?,
         ))
-    } else if name.local_name.eq_ignore_ascii_case("playanimation") {
-        parser.skip()?;
-        PlayAnimation(Arc::new(play_animation::from_xml(attributes).unwrap()))
-    } else if name.local_name.eq_ignore_ascii_case("avatarmove") {
-        AvatarMove(Arc::new(avatar_move::from_xml(attributes, parser).unwrap()))
     } else {
         warn!("Unhandled scene trigger action type {:?}", name.local_name);
         #[cfg(test)]
         eprintln!("Unhandled scene trigger action type {:?}", name.local_name);
-        parser.skip()?;
-        TriggerActionBehavior::Unknown
+        Unknown(name.local_name)
     };
     Ok(behavior)
 }
+
+/// The characters an action applies to, by the `id` they were spawned with or their `type`.
+#[derive(Clone, Debug)]
+pub enum CharacterSelector {
+    Id(String),
+    Type(String),
+}
+
+impl CharacterSelector {
+    pub fn matches(&self, name: Option<&Name>, character: &Character) -> bool {
+        match self {
+            Self::Id(id) => name.is_some_and(|name| name.eq_ignore_ascii_case(id)),
+            Self::Type(type_name) => character.type_name.eq_ignore_ascii_case(type_name),
+        }
+    }
+
+    /// Reads an `id` or `type` attribute into `selector`, returning whether it was either.
+    pub(crate) fn read_attribute(
+        selector: &mut Option<Self>,
+        attribute: &xml::attribute::OwnedAttribute,
+    ) -> bool {
+        let name = &attribute.name.local_name;
+        if name.eq_ignore_ascii_case("id") {
+            *selector = Some(Self::Id(attribute.value.clone()));
+        } else if name.eq_ignore_ascii_case("type") {
+            // An ID is more specific
+            if !matches!(selector, Some(Self::Id(_))) {
+                *selector = Some(Self::Type(attribute.value.clone()));
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// Finds the locators and trigger areas actions refer to by name.
+#[derive(SystemParam)]
+pub struct NamedLocations<'w, 's> {
+    locators: Query<'w, 's, (Entity, &'static Name), With<Locator>>,
+    areas: Query<'w, 's, (Entity, &'static Name), With<TriggerArea>>,
+    transform_helper: TransformHelper<'w, 's>,
+}
+
+/// A locator or trigger area found by [`NamedLocations`].
+#[derive(Clone, Copy, Debug)]
+pub struct NamedLocation {
+    pub transform: GlobalTransform,
+    /// Whether this is a trigger area, which spans the unit cube in its local space.
+    pub area: bool,
+}
+
+impl NamedLocations<'_, '_> {
+    pub fn find(&self, name: &str) -> Option<NamedLocation> {
+        let (entity, area) = if let Some((locator, _name)) = self
+            .locators
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        {
+            (locator, false)
+        } else if let Some((area, _name)) = self
+            .areas
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        {
+            (area, true)
+        } else {
+            return None;
+        };
+        let transform = self
+            .transform_helper
+            .compute_global_transform(entity)
+            .ok()?;
+        Some(NamedLocation { transform, area })
+    }
+}
+
+impl NamedLocation {
+    /// The locator's position, or a random point in the area.
+    pub fn random_point(&self) -> Vec3 {
+        if self.area {
+            use rand::distr::uniform::SampleRange as _;
+            let mut rng: rand::rngs::SmallRng = rand::make_rng();
+            let local_point = vec3(
+                (-1.0..=1.0).sample_single(&mut rng).unwrap(),
+                (-1.0..=1.0).sample_single(&mut rng).unwrap(),
+                (-1.0..=1.0).sample_single(&mut rng).unwrap(),
+            );
+            self.transform.transform_point(local_point)
+        } else {
+            self.transform.translation()
+        }
+    }
+}
+
+/// Parses `x,y,z` coordinates.
+pub(crate) fn parse_vec3(value: &str) -> Option<Vec3> {
+    let coordinates: Vec<_> = value.splitn(3, ',').collect();
+    let [x, y, z] = coordinates.as_slice() else {
+        return None;
+    };
+    Some(vec3(
+        x.trim().parse().ok()?,
+        y.trim().parse().ok()?,
+        z.trim().parse().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CharacterSelector, TriggerActionBehavior, read_behavior};
+    use crate::magicka_scene::SceneError;
+    use xml::{EventReader, reader::XmlEvent};
+
+    /// Reads the action of a single XML element.
+    fn read(element: &str) -> Result<TriggerActionBehavior, SceneError> {
+        let mut parser = EventReader::new(element.as_bytes());
+        loop {
+            if let XmlEvent::StartElement {
+                name, attributes, ..
+            } = parser.next().unwrap()
+            {
+                return read_behavior(&mut parser, name, attributes);
+            }
+        }
+    }
+
+    #[test]
+    fn read_kill() {
+        let Ok(TriggerActionBehavior::Kill(kill)) =
+            read(r#"<Kill type="goblin" area="pit" remove="true" />"#)
+        else {
+            panic!();
+        };
+        assert!(matches!(&kill.target, CharacterSelector::Type(t) if t == "goblin"));
+        assert_eq!(kill.area.as_deref(), Some("pit"));
+        assert!(kill.remove);
+        assert!(read(r#"<Kill area="pit" />"#).is_err());
+    }
+
+    #[test]
+    fn read_damage_and_heal() {
+        let Ok(TriggerActionBehavior::Damage(damage)) =
+            read(r#"<Damage id="boss" amount="25" percent="true" />"#)
+        else {
+            panic!();
+        };
+        assert!(matches!(&damage.target, CharacterSelector::Id(id) if id == "boss"));
+        assert_eq!(damage.amount, 25.);
+        assert!(damage.percent);
+        assert!(matches!(
+            read(r#"<Heal id="boss" amount="25" />"#),
+            Ok(TriggerActionBehavior::Damage(_))
+        ));
+        assert!(read(r#"<Damage id="boss" amount="25" element="Chaos" />"#).is_err());
+    }
+
+    #[test]
+    fn read_switch_trigger() {
+        let Ok(TriggerActionBehavior::SwitchTrigger(switch)) =
+            read(r#"<DisableTrigger trigger="ambush" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(switch.trigger_name, "ambush");
+        assert!(!switch.enable);
+        let Ok(TriggerActionBehavior::SwitchTrigger(switch)) =
+            read(r#"<EnableTrigger trigger="ambush" />"#)
+        else {
+            panic!();
+        };
+        assert!(switch.enable);
+    }
+
+    #[test]
+    fn read_teleport() {
+        let Ok(TriggerActionBehavior::Teleport(teleport)) =
+            read(r#"<Teleport id="player1" area="start" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(teleport.area, "start");
+        assert!(read(r#"<Teleport id="player1" />"#).is_err());
+    }
+
+    #[test]
+    fn read_spawn_item() {
+        let Ok(TriggerActionBehavior::SpawnItem(item)) =
+            read(r#"<SpawnItem area="chest" type="staff_of_war" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(item.area, "chest");
+        assert_eq!(item.item_type, "staff_of_war");
+        assert_eq!(item.id, None);
+    }
+
+    #[test]
+    fn read_camera() {
+        let Ok(TriggerActionBehavior::CameraMove(camera)) =
+            read(r#"<CameraMove target="bridge" time="2" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(camera.target.as_deref(), Some("bridge"));
+        assert_eq!(camera.time, 2.);
+        let Ok(TriggerActionBehavior::CameraShake(shake)) =
+            read(r#"<CameraShake magnitude="0.5" time="1.5" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!((shake.magnitude, shake.time), (0.5, 1.5));
+        let Ok(TriggerActionBehavior::CameraLock(lock)) = read("<CameraRelease />") else {
+            panic!();
+        };
+        assert!(!lock.locked);
+    }
+
+    #[test]
+    fn read_show_text() {
+        let Ok(TriggerActionBehavior::ShowText(text)) =
+            read(r#"<ShowText text="intro_01" time="4" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(text.text, "intro_01");
+        assert_eq!(text.time, 4.);
+        assert!(read(r#"<ShowText time="4" />"#).is_err());
+    }
+
+    #[test]
+    fn read_set_ai_target() {
+        let Ok(TriggerActionBehavior::SetAiTarget(ai)) =
+            read(r#"<SetAiTarget type="goblin" target="gate" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(ai.target, "gate");
+    }
+
+    #[test]
+    fn read_switch_light() {
+        let Ok(TriggerActionBehavior::SwitchLight(light)) = read(r#"<SwitchLight name="torch" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(light.name, "torch");
+        assert_eq!(light.on, None);
+        let Ok(TriggerActionBehavior::SwitchLight(light)) = read(r#"<LightOff name="torch" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(light.on, Some(false));
+    }
+
+    #[test]
+    fn read_checkpoint_and_end_level() {
+        let Ok(TriggerActionBehavior::Checkpoint(checkpoint)) =
+            read(r#"<Checkpoint spawnPoint="gate" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(checkpoint.spawn_point.as_deref(), Some("gate"));
+        let Ok(TriggerActionBehavior::EndLevel(end)) =
+            read(r#"<EndLevel level="Havindr" scene="ha_s1" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(end.next, Some(("Havindr".to_owned(), "ha_s1".to_owned())));
+        let Ok(TriggerActionBehavior::EndLevel(end)) = read("<EndLevel />") else {
+            panic!();
+        };
+        assert_eq!(end.next, None);
+        // There's no way to tell which scene of a level is first
+        assert!(read(r#"<EndLevel level="Havindr" />"#).is_err());
+    }
+
+    #[test]
+    fn read_spawn_magick() {
+        let Ok(TriggerActionBehavior::SpawnMagick(magick)) =
+            read(r#"<SpawnMagick area="altar" magick="Haste" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(magick.area, "altar");
+        assert_eq!(magick.magick, "Haste");
+        assert!(read(r#"<SpawnMagick area="altar" />"#).is_err());
+    }
+
+    #[test]
+    fn read_play_sound() {
+        let Ok(TriggerActionBehavior::PlaySound(sound)) =
+            read(r#"<PlaySound cue="boom" bank="Misc" area="gate" volume="0.5" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(sound.cue, "boom");
+        assert_eq!(sound.bank.as_deref(), Some("Misc"));
+        assert_eq!(sound.area.as_deref(), Some("gate"));
+        assert_eq!(sound.volume, 0.5);
+        let Ok(TriggerActionBehavior::PlaySound(sound)) = read(r#"<PlaySound cue="boom" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(sound.volume, 1.);
+        assert!(read(r#"<PlaySound bank="Misc" />"#).is_err());
+        assert!(read(r#"<PlaySound cue="boom" volume="loud" />"#).is_err());
+    }
+
+    #[test]
+    fn read_play_and_stop_music() {
+        let Ok(TriggerActionBehavior::PlayMusic(music)) = read(r#"<PlayMusic cue="battle" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(music.cue.as_deref(), Some("battle"));
+        let Ok(TriggerActionBehavior::PlayMusic(music)) = read("<StopMusic />") else {
+            panic!();
+        };
+        assert_eq!(music.cue, None);
+        assert!(read("<PlayMusic />").is_err());
+    }
+
+    #[test]
+    fn read_set_ai_behaviour() {
+        use super::ai::AiBehaviour;
+
+        let Ok(TriggerActionBehavior::SetAiBehaviour(ai)) =
+            read(r#"<SetAiBehaviour id="guard" behaviour="idle" />"#)
+        else {
+            panic!();
+        };
+        assert!(matches!(&ai.agents, CharacterSelector::Id(id) if id == "guard"));
+        assert!(matches!(ai.behaviour, AiBehaviour::Idle));
+        let Ok(TriggerActionBehavior::SetAiBehaviour(ai)) =
+            read(r#"<SetAiBehaviour type="goblin" behaviour="patrol" />"#)
+        else {
+            panic!();
+        };
+        assert!(matches!(&ai.behaviour, AiBehaviour::Other(b) if b == "patrol"));
+        assert!(read(r#"<SetAiBehaviour id="guard" />"#).is_err());
+    }
+
+    #[test]
+    fn read_set_and_add_variable() {
+        use crate::script_triggers::variables::{VariableScope, VariableValue};
+
+        let Ok(TriggerActionBehavior::SetVariable(set)) =
+            read(r#"<SetVariable name="gate_open" value="true" scope="global" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(set.name, "gate_open");
+        assert_eq!(set.value, VariableValue::Bool(true));
+        assert!(!set.add);
+        assert_eq!(set.scope, Some(VariableScope::Global));
+        // Adding counts up by default
+        let Ok(TriggerActionBehavior::SetVariable(add)) = read(r#"<AddVariable name="waves" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(add.value, VariableValue::Int(1));
+        assert!(add.add);
+        assert_eq!(add.scope, None);
+        assert!(read(r#"<SetVariable name="waves" />"#).is_err());
+        assert!(read(r#"<SetVariable name="waves" value="1" scope="scene" />"#).is_err());
+    }
+}
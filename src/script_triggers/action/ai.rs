@@ -0,0 +1,134 @@
+use super::{CharacterSelector, NamedLocations};
+use crate::character::Character;
+use bevy::prelude::*;
+use bevy_landmass::{Agent3d, AgentTarget3d};
+
+/// Sends the selected agents to a character with an ID, or else a locator or trigger area.
+#[derive(Clone, Debug)]
+pub struct SetAiTarget {
+    pub agents: CharacterSelector,
+    pub target: String,
+}
+
+/// Changes what the selected agents do.
+#[derive(Clone, Debug)]
+pub struct SetAiBehaviour {
+    pub agents: CharacterSelector,
+    pub behaviour: AiBehaviour,
+}
+
+#[derive(Clone, Debug)]
+pub enum AiBehaviour {
+    /// Stops and stays there.
+    Idle,
+    /// Not supported, by name.
+    Other(String),
+}
+
+pub fn execute_set_ai_target(
+    action: In<impl AsRef<SetAiTarget>>,
+    agents: Query<(Entity, Option<&Name>, &Character), With<Agent3d>>,
+    named_characters: Query<(Entity, &Name), With<Character>>,
+    locations: NamedLocations,
+    mut commands: Commands,
+) {
+    let SetAiTarget {
+        agents: selector,
+        target,
+    } = action.as_ref();
+    let character_target = named_characters
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(target))
+        .map(|(character, _)| character);
+    let location_target = locations.find(target);
+    if character_target.is_none() && location_target.is_none() {
+        warn!("AI target trigger action can't find character/locator/area {target:?}");
+        return;
+    }
+    for (agent, name, character) in &agents {
+        if !selector.matches(name, character) {
+            continue;
+        }
+        // Each to a different spot in an area
+        let agent_target = character_target.map(AgentTarget3d::Entity).or_else(|| {
+            location_target.map(|location| AgentTarget3d::Point(location.random_point()))
+        });
+        if let Some(agent_target) = agent_target {
+            commands.entity(agent).insert(agent_target);
+        }
+    }
+}
+
+pub fn execute_set_ai_behaviour(
+    action: In<impl AsRef<SetAiBehaviour>>,
+    agents: Query<(Entity, Option<&Name>, &Character), With<Agent3d>>,
+    mut commands: Commands,
+) {
+    let SetAiBehaviour {
+        agents: selector,
+        behaviour,
+    } = action.as_ref();
+    let AiBehaviour::Idle = behaviour else {
+        info!("NYI: AI behaviour {behaviour:?}");
+        return;
+    };
+    for (agent, name, character) in &agents {
+        if selector.matches(name, character) {
+            commands.entity(agent).insert(AgentTarget3d::None);
+        }
+    }
+}
+
+pub(crate) fn target_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<SetAiTarget, ()> {
+    let mut agents = None;
+    let mut target = None;
+    for attribute in attributes {
+        if CharacterSelector::read_attribute(&mut agents, &attribute) {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value } = attribute;
+        if name.local_name.eq_ignore_ascii_case("target") {
+            target = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger AI target action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SetAiTarget {
+        agents: agents.ok_or(())?,
+        target: target.ok_or(())?,
+    })
+}
+
+pub(crate) fn behaviour_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<SetAiBehaviour, ()> {
+    let mut agents = None;
+    let mut behaviour = None;
+    for attribute in attributes {
+        if CharacterSelector::read_attribute(&mut agents, &attribute) {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value } = attribute;
+        if name.local_name.eq_ignore_ascii_case("behaviour") {
+            behaviour = Some(if value.eq_ignore_ascii_case("idle") {
+                AiBehaviour::Idle
+            } else {
+                AiBehaviour::Other(value)
+            });
+        } else {
+            warn!(
+                "Unhandled trigger AI behaviour action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SetAiBehaviour {
+        agents: agents.ok_or(())?,
+        behaviour: behaviour.ok_or(())?,
+    })
+}
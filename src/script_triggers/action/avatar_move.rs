@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use super::NamedLocations;
 use crate::character::{Character, player::PlayerCharacter};
 
 #[derive(Clone, Debug)]
@@ -48,12 +49,10 @@ enum MoveFacing {
 pub fn execute_avatar_move(
     action: In<impl AsRef<AvatarMove>>,
     numbered_players: Query<(Entity, &PlayerCharacter)>,
-    mut player_transforms_and_transform_helper: ParamSet<(
+    mut player_transforms_and_locations: ParamSet<(
         Query<&mut Transform, With<Character>>,
-        TransformHelper,
+        NamedLocations,
     )>,
-    locators: Query<(Entity, &Name), With<crate::magicka_level_model::Locator>>,
-    areas: Query<(Entity, &Name), With<crate::magicka_level_model::TriggerArea>>,
 ) {
     let action = action.as_ref();
     for (target, events) in &action.targets {
@@ -82,25 +81,20 @@ pub fn execute_avatar_move(
                     let target_position = match *target_position_source {
                         MoveTarget::Position(target) => target,
                         MoveTarget::Trigger(ref id) => {
-                            let Some(location) = named_trigger_location(
-                                id,
-                                &locators,
-                                &areas,
-                                &player_transforms_and_transform_helper.p1(),
-                            ) else {
+                            let Some(location) = player_transforms_and_locations.p1().find(id)
+                            else {
                                 warn!(
                                     "AvatarMove Move can't find position target {target_position_source:?}"
                                 );
                                 continue;
                             };
-                            location
+                            location.random_point()
                         }
                     };
 
                     // TODO: Walk there instead of teleporting there
-                    if let Ok(mut trans) = player_transforms_and_transform_helper
-                        .p0()
-                        .get_mut(target_entity)
+                    if let Ok(mut trans) =
+                        player_transforms_and_locations.p0().get_mut(target_entity)
                     {
                         trans.translation = target_position;
                     } else {
@@ -115,41 +109,6 @@ pub fn execute_avatar_move(
     }
 }
 
-// FIXME: This somewhat duplicates what action::spawn_character does
-fn named_trigger_location(
-    name: &str,
-    locators: &Query<(Entity, &Name), With<crate::magicka_level_model::Locator>>,
-    areas: &Query<(Entity, &Name), With<crate::magicka_level_model::TriggerArea>>,
-    transform_helper: &TransformHelper,
-) -> Option<Vec3> {
-    let (source_entity, sample_space) = if let Some((locator, _name)) =
-        locators.iter().find(|(_, n)| n.eq_ignore_ascii_case(name))
-    {
-        (locator, false)
-    } else if let Some((area, _name)) = areas.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)) {
-        (area, true)
-    } else {
-        return None;
-    };
-
-    let source_transform = transform_helper
-        .compute_global_transform(source_entity)
-        .ok()?;
-    let location = if sample_space {
-        use rand::distr::uniform::SampleRange as _;
-        let mut rng: rand::rngs::SmallRng = rand::make_rng();
-        let local_point = vec3(
-            (-1.0..=1.0).sample_single(&mut rng).unwrap(),
-            (-1.0..=1.0).sample_single(&mut rng).unwrap(),
-            (-1.0..=1.0).sample_single(&mut rng).unwrap(),
-        );
-        source_transform.transform_point(local_point)
-    } else {
-        source_transform.translation()
-    };
-    Some(location)
-}
-
 pub(crate) fn from_xml(
     _attributes: Vec<xml::attribute::OwnedAttribute>,
     parser: &mut xml::EventReader<impl std::io::Read>,
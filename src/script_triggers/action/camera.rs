@@ -0,0 +1,126 @@
+use super::NamedLocations;
+use crate::camera::CameraScript;
+use bevy::prelude::*;
+
+/// Moves the camera to look at a locator or trigger area, or back to the players without one.
+#[derive(Clone, Debug)]
+pub struct CameraMove {
+    pub target: Option<String>,
+    /// About how long it takes to get there, in seconds.
+    pub time: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct CameraShake {
+    pub magnitude: f32,
+    pub time: f32,
+}
+
+/// Keeps the camera where it is, or lets it follow the players again.
+#[derive(Clone, Debug)]
+pub struct CameraLock {
+    pub locked: bool,
+}
+
+pub fn execute_camera_move(
+    action: In<impl AsRef<CameraMove>>,
+    mut scripts: Query<&mut CameraScript>,
+    locations: NamedLocations,
+) {
+    let CameraMove { target, time } = action.as_ref();
+    let focus = match target {
+        Some(target) => {
+            let Some(location) = locations.find(target) else {
+                warn!("Camera move trigger action can't find locator/area {target:?}");
+                return;
+            };
+            Some(location.transform.translation())
+        }
+        None => None,
+    };
+    for mut script in &mut scripts {
+        script.focus = focus;
+        script.move_time = *time;
+        script.locked = false;
+    }
+}
+
+pub fn execute_camera_shake(
+    action: In<impl AsRef<CameraShake>>,
+    mut scripts: Query<&mut CameraScript>,
+) {
+    let CameraShake { magnitude, time } = action.as_ref();
+    for mut script in &mut scripts {
+        script.shake = Some(crate::camera::CameraShake {
+            magnitude: *magnitude,
+            duration: *time,
+            elapsed: 0.,
+        });
+    }
+}
+
+pub fn execute_camera_lock(
+    action: In<impl AsRef<CameraLock>>,
+    mut scripts: Query<&mut CameraScript>,
+) {
+    let CameraLock { locked } = action.as_ref();
+    for mut script in &mut scripts {
+        script.locked = *locked;
+        if !*locked {
+            script.focus = None;
+        }
+    }
+}
+
+pub(crate) fn move_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<CameraMove, ()> {
+    let mut target = None;
+    let mut time = 1.;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("target") {
+            target = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("time") {
+            time = value.parse().map_err(|_e| ())?;
+        } else {
+            warn!(
+                "Unhandled trigger camera move action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(CameraMove { target, time })
+}
+
+pub(crate) fn shake_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<CameraShake, ()> {
+    let mut magnitude = 1.;
+    let mut time = 1.;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("magnitude") {
+            magnitude = value.parse().map_err(|_e| ())?;
+        } else if name.local_name.eq_ignore_ascii_case("time") {
+            time = value.parse().map_err(|_e| ())?;
+        } else {
+            warn!(
+                "Unhandled trigger camera shake action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(CameraShake { magnitude, time })
+}
+
+pub(crate) fn lock_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    locked: bool,
+) -> Result<CameraLock, ()> {
+    let mut locked = locked;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("lock") {
+            locked = value.parse().map_err(|_e| ())?;
+        }
+    }
+    Ok(CameraLock { locked })
+}
@@ -0,0 +1,43 @@
+use crate::scene::{self, Scene};
+use bevy::prelude::*;
+
+/// Saves the current scene as where players start over once they have all died.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// The spawn point to start at, `start` by default.
+    pub spawn_point: Option<String>,
+}
+
+pub fn execute_checkpoint(
+    action: In<impl AsRef<Checkpoint>>,
+    scenes: Query<&Scene>,
+    mut commands: Commands,
+) -> Result {
+    let Checkpoint { spawn_point } = action.as_ref();
+    let current_scene = scenes.single()?;
+    info!(
+        "Checkpoint in {} of level {}",
+        current_scene.scene, current_scene.level
+    );
+    commands.insert_resource(scene::Checkpoint {
+        level: current_scene.level.clone(),
+        scene: current_scene.scene.clone(),
+        spawn_point: spawn_point.clone(),
+    });
+    Ok(())
+}
+
+pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Result<Checkpoint, ()> {
+    let mut spawn_point = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("spawnpoint") {
+            spawn_point = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger checkpoint action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(Checkpoint { spawn_point })
+}
@@ -0,0 +1,93 @@
+use super::CharacterSelector;
+use crate::{
+    character::Character,
+    gameplay::damage::{self, DamagePayload, DamageType, Health},
+    spelling::element::Element,
+};
+use bevy::prelude::*;
+
+/// Damages the selected characters, or heals them with a negative amount.
+#[derive(Clone, Debug)]
+pub struct Damage {
+    pub target: CharacterSelector,
+    pub amount: f32,
+    /// Whether [`amount`](Self::amount) is a percentage of their maximum health.
+    pub percent: bool,
+    pub damage_type: DamageType,
+}
+
+pub fn execute_damage(
+    action: In<impl AsRef<Damage>>,
+    characters: Query<(Entity, Option<&Name>, &Character, &Health)>,
+    mut commands: Commands,
+) {
+    let Damage {
+        target,
+        amount,
+        percent,
+        damage_type,
+    } = action.as_ref();
+    for (character, name, character_data, health) in &characters {
+        if !target.matches(name, character_data) {
+            continue;
+        }
+        let amount = if *percent {
+            amount * 0.01 * health.limit()
+        } else {
+            *amount
+        };
+        commands.trigger(damage::Damage {
+            recipient: character,
+            damage: DamagePayload {
+                damage_type: *damage_type,
+                amount,
+                source: None,
+                silent: false,
+            },
+        });
+    }
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    heal: bool,
+) -> Result<Damage, ()> {
+    let mut target = None;
+    let mut amount = None;
+    let mut percent = false;
+    let mut damage_type = if heal {
+        DamageType::Element(Element::Life)
+    } else {
+        DamageType::True
+    };
+    for attribute in attributes {
+        if CharacterSelector::read_attribute(&mut target, &attribute) {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value } = attribute;
+        if name.local_name.eq_ignore_ascii_case("amount") {
+            amount = Some(value.parse::<f32>().map_err(|_e| ())?);
+        } else if name.local_name.eq_ignore_ascii_case("percent") {
+            percent = value.parse().map_err(|_e| ())?;
+        } else if name.local_name.eq_ignore_ascii_case("element") {
+            let element = Element::all()
+                .iter()
+                .find(|element| format!("{element:?}").eq_ignore_ascii_case(&value))
+                .ok_or(())?;
+            damage_type = DamageType::Element(*element);
+        } else {
+            warn!(
+                "Unhandled trigger damage action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    // Healing is negative damage
+    let amount = amount.ok_or(())?.abs();
+    Ok(Damage {
+        target: target.ok_or(())?,
+        amount: if heal { -amount } else { amount },
+        percent,
+        damage_type,
+    })
+}
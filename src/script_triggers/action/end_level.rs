@@ -0,0 +1,58 @@
+use crate::scene::{self, Scene};
+use bevy::prelude::*;
+
+/// Finishes the current level, going on to a scene of another level if one is given.
+#[derive(Clone, Debug)]
+pub struct EndLevel {
+    /// The level and scene to go to.
+    pub next: Option<(String, String)>,
+}
+
+pub fn execute_end_level(
+    action: In<impl AsRef<EndLevel>>,
+    world: &mut World,
+    q_scenes: &mut QueryState<&Scene>,
+) -> Result {
+    let EndLevel { next } = action.as_ref();
+    let current_scene = q_scenes.single(world)?;
+    info!("Finished level {}", current_scene.level);
+    // A checkpoint doesn't carry over into another level
+    world.remove_resource::<scene::Checkpoint>();
+
+    let Some((level, scene)) = next else {
+        // TODO: Go back to the menu once there is one
+        return Ok(());
+    };
+    let change = scene::ChangeScene {
+        level: level.clone(),
+        scene: scene.clone(),
+        spawn_players: true,
+        spawn_point: Some("start".to_owned()),
+    };
+    world.run_system_cached_with(scene::queue_delayed_scene_change, change)?;
+    Ok(())
+}
+
+pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Result<EndLevel, ()> {
+    let mut next_level = None;
+    let mut next_scene = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("level") {
+            next_level = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("scene") {
+            next_scene = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger end level action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    let next = match (next_level, next_scene) {
+        (Some(level), Some(scene)) => Some((level, scene)),
+        (None, None) => None,
+        // Levels don't say which of their scenes comes first
+        _ => return Err(()),
+    };
+    Ok(EndLevel { next })
+}
@@ -0,0 +1,89 @@
+use super::CharacterSelector;
+use crate::{
+    character::Character,
+    gameplay::damage::{Damage, DamagePayload, DamageType, Health},
+    magicka_level_model::TriggerArea,
+};
+use bevy::prelude::*;
+
+/// Kills the selected characters, or removes them from the scene without dying.
+#[derive(Clone, Debug)]
+pub struct Kill {
+    pub target: CharacterSelector,
+    /// Only characters in this trigger area.
+    pub area: Option<String>,
+    pub remove: bool,
+}
+
+pub fn execute_kill(
+    action: In<impl AsRef<Kill>>,
+    characters: Query<(Entity, Option<&Name>, &Character, &Health)>,
+    areas: Query<(&Name, &TriggerArea)>,
+    mut commands: Commands,
+) {
+    let Kill {
+        target,
+        area,
+        remove,
+    } = action.as_ref();
+    let area = match area {
+        Some(area_name) => {
+            let Some((_name, area)) = areas
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(area_name))
+            else {
+                warn!("Kill trigger action can't find area {area_name:?}");
+                return;
+            };
+            Some(area)
+        }
+        None => None,
+    };
+    for (character, name, character_data, health) in &characters {
+        if !target.matches(name, character_data)
+            || area.is_some_and(|area| !area.contains(character))
+        {
+            continue;
+        }
+        if *remove {
+            commands.entity(character).despawn();
+        } else if health.alive() {
+            commands.trigger(Damage {
+                recipient: character,
+                damage: DamagePayload {
+                    damage_type: DamageType::True,
+                    amount: health.current(),
+                    source: None,
+                    silent: false,
+                },
+            });
+        }
+    }
+}
+
+pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Result<Kill, ()> {
+    let mut target = None;
+    let mut area = None;
+    let mut remove = false;
+    for attribute in attributes {
+        if CharacterSelector::read_attribute(&mut target, &attribute) {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value } = attribute;
+        if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("remove") {
+            remove = value.parse().map_err(|_e| ())?;
+        } else {
+            warn!(
+                "Unhandled trigger kill action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(Kill {
+        target: target.ok_or(())?,
+        area,
+        remove,
+    })
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+/// Turns a level light on or off, or the other way around without [`on`](Self::on).
+#[derive(Clone, Debug)]
+pub struct SwitchLight {
+    pub name: String,
+    pub on: Option<bool>,
+}
+
+pub fn execute_switch_light(
+    action: In<impl AsRef<SwitchLight>>,
+    mut lights: Query<
+        (&Name, &mut Visibility),
+        Or<(With<PointLight>, With<SpotLight>, With<DirectionalLight>)>,
+    >,
+) -> Result {
+    let SwitchLight {
+        name: light_name,
+        on,
+    } = action.as_ref();
+    let (_name, mut visibility) = lights
+        .iter_mut()
+        .find(|(name, _)| name.eq_ignore_ascii_case(light_name))
+        .ok_or("Light switch action can't find light by name")?;
+    let on = on.unwrap_or(*visibility == Visibility::Hidden);
+    // Hidden lights don't contribute to the ambient light either
+    visibility.set_if_neq(if on {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+    Ok(())
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    on: Option<bool>,
+) -> Result<SwitchLight, ()> {
+    let mut light_name = None;
+    let mut on = on;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("name") {
+            light_name = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("on") {
+            on = Some(value.parse().map_err(|_e| ())?);
+        } else {
+            warn!(
+                "Unhandled trigger light action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SwitchLight {
+        name: light_name.ok_or(())?,
+        on,
+    })
+}
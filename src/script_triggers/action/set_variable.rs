@@ -0,0 +1,66 @@
+use crate::script_triggers::variables::{self, SceneVariables, VariableScope, VariableValue};
+use bevy::prelude::*;
+
+/// Sets a scene variable, or adds to it.
+#[derive(Clone, Debug)]
+pub struct SetVariable {
+    pub name: String,
+    pub value: VariableValue,
+    pub add: bool,
+    /// Where it's already set, or else the level, if `None`.
+    pub scope: Option<VariableScope>,
+}
+
+pub fn execute_set_variable(
+    action: In<impl AsRef<SetVariable>>,
+    mut variables: ResMut<SceneVariables>,
+) -> Result {
+    let SetVariable {
+        name,
+        value,
+        add,
+        scope,
+    } = action.as_ref();
+    let value = if *add {
+        let current = variables.get(name).unwrap_or(VariableValue::DEFAULT);
+        current
+            .add(*value)
+            .ok_or("Add variable action can't add to or with a bool")?
+    } else {
+        *value
+    };
+    variables.set(name, value, *scope);
+    Ok(())
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    add: bool,
+) -> Result<SetVariable, ()> {
+    let mut variable_name = None;
+    // Counting up by default
+    let mut value = add.then_some(VariableValue::Int(1));
+    let mut scope = None;
+    for attribute in attributes {
+        if variables::read_scope_attribute(&mut scope, &attribute)? {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value: text } = attribute;
+        if name.local_name.eq_ignore_ascii_case("name") {
+            variable_name = Some(text);
+        } else if name.local_name.eq_ignore_ascii_case("value") {
+            value = Some(VariableValue::parse(&text).ok_or(())?);
+        } else {
+            warn!(
+                "Unhandled trigger variable action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SetVariable {
+        name: variable_name.ok_or(())?,
+        value: value.ok_or(())?,
+        add,
+        scope,
+    })
+}
@@ -0,0 +1,40 @@
+use crate::dialog::ShowCaption;
+use bevy::prelude::*;
+
+/// Shows a caption for a while.
+#[derive(Clone, Debug)]
+pub struct ShowText {
+    pub text: String,
+    /// In seconds.
+    pub time: f32,
+}
+
+pub fn execute_show_text(action: In<impl AsRef<ShowText>>, mut commands: Commands) {
+    let ShowText { text, time } = action.as_ref();
+    // TODO: Look up localized text once there's a string table
+    commands.trigger(ShowCaption {
+        text: text.clone(),
+        duration: *time,
+    });
+}
+
+pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Result<ShowText, ()> {
+    let mut text = None;
+    let mut time = 3.;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("text") {
+            text = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("time") {
+            time = value.parse().map_err(|_e| ())?;
+        } else {
+            warn!(
+                "Unhandled trigger show text action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(ShowText {
+        text: text.ok_or(())?,
+        time,
+    })
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+/// Plays a sound cue, at a locator or trigger area if one is given.
+#[derive(Clone, Debug)]
+pub struct PlaySound {
+    pub cue: String,
+    pub bank: Option<String>,
+    pub area: Option<String>,
+    pub volume: f32,
+}
+
+/// Starts a music cue, or stops the music without one.
+#[derive(Clone, Debug)]
+pub struct PlayMusic {
+    pub cue: Option<String>,
+}
+
+// TODO: Play these once there is audio, Magicka's cues are in XACT sound banks
+pub fn execute_play_sound(action: In<impl AsRef<PlaySound>>) {
+    let PlaySound {
+        cue,
+        bank,
+        area,
+        volume,
+    } = action.as_ref();
+    debug!("NYI: Play sound cue {cue:?} from bank {bank:?} at {area:?} with volume {volume}");
+}
+
+pub fn execute_play_music(action: In<impl AsRef<PlayMusic>>) {
+    let PlayMusic { cue } = action.as_ref();
+    match cue {
+        Some(cue) => debug!("NYI: Play music cue {cue:?}"),
+        None => debug!("NYI: Stop music"),
+    }
+}
+
+pub(crate) fn sound_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<PlaySound, ()> {
+    let mut cue = None;
+    let mut bank = None;
+    let mut area = None;
+    let mut volume = 1.;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("cue") {
+            cue = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("bank") {
+            bank = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("volume") {
+            volume = value.parse().map_err(|_e| ())?;
+        } else {
+            warn!(
+                "Unhandled trigger play sound action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(PlaySound {
+        cue: cue.ok_or(())?,
+        bank,
+        area,
+        volume,
+    })
+}
+
+/// Reads a music action, which plays its cue or else stops the music.
+pub(crate) fn music_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    play: bool,
+) -> Result<PlayMusic, ()> {
+    let mut cue = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if play && name.local_name.eq_ignore_ascii_case("cue") {
+            cue = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger music action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    if play && cue.is_none() {
+        return Err(());
+    }
+    Ok(PlayMusic { cue })
+}
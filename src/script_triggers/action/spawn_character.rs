@@ -75,6 +75,8 @@ pub fn execute_spawn_character(
                     scene_entity: Some(current_scene_entity),
                     model_index: None,
                     start_as_agent: true,
+                    trigger_type_name: None,
+                    accel: None,
                 },
             )
             .unwrap()
@@ -0,0 +1,105 @@
+use super::NamedLocations;
+use crate::{
+    item::{ItemInstance, item_asset_path},
+    scene::Scene,
+};
+use bevy::prelude::*;
+
+/// Spawns an item lying loose at a locator or in a trigger area.
+#[derive(Clone, Debug)]
+pub struct SpawnItem {
+    pub area: String,
+    pub item_type: String,
+    pub id: Option<String>,
+}
+
+/// Spawns a Magick book, which teaches the Magick to whoever picks it up.
+#[derive(Clone, Debug)]
+pub struct SpawnMagick {
+    pub area: String,
+    pub magick: String,
+}
+
+pub fn execute_spawn_item(
+    action: In<impl AsRef<SpawnItem>>,
+    locations: NamedLocations,
+    scenes: Query<Entity, With<Scene>>,
+    assets: Res<AssetServer>,
+    mut commands: Commands,
+) -> Result {
+    let SpawnItem {
+        area,
+        item_type,
+        id,
+    } = action.as_ref();
+    let Some(location) = locations.find(area) else {
+        warn!("Spawn item trigger action can't find locator/area {area:?}");
+        return Ok(());
+    };
+    let scene = scenes.single()?;
+    // Usually already loaded with the scene
+    let item = assets.load(item_asset_path(item_type));
+    let name = id.clone().unwrap_or_else(|| format!("Item - {item_type}"));
+    commands.spawn((
+        Name::new(name),
+        ItemInstance(item),
+        Transform::from_translation(location.random_point()),
+        ChildOf(scene),
+    ));
+    Ok(())
+}
+
+pub fn execute_spawn_magick(action: In<impl AsRef<SpawnMagick>>) {
+    let SpawnMagick { area, magick } = action.as_ref();
+    info!("NYI: Spawn Magick {magick:?} at {area:?}, there are no Magicks to learn yet");
+}
+
+pub(crate) fn item_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<SpawnItem, ()> {
+    let mut area = None;
+    let mut item_type = None;
+    let mut id = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("type") {
+            item_type = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("id") {
+            id = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger spawn item action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SpawnItem {
+        area: area.ok_or(())?,
+        item_type: item_type.ok_or(())?,
+        id,
+    })
+}
+
+pub(crate) fn magick_from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<SpawnMagick, ()> {
+    let mut area = None;
+    let mut magick = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("magick") {
+            magick = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger spawn magick action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(SpawnMagick {
+        area: area.ok_or(())?,
+        magick: magick.ok_or(())?,
+    })
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+
+use crate::script_triggers::{Trigger, TriggerDisabled};
+
+/// Enables or disables a trigger. Disabling it also stops its looped actions.
+#[derive(Clone, Debug)]
+pub struct SwitchTrigger {
+    pub trigger_name: String,
+    pub enable: bool,
+}
+
+pub fn execute_switch_trigger(
+    action: In<impl AsRef<SwitchTrigger>>,
+    mut triggers: Query<(Entity, &Name, &mut Trigger)>,
+    mut commands: Commands,
+) -> Result {
+    let SwitchTrigger {
+        trigger_name,
+        enable,
+    } = action.as_ref();
+    let (trigger, _name, mut trigger_data) = triggers
+        .iter_mut()
+        .find(|(_t, name, _)| name.eq_ignore_ascii_case(trigger_name))
+        .ok_or("Can't find target trigger by name")?;
+    if *enable {
+        commands.entity(trigger).remove::<TriggerDisabled>();
+    } else {
+        trigger_data.stop_loop();
+        commands.entity(trigger).insert(TriggerDisabled);
+    }
+    Ok(())
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    enable: bool,
+) -> Result<SwitchTrigger, ()> {
+    let mut trigger_name: Option<String> = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("trigger") {
+            trigger_name = Some(value);
+        }
+    }
+    Ok(SwitchTrigger {
+        trigger_name: trigger_name.ok_or(())?,
+        enable,
+    })
+}
@@ -0,0 +1,63 @@
+use super::{CharacterSelector, NamedLocations};
+use crate::character::Character;
+use avian3d::prelude::LinearVelocity;
+use bevy::prelude::*;
+
+/// Moves the selected characters to a locator or into a trigger area at once.
+#[derive(Clone, Debug)]
+pub struct Teleport {
+    pub target: CharacterSelector,
+    pub area: String,
+}
+
+pub fn execute_teleport(
+    action: In<impl AsRef<Teleport>>,
+    mut characters_and_locations: ParamSet<(
+        Query<(
+            Option<&Name>,
+            &Character,
+            &mut Transform,
+            Option<&mut LinearVelocity>,
+        )>,
+        NamedLocations,
+    )>,
+) {
+    let Teleport { target, area } = action.as_ref();
+    let Some(location) = characters_and_locations.p1().find(area) else {
+        warn!("Teleport trigger action can't find locator/area {area:?}");
+        return;
+    };
+    for (name, character, mut transform, velocity) in &mut characters_and_locations.p0() {
+        if !target.matches(name, character) {
+            continue;
+        }
+        // Each into a different spot in an area
+        transform.translation = location.random_point();
+        if let Some(mut velocity) = velocity {
+            *velocity = LinearVelocity::ZERO;
+        }
+    }
+}
+
+pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Result<Teleport, ()> {
+    let mut target = None;
+    let mut area = None;
+    for attribute in attributes {
+        if CharacterSelector::read_attribute(&mut target, &attribute) {
+            continue;
+        }
+        let xml::attribute::OwnedAttribute { name, value } = attribute;
+        if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else {
+            warn!(
+                "Unhandled trigger teleport action attribute {:?}",
+                name.local_name
+            );
+        }
+    }
+    Ok(Teleport {
+        target: target.ok_or(())?,
+        area: area.ok_or(())?,
+    })
+}
@@ -1,15 +1,36 @@
+pub mod character_dead;
 pub mod dialog_done;
+pub mod health;
+pub mod item_present;
+pub mod magick_cast;
+pub mod players_present;
 pub mod present;
+pub mod scene_visited;
+pub mod timer;
+pub mod variable;
 
 use super::Trigger;
 use bevy::prelude::*;
-use std::sync::Arc;
+use std::{cmp::Ordering, sync::Arc};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TriggerConditionLogic {
     Present(Arc<present::TriggerConditionPresent>),
     DialogDone(Arc<dialog_done::TriggerConditionDialogDone>),
-    Unknown,
+    CharacterDead(Arc<character_dead::TriggerConditionCharacterDead>),
+    Health(Arc<health::TriggerConditionHealth>),
+    Timer(Arc<timer::TriggerConditionTimer>),
+    PlayersPresent(Arc<players_present::TriggerConditionPlayersPresent>),
+    ItemPresent(Arc<item_present::TriggerConditionItemPresent>),
+    MagickCast(Arc<magick_cast::TriggerConditionMagickCast>),
+    SceneVisited(Arc<scene_visited::TriggerConditionSceneVisited>),
+    Variable(Arc<variable::TriggerConditionVariable>),
+    /// A condition type that isn't supported, by its element name.
+    Unknown(String),
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<magick_cast::CastMagicks>();
 }
 
 pub(super) fn conditions_met(
@@ -25,16 +46,43 @@ pub(super) fn conditions_met(
     'outer: for conjunction in conditions.as_ref() {
         for condition in conjunction {
             use TriggerConditionLogic::*;
-            let evaluated: Option<bool> = match &condition.logic {
-                Present(handle) => {
-                    world.run_system_cached_with(present::condition_met_present, handle.clone())?
-                }
-                DialogDone(handle) => world.run_system_cached_with(
-                    dialog_done::condition_met_dialog_done,
-                    handle.clone(),
-                )?,
-                Unknown => None,
-            };
+            let evaluated: Option<bool> =
+                match &condition.logic {
+                    Present(handle) => world
+                        .run_system_cached_with(present::condition_met_present, handle.clone())?,
+                    DialogDone(handle) => world.run_system_cached_with(
+                        dialog_done::condition_met_dialog_done,
+                        handle.clone(),
+                    )?,
+                    CharacterDead(handle) => world.run_system_cached_with(
+                        character_dead::condition_met_character_dead,
+                        handle.clone(),
+                    )?,
+                    Health(handle) => world
+                        .run_system_cached_with(health::condition_met_health, handle.clone())?,
+                    Timer(handle) => {
+                        world.run_system_cached_with(timer::condition_met_timer, handle.clone())?
+                    }
+                    PlayersPresent(handle) => world.run_system_cached_with(
+                        players_present::condition_met_players_present,
+                        handle.clone(),
+                    )?,
+                    ItemPresent(handle) => world.run_system_cached_with(
+                        item_present::condition_met_item_present,
+                        handle.clone(),
+                    )?,
+                    MagickCast(handle) => world.run_system_cached_with(
+                        magick_cast::condition_met_magick_cast,
+                        handle.clone(),
+                    )?,
+                    SceneVisited(handle) => world.run_system_cached_with(
+                        scene_visited::condition_met_scene_visited,
+                        handle.clone(),
+                    )?,
+                    Variable(handle) => world
+                        .run_system_cached_with(variable::condition_met_variable, handle.clone())?,
+                    Unknown(_) => None,
+                };
             let evaluated = evaluated.map(|r| r ^ condition.invert);
             if evaluated.is_none() {
                 let name = q_triggers
@@ -56,14 +104,35 @@ pub(super) fn conditions_met(
     Ok(false)
 }
 
+/// Reads a condition from its element. Fails when a required attribute is missing or invalid.
 pub fn read_logic(
     name: xml::name::OwnedName,
     attributes: Vec<xml::attribute::OwnedAttribute>,
 ) -> Result<TriggerConditionLogic, ()> {
-    let logic = if name.local_name.eq_ignore_ascii_case("present") {
-        TriggerConditionLogic::Present(Arc::new(present::from_xml(attributes)))
-    } else if name.local_name.eq_ignore_ascii_case("dialogdone") {
-        TriggerConditionLogic::DialogDone(Arc::new(dialog_done::from_xml(attributes).unwrap()))
+    use TriggerConditionLogic::*;
+    let is = |n: &str| name.local_name.eq_ignore_ascii_case(n);
+    let logic = if is("present") {
+        Present(Arc::new(present::from_xml(attributes)?))
+    } else if is("dialogdone") {
+        DialogDone(Arc::new(dialog_done::from_xml(attributes)?))
+    } else if is("characterdead") {
+        CharacterDead(Arc::new(character_dead::from_xml(attributes, true)?))
+    } else if is("characteralive") {
+        CharacterDead(Arc::new(character_dead::from_xml(attributes, false)?))
+    } else if is("health") {
+        Health(Arc::new(health::from_xml(attributes)?))
+    } else if is("timer") {
+        Timer(Arc::new(timer::from_xml(attributes)?))
+    } else if is("playerspresent") {
+        PlayersPresent(Arc::new(players_present::from_xml(attributes)?))
+    } else if is("itempresent") {
+        ItemPresent(Arc::new(item_present::from_xml(attributes)?))
+    } else if is("magickcast") {
+        MagickCast(Arc::new(magick_cast::from_xml(attributes)?))
+    } else if is("scenevisited") {
+        SceneVisited(Arc::new(scene_visited::from_xml(attributes)?))
+    } else if is("variable") {
+        Variable(Arc::new(variable::from_xml(attributes)?))
     } else {
         warn!(
             "Unhandled scene trigger condition type {:?}",
@@ -74,7 +143,184 @@ pub fn read_logic(
             "Unhandled scene trigger condition type {:?}",
             name.local_name
         );
-        TriggerConditionLogic::Unknown
+        Unknown(name.local_name)
     };
     Ok(logic)
 }
+
+/// Reads a `compareMethod` attribute value.
+pub(crate) fn parse_compare_method(value: &str) -> Result<Ordering, ()> {
+    if value.eq_ignore_ascii_case("equal") {
+        Ok(Ordering::Equal)
+    } else if value.eq_ignore_ascii_case("less") {
+        Ok(Ordering::Less)
+    } else if value.eq_ignore_ascii_case("greater") {
+        Ok(Ordering::Greater)
+    } else {
+        warn!("Invalid trigger condition compare method {value:?}");
+        Err(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TriggerConditionLogic, read_logic};
+    use std::cmp::Ordering;
+    use xml::{EventReader, reader::XmlEvent};
+
+    /// Reads the condition of a single XML element.
+    fn read(element: &str) -> Result<TriggerConditionLogic, ()> {
+        let mut parser = EventReader::new(element.as_bytes());
+        loop {
+            if let XmlEvent::StartElement {
+                name, attributes, ..
+            } = parser.next().unwrap()
+            {
+                return read_logic(name, attributes);
+            }
+        }
+    }
+
+    #[test]
+    fn read_present() {
+        let Ok(TriggerConditionLogic::Present(present)) =
+            read(r#"<Present area="gate" type="goblin" compareMethod="greater" nr="2" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(present.area.as_deref(), Some("gate"));
+        assert_eq!(present.member_type.as_deref(), Some("goblin"));
+        assert_eq!(present.compare_method, Ordering::Greater);
+        assert_eq!(present.nr, 2);
+    }
+
+    #[test]
+    fn read_dialog_done() {
+        let Ok(TriggerConditionLogic::DialogDone(dialog)) =
+            read(r#"<DialogDone dialog="intro" interactIndex="1" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(dialog.dialog, "intro");
+        assert_eq!(dialog.interact_index, 1);
+        assert!(read("<DialogDone />").is_err());
+    }
+
+    #[test]
+    fn read_character_dead_and_alive() {
+        let Ok(TriggerConditionLogic::CharacterDead(dead)) = read(r#"<CharacterDead id="boss" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(dead.id, "boss");
+        assert!(dead.dead);
+        let Ok(TriggerConditionLogic::CharacterDead(alive)) =
+            read(r#"<CharacterAlive id="boss" />"#)
+        else {
+            panic!();
+        };
+        assert!(!alive.dead);
+        assert!(read("<CharacterDead />").is_err());
+    }
+
+    #[test]
+    fn read_health() {
+        let Ok(TriggerConditionLogic::Health(health)) =
+            read(r#"<Health id="boss" compareMethod="less" value="50" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(health.id, "boss");
+        assert_eq!(health.compare_method, Ordering::Less);
+        assert_eq!(health.percent, 50.);
+        assert!(read(r#"<Health id="boss" value="half" />"#).is_err());
+    }
+
+    #[test]
+    fn read_timer() {
+        let Ok(TriggerConditionLogic::Timer(timer)) =
+            read(r#"<Timer compareMethod="equal" time="2.5" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(timer.compare_method, Ordering::Equal);
+        assert_eq!(timer.time, 2.5);
+        assert!(read(r#"<Timer compareMethod="sooner" time="2.5" />"#).is_err());
+        assert!(read("<Timer />").is_err());
+    }
+
+    #[test]
+    fn read_players_present() {
+        let Ok(TriggerConditionLogic::PlayersPresent(players)) =
+            read(r#"<PlayersPresent area="exit" all="false" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(players.area, "exit");
+        assert!(!players.all);
+        assert!(read(r#"<PlayersPresent all="false" />"#).is_err());
+    }
+
+    #[test]
+    fn read_item_present() {
+        let Ok(TriggerConditionLogic::ItemPresent(item)) =
+            read(r#"<ItemPresent type="key" id="player" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(item.item_type, "key");
+        assert_eq!(item.holder.as_deref(), Some("player"));
+        let Ok(TriggerConditionLogic::ItemPresent(item)) = read(r#"<ItemPresent type="key" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(item.holder, None);
+    }
+
+    #[test]
+    fn read_magick_cast() {
+        let Ok(TriggerConditionLogic::MagickCast(magick)) =
+            read(r#"<MagickCast magick="Haste" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(magick.magick, "Haste");
+        assert!(read("<MagickCast />").is_err());
+    }
+
+    #[test]
+    fn read_scene_visited() {
+        let Ok(TriggerConditionLogic::SceneVisited(visited)) =
+            read(r#"<SceneVisited level="WizardCastle" scene="wc_s4" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(visited.level.as_deref(), Some("WizardCastle"));
+        assert_eq!(visited.scene, "wc_s4");
+        assert!(read(r#"<SceneVisited level="WizardCastle" />"#).is_err());
+    }
+
+    #[test]
+    fn read_unknown() {
+        let Ok(TriggerConditionLogic::Unknown(name)) = read(r#"<Dead id="boss" />"#) else {
+            panic!();
+        };
+        assert_eq!(name, "Dead");
+    }
+
+    #[test]
+    fn read_variable() {
+        use crate::script_triggers::variables::VariableValue;
+
+        let Ok(TriggerConditionLogic::Variable(variable)) =
+            read(r#"<Variable name="waves" compareMethod="greater" value="2" />"#)
+        else {
+            panic!();
+        };
+        assert_eq!(variable.name, "waves");
+        assert_eq!(variable.compare_method, Ordering::Greater);
+        assert_eq!(variable.value, VariableValue::Int(2));
+        assert!(read(r#"<Variable name="waves" value="many" />"#).is_err());
+        assert!(read(r#"<Variable value="2" />"#).is_err());
+    }
+}
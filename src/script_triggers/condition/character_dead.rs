@@ -0,0 +1,37 @@
+use crate::{character::Character, gameplay::damage::Health};
+use bevy::prelude::*;
+
+/// Whether the characters spawned with an ID are all dead, or any is alive. Characters that have
+/// been removed count as dead.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionCharacterDead {
+    pub id: String,
+    pub dead: bool,
+}
+
+pub(super) fn condition_met_character_dead(
+    condition: In<impl AsRef<TriggerConditionCharacterDead>>,
+    characters: Query<(&Name, &Health), With<Character>>,
+) -> Option<bool> {
+    let TriggerConditionCharacterDead { id, dead } = condition.as_ref();
+    let any_alive = characters
+        .iter()
+        .any(|(name, health)| name.eq_ignore_ascii_case(id) && health.alive());
+    Some(any_alive != *dead)
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+    dead: bool,
+) -> Result<TriggerConditionCharacterDead, ()> {
+    let mut id = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("id") {
+            id = Some(value);
+        }
+    }
+    Ok(TriggerConditionCharacterDead {
+        id: id.ok_or(())?,
+        dead,
+    })
+}
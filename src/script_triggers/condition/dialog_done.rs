@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-#[expect(dead_code)] // TODO: Start dialog action
+#[cfg_attr(not(test), expect(dead_code))] // TODO: Start dialog action
 #[derive(Clone, Debug)]
 pub(crate) struct TriggerConditionDialogDone {
     pub dialog: String,
@@ -0,0 +1,53 @@
+use crate::{character::Character, gameplay::damage::Health};
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// Compares the remaining health of the character with an ID, as a percentage of its maximum.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionHealth {
+    pub id: String,
+    pub compare_method: Ordering,
+    pub percent: f32,
+}
+
+pub(super) fn condition_met_health(
+    condition: In<impl AsRef<TriggerConditionHealth>>,
+    characters: Query<(&Name, &Health), With<Character>>,
+) -> Option<bool> {
+    let TriggerConditionHealth {
+        id,
+        compare_method,
+        percent,
+    } = condition.as_ref();
+    let Some((_name, health)) = characters
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(id))
+    else {
+        warn_once!("Health condition can't find character {id:?}");
+        return None;
+    };
+    let current = 100. * health.current() / health.limit();
+    Some(current.partial_cmp(percent) == Some(*compare_method))
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionHealth, ()> {
+    let mut id = None;
+    let mut compare_method = Ordering::Less;
+    let mut percent = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("id") {
+            id = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("comparemethod") {
+            compare_method = super::parse_compare_method(&value)?;
+        } else if name.local_name.eq_ignore_ascii_case("value") {
+            percent = Some(value.parse().map_err(|_| ())?);
+        }
+    }
+    Ok(TriggerConditionHealth {
+        id: id.ok_or(())?,
+        compare_method,
+        percent: percent.ok_or(())?,
+    })
+}
@@ -0,0 +1,59 @@
+use crate::{
+    character::Character,
+    item::{AttachedItem, ItemInstance},
+    magicka_assets::item::Item,
+};
+use bevy::prelude::*;
+
+/// Whether there is an item of a type, held by the character with an ID if one is given, or
+/// otherwise lying loose in the scene.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionItemPresent {
+    pub item_type: String,
+    pub holder: Option<String>,
+}
+
+pub(super) fn condition_met_item_present(
+    condition: In<impl AsRef<TriggerConditionItemPresent>>,
+    items: Query<(&ItemInstance, Has<AttachedItem>, &ChildOf)>,
+    loose_items: Query<&ItemInstance, Without<AttachedItem>>,
+    characters: Query<&Name, With<Character>>,
+    item_assets: Res<Assets<Item>>,
+) -> Option<bool> {
+    let TriggerConditionItemPresent { item_type, holder } = condition.as_ref();
+    let is_type = |instance: &ItemInstance| {
+        item_assets
+            .get(&instance.0)
+            .is_some_and(|item| item.item.name.eq_ignore_ascii_case(item_type))
+    };
+    Some(if let Some(holder) = holder {
+        // Held items are children of their character
+        items.iter().any(|(instance, attached, parent)| {
+            attached
+                && is_type(instance)
+                && characters
+                    .get(parent.parent())
+                    .is_ok_and(|name| name.eq_ignore_ascii_case(holder))
+        })
+    } else {
+        loose_items.iter().any(is_type)
+    })
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionItemPresent, ()> {
+    let mut item_type = None;
+    let mut holder = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("type") {
+            item_type = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("id") {
+            holder = Some(value);
+        }
+    }
+    Ok(TriggerConditionItemPresent {
+        item_type: item_type.ok_or(())?,
+        holder,
+    })
+}
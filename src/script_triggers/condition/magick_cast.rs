@@ -0,0 +1,39 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+
+/// Whether a Magick has been cast in the current scene.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionMagickCast {
+    pub magick: String,
+}
+
+/// The lowercase names of the Magicks cast in the current scene.
+#[derive(Resource, Default, Debug)]
+pub struct CastMagicks(pub HashSet<String>);
+
+impl CastMagicks {
+    pub fn record(&mut self, magick: &str) {
+        self.0.insert(magick.to_ascii_lowercase());
+    }
+}
+
+pub(super) fn condition_met_magick_cast(
+    condition: In<impl AsRef<TriggerConditionMagickCast>>,
+    cast_magicks: Res<CastMagicks>,
+) -> Option<bool> {
+    let TriggerConditionMagickCast { magick } = condition.as_ref();
+    Some(cast_magicks.0.contains(&magick.to_ascii_lowercase()))
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionMagickCast, ()> {
+    let mut magick = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("magick") {
+            magick = Some(value);
+        }
+    }
+    Ok(TriggerConditionMagickCast {
+        magick: magick.ok_or(())?,
+    })
+}
@@ -0,0 +1,54 @@
+use crate::{PlayerControlled, magicka_level_model::TriggerArea};
+use bevy::prelude::*;
+
+/// Whether all players are in an area, or with `all` off, whether any are.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionPlayersPresent {
+    pub area: String,
+    pub all: bool,
+}
+
+pub(super) fn condition_met_players_present(
+    condition: In<impl AsRef<TriggerConditionPlayersPresent>>,
+    areas: Query<(&Name, &TriggerArea)>,
+    players: Query<Entity, With<PlayerControlled>>,
+) -> Option<bool> {
+    let TriggerConditionPlayersPresent {
+        area: area_name,
+        all,
+    } = condition.as_ref();
+    let Some((_name, area)) = areas
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(area_name))
+    else {
+        warn!("Players present condition can't find area {area_name:?}");
+        return None;
+    };
+    if players.is_empty() {
+        return Some(false);
+    }
+    let mut present = players.iter().map(|player| area.contains(player));
+    Some(if *all {
+        present.all(|p| p)
+    } else {
+        present.any(|p| p)
+    })
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionPlayersPresent, ()> {
+    let mut area = None;
+    let mut all = true;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("area") {
+            area = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("all") {
+            all = value.parse().map_err(|_| ())?;
+        }
+    }
+    Ok(TriggerConditionPlayersPresent {
+        area: area.ok_or(())?,
+        all,
+    })
+}
@@ -55,7 +55,9 @@ pub(super) fn condition_met_present(
     Some(current_count.cmp(&compare_count) == *compare_method)
 }
 
-pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> TriggerConditionPresent {
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionPresent, ()> {
     let mut area = None;
     let mut member_type = None;
     let mut include_invisible = true;
@@ -69,24 +71,16 @@ pub(crate) fn from_xml(attributes: Vec<xml::attribute::OwnedAttribute>) -> Trigg
         } else if name.local_name.eq_ignore_ascii_case("includeinvisible") {
             include_invisible = value.parse().unwrap();
         } else if name.local_name.eq_ignore_ascii_case("comparemethod") {
-            if value.eq_ignore_ascii_case("equal") {
-                compare_method = Ordering::Equal;
-            } else if value.eq_ignore_ascii_case("less") {
-                compare_method = Ordering::Less;
-            } else if value.eq_ignore_ascii_case("greater") {
-                compare_method = Ordering::Greater;
-            } else {
-                todo!("invalid trigger condition comparemethod error")
-            }
+            compare_method = super::parse_compare_method(&value)?;
         } else if name.local_name.eq_ignore_ascii_case("nr") {
             nr = value.parse().unwrap();
         }
     }
-    TriggerConditionPresent {
+    Ok(TriggerConditionPresent {
         area,
         member_type,
         include_invisible,
         compare_method,
         nr,
-    }
+    })
 }
@@ -0,0 +1,46 @@
+use crate::scene::{Scene, VisitedScenes};
+use bevy::prelude::*;
+
+/// Whether a scene was left before, in the current level unless another is given.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionSceneVisited {
+    pub level: Option<String>,
+    pub scene: String,
+}
+
+pub(super) fn condition_met_scene_visited(
+    condition: In<impl AsRef<TriggerConditionSceneVisited>>,
+    scenes: Query<&Scene>,
+    visited: Res<VisitedScenes>,
+) -> Option<bool> {
+    let TriggerConditionSceneVisited { level, scene } = condition.as_ref();
+    let level = match level {
+        Some(level) => level.as_str(),
+        None => match scenes.single() {
+            Ok(current) => current.level.as_str(),
+            Err(_) => {
+                warn_once!("Scene visited condition has no single running scene");
+                return None;
+            }
+        },
+    };
+    Some(visited.contains(level, scene))
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionSceneVisited, ()> {
+    let mut level = None;
+    let mut scene = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("level") {
+            level = Some(value);
+        } else if name.local_name.eq_ignore_ascii_case("scene") {
+            scene = Some(value);
+        }
+    }
+    Ok(TriggerConditionSceneVisited {
+        level,
+        scene: scene.ok_or(())?,
+    })
+}
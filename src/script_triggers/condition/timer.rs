@@ -0,0 +1,49 @@
+use crate::scene::SceneStarted;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// Compares how many seconds the current scene has been running.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionTimer {
+    pub compare_method: Ordering,
+    pub time: f32,
+}
+
+pub(super) fn condition_met_timer(
+    condition: In<impl AsRef<TriggerConditionTimer>>,
+    scenes: Query<&SceneStarted>,
+    time: Res<Time>,
+) -> Option<bool> {
+    let TriggerConditionTimer {
+        compare_method,
+        time: compare_time,
+    } = condition.as_ref();
+    let Ok(SceneStarted(started)) = scenes.single() else {
+        warn_once!("Timer condition has no single running scene");
+        return None;
+    };
+    let elapsed = time.elapsed().saturating_sub(*started).as_secs_f32();
+    Some(match compare_method {
+        // Frames never land exactly on the time, so it's met from then on
+        Ordering::Equal => elapsed >= *compare_time,
+        _ => elapsed.partial_cmp(compare_time) == Some(*compare_method),
+    })
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionTimer, ()> {
+    let mut compare_method = Ordering::Greater;
+    let mut time = None;
+    for xml::attribute::OwnedAttribute { name, value } in attributes {
+        if name.local_name.eq_ignore_ascii_case("comparemethod") {
+            compare_method = super::parse_compare_method(&value)?;
+        } else if name.local_name.eq_ignore_ascii_case("time") {
+            time = Some(value.parse().map_err(|_| ())?);
+        }
+    }
+    Ok(TriggerConditionTimer {
+        compare_method,
+        time: time.ok_or(())?,
+    })
+}
@@ -0,0 +1,46 @@
+use crate::script_triggers::variables::{SceneVariables, VariableValue};
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// Compares a scene variable with a value. Variables that haven't been set are 0.
+#[derive(Clone, Debug)]
+pub(crate) struct TriggerConditionVariable {
+    pub name: String,
+    pub compare_method: Ordering,
+    pub value: VariableValue,
+}
+
+pub(super) fn condition_met_variable(
+    condition: In<impl AsRef<TriggerConditionVariable>>,
+    variables: Res<SceneVariables>,
+) -> Option<bool> {
+    let TriggerConditionVariable {
+        name,
+        compare_method,
+        value,
+    } = condition.as_ref();
+    let current = variables.get(name).unwrap_or(VariableValue::DEFAULT);
+    Some(current.compare(*value) == Some(*compare_method))
+}
+
+pub(crate) fn from_xml(
+    attributes: Vec<xml::attribute::OwnedAttribute>,
+) -> Result<TriggerConditionVariable, ()> {
+    let mut variable_name = None;
+    let mut compare_method = Ordering::Equal;
+    let mut value = None;
+    for xml::attribute::OwnedAttribute { name, value: text } in attributes {
+        if name.local_name.eq_ignore_ascii_case("name") {
+            variable_name = Some(text);
+        } else if name.local_name.eq_ignore_ascii_case("comparemethod") {
+            compare_method = super::parse_compare_method(&text)?;
+        } else if name.local_name.eq_ignore_ascii_case("value") {
+            value = Some(VariableValue::parse(&text).ok_or(())?);
+        }
+    }
+    Ok(TriggerConditionVariable {
+        name: variable_name.ok_or(())?,
+        compare_method,
+        value: value.ok_or(())?,
+    })
+}
@@ -0,0 +1,246 @@
+//! Named values scene scripts keep track of progress with, like puzzle states and wave counters.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use std::{cmp::Ordering, fmt};
+
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+pub enum VariableValue {
+    Int(i32),
+    Bool(bool),
+    Float(f32),
+}
+
+impl VariableValue {
+    /// Variables that haven't been set read as this.
+    pub const DEFAULT: Self = Self::Int(0);
+
+    /// Parses `true`/`false`, an integer, or else a float.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("true") {
+            Some(Self::Bool(true))
+        } else if value.eq_ignore_ascii_case("false") {
+            Some(Self::Bool(false))
+        } else if let Ok(int) = value.parse() {
+            Some(Self::Int(int))
+        } else {
+            value.parse().ok().map(Self::Float)
+        }
+    }
+
+    fn as_f32(self) -> f32 {
+        match self {
+            Self::Int(int) => int as f32,
+            Self::Bool(bool) => f32::from(u8::from(bool)),
+            Self::Float(float) => float,
+        }
+    }
+
+    /// Compares numerically, with `true` as 1 and `false` as 0.
+    pub fn compare(self, other: Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => Some(a.cmp(&b)),
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f32().partial_cmp(&b.as_f32()),
+        }
+    }
+
+    /// Adds numbers, bools can't be added to.
+    pub fn add(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => Some(Self::Int(a.saturating_add(b))),
+            (Self::Bool(_), _) | (_, Self::Bool(_)) => None,
+            (a, b) => Some(Self::Float(a.as_f32() + b.as_f32())),
+        }
+    }
+}
+
+impl fmt::Display for VariableValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(int) => int.fmt(f),
+            Self::Bool(bool) => bool.fmt(f),
+            Self::Float(float) => float.fmt(f),
+        }
+    }
+}
+
+/// How long a variable lasts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum VariableScope {
+    /// Until going to another level.
+    Level,
+    /// For the rest of the game.
+    Global,
+}
+
+/// The variables set by scene trigger actions, by lowercase name. A level variable hides a global
+/// one with the same name.
+#[derive(Resource, Default, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct SceneVariables {
+    /// The lowercase name of the level the level variables belong to.
+    current_level: Option<String>,
+    level: HashMap<String, VariableValue>,
+    global: HashMap<String, VariableValue>,
+}
+
+impl SceneVariables {
+    pub fn get(&self, name: &str) -> Option<VariableValue> {
+        let name = name.to_ascii_lowercase();
+        self.level
+            .get(&name)
+            .or_else(|| self.global.get(&name))
+            .copied()
+    }
+
+    /// Which scope the variable is set in, if any.
+    pub fn scope(&self, name: &str) -> Option<VariableScope> {
+        let name = name.to_ascii_lowercase();
+        if self.level.contains_key(&name) {
+            Some(VariableScope::Level)
+        } else if self.global.contains_key(&name) {
+            Some(VariableScope::Global)
+        } else {
+            None
+        }
+    }
+
+    /// Sets a variable, in the scope it's already set in if `scope` is `None`, or else the level.
+    pub fn set(&mut self, name: &str, value: VariableValue, scope: Option<VariableScope>) {
+        let scope = scope
+            .or_else(|| self.scope(name))
+            .unwrap_or(VariableScope::Level);
+        let name = name.to_ascii_lowercase();
+        match scope {
+            VariableScope::Level => {
+                self.level.insert(name, value);
+            }
+            VariableScope::Global => {
+                // So the global value isn't hidden
+                self.level.remove(&name);
+                self.global.insert(name, value);
+            }
+        }
+    }
+
+    /// Every variable and its scope, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, VariableValue, VariableScope)> {
+        let mut variables: Vec<_> = self
+            .level
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value, VariableScope::Level))
+            .chain(
+                self.global
+                    .iter()
+                    .filter(|(name, _)| !self.level.contains_key(*name))
+                    .map(|(name, value)| (name.as_str(), *value, VariableScope::Global)),
+            )
+            .collect();
+        variables.sort_by_key(|(name, _, _)| *name);
+        variables.into_iter()
+    }
+
+    /// Forgets the level variables when going to a different level.
+    pub(crate) fn enter_level(&mut self, level: &str) {
+        let level = level.to_ascii_lowercase();
+        if self.current_level.as_ref() != Some(&level) {
+            self.level.clear();
+            self.current_level = Some(level);
+        }
+    }
+}
+
+/// Reads a `scope` attribute into `scope`, returning whether it was one.
+pub(crate) fn read_scope_attribute(
+    scope: &mut Option<VariableScope>,
+    attribute: &xml::attribute::OwnedAttribute,
+) -> Result<bool, ()> {
+    let xml::attribute::OwnedAttribute { name, value } = attribute;
+    if name.local_name.eq_ignore_ascii_case("scope") {
+        *scope = Some(if value.eq_ignore_ascii_case("level") {
+            VariableScope::Level
+        } else if value.eq_ignore_ascii_case("global") {
+            VariableScope::Global
+        } else {
+            return Err(());
+        });
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SceneVariables, VariableScope, VariableValue};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn parse() {
+        assert_eq!(VariableValue::parse(" 3 "), Some(VariableValue::Int(3)));
+        assert_eq!(
+            VariableValue::parse("-1.5"),
+            Some(VariableValue::Float(-1.5))
+        );
+        assert_eq!(
+            VariableValue::parse("TRUE"),
+            Some(VariableValue::Bool(true))
+        );
+        assert_eq!(VariableValue::parse("three"), None);
+    }
+
+    #[test]
+    fn compare_and_add_ints_and_floats() {
+        use VariableValue::*;
+        assert_eq!(Int(2).compare(Float(2.)), Some(Ordering::Equal));
+        assert_eq!(Float(1.5).compare(Int(2)), Some(Ordering::Less));
+        assert_eq!(Bool(true).compare(Int(0)), Some(Ordering::Greater));
+        assert_eq!(Int(2).add(Int(3)), Some(Int(5)));
+        assert_eq!(Int(i32::MAX).add(Int(1)), Some(Int(i32::MAX)));
+        assert_eq!(Int(2).add(Float(0.5)), Some(Float(2.5)));
+        assert_eq!(Bool(true).add(Int(1)), None);
+        assert_eq!(Int(1).add(Bool(true)), None);
+    }
+
+    #[test]
+    fn level_variables_hide_globals() {
+        let mut variables = SceneVariables::default();
+        variables.set("Gates", VariableValue::Int(1), Some(VariableScope::Global));
+        variables.set("gates", VariableValue::Int(2), Some(VariableScope::Level));
+        assert_eq!(variables.get("GATES"), Some(VariableValue::Int(2)));
+        assert_eq!(variables.scope("gates"), Some(VariableScope::Level));
+        assert_eq!(variables.iter().count(), 1);
+
+        // Setting the global again shows it
+        variables.set("gates", VariableValue::Int(3), Some(VariableScope::Global));
+        assert_eq!(variables.get("gates"), Some(VariableValue::Int(3)));
+        assert_eq!(variables.scope("gates"), Some(VariableScope::Global));
+    }
+
+    #[test]
+    fn set_keeps_the_current_scope() {
+        let mut variables = SceneVariables::default();
+        variables.set("waves", VariableValue::Int(1), None);
+        assert_eq!(variables.scope("waves"), Some(VariableScope::Level));
+        variables.set("bosses", VariableValue::Int(1), Some(VariableScope::Global));
+        variables.set("bosses", VariableValue::Int(2), None);
+        assert_eq!(variables.scope("bosses"), Some(VariableScope::Global));
+    }
+
+    #[test]
+    fn enter_level_clears_level_variables() {
+        let mut variables = SceneVariables::default();
+        variables.enter_level("Havindr");
+        variables.set("waves", VariableValue::Int(1), Some(VariableScope::Level));
+        variables.set("bosses", VariableValue::Int(1), Some(VariableScope::Global));
+
+        // Another scene of the same level
+        variables.enter_level("havindr");
+        assert_eq!(variables.get("waves"), Some(VariableValue::Int(1)));
+
+        variables.enter_level("Mountaindale");
+        assert_eq!(variables.get("waves"), None);
+        assert_eq!(variables.get("bosses"), Some(VariableValue::Int(1)));
+    }
+}
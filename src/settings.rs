@@ -0,0 +1,251 @@
+//! User settings, persisted as `settings.toml` in the platform config directory.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const FILE_NAME: &str = "settings.toml";
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    /// The Content subdirectory of the Magicka install. Detected when unset.
+    pub content_dir: Option<PathBuf>,
+    pub graphics: GraphicsSettings,
+    pub audio: AudioSettings,
+    pub keybinds: Keybinds,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub shadows: bool,
+    pub shadow_quality: ShadowQuality,
+    /// Merge the smallest parts of level geometry, for fewer draws but less precise culling.
+    pub batch_level_meshes: bool,
+    /// How many decals can be on the level at once, the oldest are removed first.
+    pub max_decals: usize,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            shadows: true,
+            shadow_quality: ShadowQuality::default(),
+            batch_level_meshes: false,
+            max_decals: 128,
+        }
+    }
+}
+
+/// Scales the shadow map sizes levels ask for, and how many cascades directional lights have.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ShadowQuality {
+    Low,
+    /// As in Magicka.
+    #[default]
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    pub fn map_size_scale(self) -> f32 {
+        match self {
+            Self::Low => 0.5,
+            Self::Medium => 1.,
+            Self::High => 2.,
+        }
+    }
+
+    pub fn cascades(self) -> usize {
+        match self {
+            Self::Low => 1,
+            Self::Medium => 2,
+            Self::High => 4,
+        }
+    }
+}
+
+/// Volumes from 0 to 1. Stored ahead of sound support, so they don't do anything yet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            music_volume: 1.,
+            effects_volume: 1.,
+        }
+    }
+}
+
+/// Keyboard bindings for player 1.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Keybinds {
+    pub move_up: KeyCode,
+    pub move_down: KeyCode,
+    pub move_left: KeyCode,
+    pub move_right: KeyCode,
+    pub water: KeyCode,
+    pub life: KeyCode,
+    pub shield: KeyCode,
+    pub cold: KeyCode,
+    pub lightning: KeyCode,
+    pub arcane: KeyCode,
+    pub earth: KeyCode,
+    pub fire: KeyCode,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            move_up: KeyCode::KeyW,
+            move_down: KeyCode::KeyS,
+            move_left: KeyCode::KeyA,
+            move_right: KeyCode::KeyD,
+            water: KeyCode::KeyQ,
+            life: KeyCode::KeyW,
+            shield: KeyCode::KeyE,
+            cold: KeyCode::KeyR,
+            lightning: KeyCode::KeyA,
+            arcane: KeyCode::KeyS,
+            earth: KeyCode::KeyD,
+            fire: KeyCode::KeyF,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Can't determine the config directory for this platform")]
+    NoConfigDir,
+    #[error("Failed to access settings file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse settings file: {0}")]
+    Deserialize(#[from] toml::de::Error),
+    #[error("Failed to serialize settings: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+fn settings_path() -> Result<PathBuf, SettingsError> {
+    let dirs = directories::ProjectDirs::from("", "", "alkemi").ok_or(SettingsError::NoConfigDir)?;
+    Ok(dirs.config_dir().join(FILE_NAME))
+}
+
+impl Settings {
+    /// Reads the settings file, falling back to defaults when it doesn't exist or can't be read.
+    pub fn load() -> Self {
+        match settings_path().and_then(|path| Self::read(&path)) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => Self::default(),
+            Err(e) => {
+                warn!("Using default settings: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Option<Self>, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(toml::from_str(&text)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let path = settings_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)?;
+        info!("Saved settings to {path:?}");
+        Ok(())
+    }
+
+    /// The primary window configured from the graphics settings.
+    pub fn window(&self) -> Window {
+        Window {
+            mode: if self.graphics.fullscreen {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            } else {
+                WindowMode::Windowed
+            },
+            present_mode: if self.graphics.vsync {
+                PresentMode::AutoVsync
+            } else {
+                PresentMode::AutoNoVsync
+            },
+            ..default()
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(PostUpdate, apply_shadow_setting);
+}
+
+/// Lights are spawned with shadows wherever they're translated, so turn them off afterwards.
+fn apply_shadow_setting(
+    settings: Res<Settings>,
+    point_lights: Query<&mut PointLight, Added<PointLight>>,
+    spot_lights: Query<&mut SpotLight, Added<SpotLight>>,
+    directional_lights: Query<&mut DirectionalLight, Added<DirectionalLight>>,
+) {
+    if settings.graphics.shadows {
+        return;
+    }
+    for mut light in point_lights {
+        light.shadow_maps_enabled = false;
+    }
+    for mut light in spot_lights {
+        light.shadow_maps_enabled = false;
+    }
+    for mut light in directional_lights {
+        light.shadow_maps_enabled = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn partial_settings_use_defaults() {
+        let settings: Settings = toml::from_str(
+            r#"
+            content_dir = "/games/Magicka/Content"
+
+            [keybinds]
+            fire = "KeyG"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.content_dir.as_deref(),
+            Some(Path::new("/games/Magicka/Content"))
+        );
+        assert_eq!(settings.keybinds.fire, KeyCode::KeyG);
+        assert_eq!(settings.keybinds.water, KeyCode::KeyQ);
+        assert!(settings.graphics.vsync);
+    }
+}
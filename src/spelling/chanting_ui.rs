@@ -47,16 +47,7 @@ fn spawn_bar(
         return;
     };
 
-    let hud_path = {
-        crate::magicka_assets::content_root()
-            .join_checked("UI/HUD/hud.xnb")
-            .unwrap()
-    };
-    let hud_path = hud_path.as_ref() as &std::ffi::OsStr;
-    let hud_image = assets
-        .load_builder()
-        .override_unapproved()
-        .load(bevy::asset::AssetPath::from_path(hud_path.as_ref()));
+    let hud_image = assets.load("magicka://UI/HUD/hud.xnb");
     let hud_atlas = assets.add(hud_atlas_layout());
 
     commands
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_enhanced_input::prelude::*;
 
-use crate::spelling::element::BaseElement;
+use crate::{settings::Keybinds, spelling::element::BaseElement};
 
 #[derive(Component, TypePath)]
 pub struct SpellingInput;
@@ -112,7 +112,7 @@ pub struct BoostShield;
 // M1, M2: Left click / right trigger
 //BreakFree
 
-pub fn bindings_m1() -> impl Bundle {
+pub fn bindings_m1(keybinds: &Keybinds) -> impl Bundle + use<> {
     actions!(SpellingInput[
         (
             Action::<CastForward>::new(),
@@ -166,35 +166,35 @@ pub fn bindings_m1() -> impl Bundle {
         ),
         (
             Action::<ConjureElement<WaterElement>>::new(),
-            bindings![KeyCode::KeyQ],
+            bindings![keybinds.water],
         ),
         (
             Action::<ConjureElement<LifeElement>>::new(),
-            bindings![KeyCode::KeyW],
+            bindings![keybinds.life],
         ),
         (
             Action::<ConjureElement<ShieldElement>>::new(),
-            bindings![KeyCode::KeyE],
+            bindings![keybinds.shield],
         ),
         (
             Action::<ConjureElement<ColdElement>>::new(),
-            bindings![KeyCode::KeyR],
+            bindings![keybinds.cold],
         ),
         (
             Action::<ConjureElement<LightningElement>>::new(),
-            bindings![KeyCode::KeyA],
+            bindings![keybinds.lightning],
         ),
         (
             Action::<ConjureElement<ArcaneElement>>::new(),
-            bindings![KeyCode::KeyS],
+            bindings![keybinds.arcane],
         ),
         (
             Action::<ConjureElement<EarthElement>>::new(),
-            bindings![KeyCode::KeyD],
+            bindings![keybinds.earth],
         ),
         (
             Action::<ConjureElement<FireElement>>::new(),
-            bindings![KeyCode::KeyF],
+            bindings![keybinds.fire],
         ),
     ])
 }
@@ -6,8 +6,8 @@ mod color;
 pub(crate) mod element;
 mod input;
 mod spell_resolve;
-mod spells;
-mod status;
+pub(crate) mod spells;
+pub(crate) mod status;
 
 pub use input::bindings_m1;
 
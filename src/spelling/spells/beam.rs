@@ -8,7 +8,8 @@ use avian3d::prelude::{LayerMask, PhysicsLayer as _, SpatialQuery, SpatialQueryF
 use bevy::prelude::*;
 
 use crate::{
-    magicka_level_model::Layers,
+    gameplay::decal::{SpawnDecal, impact_decal_kind},
+    magicka_level_model::{Layers, collision::LevelCollider},
     spelling::{
         element::{Element, Magnitudes},
         spells,
@@ -37,6 +38,7 @@ pub fn plugin(app: &mut App) {
             .chain()
             .after(collide_beams),
     );
+    app.add_systems(FixedUpdate, mark_beam_impacts.after(collide_beams));
     app.add_observer(release_channeling_beam);
     app.add_observer(stop_beam);
     app.add_plugins(vfx::plugin);
@@ -129,6 +131,8 @@ struct BeamState {
     time: f32,
     colliding: Option<BeamCollision>,
     stopping_length_removed: Option<f32>,
+    /// Where the beam last left a decal on the level.
+    last_decal: Option<Vec3>,
 }
 
 #[derive(Debug, Reflect)]
@@ -152,6 +156,7 @@ impl Default for BeamState {
             time: 0.,
             colliding: None,
             stopping_length_removed: None,
+            last_decal: None,
         }
     }
 }
@@ -216,7 +221,7 @@ fn collide_beams(
         let origin = beam_trans.translation() + min_distance * direction;
         let max_distance = modified_length - min_distance;
         let filter = SpatialQueryFilter {
-            mask: LayerMask::ALL & !(Layers::Trigger.to_bits()),
+            mask: LayerMask::ALL & !(Layers::Trigger.to_bits() | Layers::Liquid.to_bits()),
             excluded_entities: beam.ignore_entity.iter().copied().collect(),
         };
         let Some(hit) = spatial_query.cast_ray(origin, direction, max_distance, false, &filter)
@@ -250,6 +255,48 @@ fn shrink_colliding_beams(beams: Query<(&mut Transform, &BeamState), Changed<Bea
     }
 }
 
+/// How far a beam's impact moves along the level before it leaves another decal.
+const DECAL_SPACING: f32 = 1.;
+
+const DECAL_SIZE: f32 = 1.5;
+
+/// Leaves decals where beams hit the level, like scorch marks from fire.
+fn mark_beam_impacts(
+    beams: Query<(&mut BeamState, &Beam)>,
+    level_colliders: Query<(), With<LevelCollider>>,
+    mut commands: Commands,
+) {
+    for (mut state, beam) in beams {
+        let Some(kind) = impact_decal_kind(&beam.elements) else {
+            continue;
+        };
+        let Some(collision) = &state.colliding else {
+            continue;
+        };
+        if !level_colliders.contains(collision.entity) {
+            continue;
+        }
+        let point = collision.point();
+        if state
+            .last_decal
+            .is_some_and(|last| last.distance(point) < DECAL_SPACING)
+        {
+            continue;
+        }
+        let Ok(normal) = Dir3::new(collision.normal) else {
+            continue;
+        };
+        commands.trigger(SpawnDecal {
+            kind,
+            index: 0,
+            position: point,
+            normal,
+            size: DECAL_SIZE,
+        });
+        state.last_decal = Some(point);
+    }
+}
+
 /// Stop emitting a beam. Detaches it from any parent & later despawns it.
 #[derive(EntityEvent)]
 pub struct Stop(pub Entity);
@@ -374,7 +374,8 @@ mod vfx {
 
         let rrt_iters = 64;
         let extend_increment = distance / (rrt_iters as f32) * 20.;
-        let ray_filter = SpatialQueryFilter::from_mask(!Layers::Trigger.to_bits())
+        let ray_filter =
+            SpatialQueryFilter::from_mask(!(Layers::Trigger.to_bits() | Layers::Liquid.to_bits()))
             .with_excluded_entities(event.source);
 
         let mut nodes = vec![RrtNode {
@@ -16,7 +16,7 @@ pub fn plugin(app: &mut App) {
     );
 }
 
-const ARC_ANGLE: f32 = std::f32::consts::TAU / 20.;
+pub(crate) const ARC_ANGLE: f32 = std::f32::consts::TAU / 20.;
 
 /// The height of the cylinder segment hitbox
 const HEIGHT: f32 = 10.;
@@ -33,14 +33,16 @@ pub fn spray_spell(caster: Entity, elements: Magnitudes) -> impl Bundle {
     (SpraySpell {
         lifetime: Timer::new(CHANNEL_MAX, TimerMode::Once),
         full_range: range(&elements),
+        elements,
     },)
 }
 
 #[derive(Component, Debug, Reflect)]
 #[require(Transform)]
-struct SpraySpell {
+pub(crate) struct SpraySpell {
     pub lifetime: Timer,
     pub full_range: f32,
+    pub elements: Magnitudes,
 }
 
 fn range(elements: &Magnitudes) -> f32 {
@@ -52,7 +54,7 @@ fn range(elements: &Magnitudes) -> f32 {
     RANGE_BASE + RANGE_ELEM * (spray_magnitude as f32)
 }
 
-fn current_range(spell: &SpraySpell) -> f32 {
+pub(crate) fn current_range(spell: &SpraySpell) -> f32 {
     let t = (spell.lifetime.elapsed_secs() / EXTEND_TIME).min(1.);
     t * spell.full_range
 }